@@ -17,6 +17,9 @@ let pollInterval = null;
 let logViewMode = 'logs'; // 'logs' or 'frames'
 let logsCursor = -1;
 let framesCursor = -1;
+let frameSource = ''; // '' = live traffic, otherwise an offline session id
+let logScope = 'session'; // 'session' (this tab only) or 'all'
+let liveStream = null; // EventSource on /api/stream while running
 
 // --- Initialization ---
 document.addEventListener('DOMContentLoaded', () => {
@@ -264,6 +267,7 @@ async function connectMaster() {
             } else {
                 pollInterval = setInterval(fetchDataPoints, 1000); // 1s for Outstation
             }
+            openLiveStream();
         } else { addLog("Error", `Connection failed: ${res.error}`); }
     } catch (e) { addLog("Error", `Connection error: ${e.message}`); }
 }
@@ -274,6 +278,7 @@ async function disconnectMaster() {
         addLog("System", "Disconnected.");
         isRunning = false;
         clearInterval(pollInterval);
+        closeLiveStream();
         document.getElementById('connStatus').innerText = "DISCONNECTED";
         document.getElementById('connStatus').className = "status-disconnected";
         if (currentConfig && typeof renderConfigTable === 'function') renderConfigTable(currentConfig);
@@ -322,6 +327,13 @@ async function fetchDataPoints() {
             document.getElementById('statErr').innerText = data.stats.errors || 0;
         }
 
+        if (data.connection) {
+            // Real channel state once running: CONNECTED, CONNECTING, WAITING, ...
+            const status = document.getElementById('connStatus');
+            status.innerText = data.connection.toUpperCase();
+            status.className = data.connection === 'connected' ? 'status-connected' : 'status-disconnected';
+        }
+
         if (typeof renderLiveTable === 'function') renderLiveTable(data.points);
 
         // Incremental Log/Frame Update
@@ -356,7 +368,10 @@ function toggleLogView() {
 // Fetch Logs with Cursor (Newest Logic)
 function fetchLogs() {
     if (logViewMode !== 'logs') return;
-    fetch('/api/logs').then(r => r.json()).then(data => {
+    const params = new URLSearchParams();
+    if (logScope === 'all') params.set('scope', 'all');
+    if (logsCursor >= 0) params.set('since', logsCursor);
+    fetch(`/api/logs?${params}`).then(r => r.json()).then(data => {
         const container = document.getElementById('protocolLog');
         const newLogs = data.logs.filter(l => l.id > logsCursor);
         if (newLogs.length === 0) return;
@@ -370,7 +385,8 @@ function fetchLogs() {
             if (log.direction === 'Error') color = '#f44336';
             if (log.direction === 'System') color = '#ffeb3b';
             const time = new Date(log.timestamp).toLocaleTimeString();
-            div.innerHTML = `<span class="log-time" style="color: grey;">[${time}]</span> <span class="log-msg"><strong style="color: ${color};">[${log.direction}]</strong> ${log.message}</span>`;
+            const tag = logScope === 'all' && log.session_id ? `<span style="color: grey;">{${log.session_id}}</span> ` : '';
+            div.innerHTML = `<span class="log-time" style="color: grey;">[${time}]</span> ${tag}<span class="log-msg"><strong style="color: ${color};">[${log.direction}]</strong> ${log.message}</span>`;
             container.prepend(div);
         });
         while (container.children.length > 500) container.removeChild(container.lastChild);
@@ -380,7 +396,12 @@ function fetchLogs() {
 // Fetch Frames with Cursor (Newest Logic)
 function fetchFrames() {
     if (logViewMode !== 'frames') return;
-    fetch('/api/frames').then(r => r.json()).then(data => {
+    const params = new URLSearchParams();
+    if (frameSource) params.set('session', frameSource);
+    else if (logScope === 'all') params.set('scope', 'all');
+    if (framesCursor >= 0) params.set('since', framesCursor);
+    else params.set('limit', 200); // First load: newest frames only
+    fetch(`/api/frames?${params}`).then(r => r.json()).then(data => {
         const container = document.getElementById('protocolFrames');
         if (container.querySelector('div[style*="text-align: center"]')) container.innerHTML = '';
         const frames = data.frames || [];
@@ -427,6 +448,100 @@ function fetchFrames() {
     }).catch(console.error);
 }
 
+// --- Offline Capture Import ---
+function importPcap(input) {
+    const file = input.files[0];
+    if (!file) return;
+    input.value = '';
+    fetch(`/api/frames/import?name=${encodeURIComponent(file.name)}`, { method: 'POST', body: file })
+        .then(r => r.json())
+        .then(data => {
+            if (!data.success) {
+                addLog('Error', `Import failed: ${data.message}`);
+                return;
+            }
+            const select = document.getElementById('frameSource');
+            const option = document.createElement('option');
+            option.value = data.session.id;
+            option.textContent = `${data.session.name} (${data.session.frame_count})`;
+            select.appendChild(option);
+            select.style.display = 'inline-block';
+            select.value = data.session.id;
+            if (logViewMode !== 'frames') toggleLogView();
+            selectFrameSource(data.session.id);
+        })
+        .catch(console.error);
+}
+
+// Disk recording of all sessions' logs and frames (settings via /api/recording/start)
+function toggleRecording() {
+    const btn = document.getElementById('recordToggle');
+    fetch('/api/recording').then(r => r.json()).then(status => {
+        const action = status.active ? 'stop' : 'start';
+        return fetch(`/api/recording/${action}`, { method: 'POST' }).then(r => r.json()).then(res => {
+            if (!res.success) {
+                addLog('Error', `Recording ${action} failed: ${res.error}`);
+                return;
+            }
+            const recording = action === 'start';
+            btn.className = recording ? 'btn btn-sm btn-danger' : 'btn btn-sm btn-secondary';
+            addLog('System', recording ? `Recording to ${status.config.directory}/` : 'Recording stopped');
+        });
+    }).catch(console.error);
+}
+
+// Zero this session's link/transport/application counters
+function resetStats() {
+    fetch('/api/stats/reset', { method: 'POST' })
+        .then(r => r.json())
+        .then(res => {
+            if (res.success) addLog('System', 'Statistics reset');
+            fetchDataPoints();
+        })
+        .catch(console.error);
+}
+
+function selectFrameSource(source) {
+    frameSource = source;
+    framesCursor = -1;
+    document.getElementById('protocolFrames').innerHTML = '';
+    fetchFrames();
+}
+
+function toggleLogScope() {
+    logScope = logScope === 'all' ? 'session' : 'all';
+    const btn = document.getElementById('logScopeToggle');
+    btn.innerHTML = logScope === 'all' ? '🌐 ALL' : '👤 MINE';
+    logsCursor = -1;
+    framesCursor = -1;
+    document.getElementById('protocolLog').innerHTML = '';
+    document.getElementById('protocolFrames').innerHTML = '';
+    if (liveStream) { closeLiveStream(); openLiveStream(); }
+    fetchLogs();
+    fetchFrames();
+}
+
+// Live push: new logs/frames refresh the views immediately instead of on the next poll.
+// The stream only signals; the cursors above still fetch the (decoded) entries.
+function openLiveStream() {
+    if (!window.EventSource || liveStream) return;
+    const params = new URLSearchParams({ types: 'log,frame' });
+    if (logScope !== 'all') params.set('session', sessionId);
+    liveStream = new EventSource(`/api/stream?${params}`);
+    let pending = null;
+    const refresh = () => {
+        if (pending) return;
+        pending = setTimeout(() => { pending = null; fetchLogs(); fetchFrames(); }, 200);
+    };
+    liveStream.addEventListener('log', refresh);
+    liveStream.addEventListener('frame', refresh);
+    liveStream.addEventListener('lagged', refresh);
+}
+
+function closeLiveStream() {
+    if (liveStream) { liveStream.close(); liveStream = null; }
+}
+
 function clearLogs() {
     if (logViewMode === 'logs') document.getElementById('protocolLog').innerHTML = '';
     else document.getElementById('protocolFrames').innerHTML = '';
@@ -465,6 +580,11 @@ async function loadDefaultConfig() {
 function handleFileLoad(event) {
     const file = event.target.files[0];
     if (!file) return;
+    event.target.value = '';
+    if (!file.name.toLowerCase().endsWith('.json')) {
+        importPointList(file);
+        return;
+    }
     const reader = new FileReader();
     reader.onload = (e) => {
         try {
@@ -476,6 +596,22 @@ function handleFileLoad(event) {
     reader.readAsText(file);
 }
 
+// CSV / XLSX point lists are converted by the backend
+async function importPointList(file) {
+    try {
+        const resp = await fetch(`/api/config/import?name=${encodeURIComponent(file.name)}`, { method: 'POST', body: file });
+        const result = await resp.json();
+        (result.errors || []).forEach(e => addLog("System", `${file.name} row ${e.row}: ${e.message}`));
+        if (!result.success) {
+            alert(`Import failed: ${result.error}`);
+            return;
+        }
+        currentConfig = result.config;
+        addLog("System", `Imported ${result.imported} of ${result.rows} points from ${file.name}`);
+        if (!isRunning) renderConfigTable(currentConfig);
+    } catch (err) { alert(`Import failed: ${err}`); }
+}
+
 function saveConfiguration() {
     if (!currentConfig) return;
     const dataStr = "data:text/json;charset=utf-8," + encodeURIComponent(JSON.stringify(currentConfig, null, 2));
@@ -0,0 +1,191 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+
+// Command line. Without flags the desktop window opens as before; with
+// --headless only the web server runs, for CI and lab machines without a
+// display. Subcommands run a master request, outstation simulators or
+// test scenarios without the UI (see commands.rs).
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "dnp3_tester",
+    version,
+    about = "DNP3 protocol tester (master / outstation simulator)",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    /// Run only the web server, without the desktop window
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Address the web server binds to in headless mode (0.0.0.0 exposes the unauthenticated API to the network)
+    #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST), requires = "headless")]
+    pub bind: IpAddr,
+
+    /// Port of the web server in headless mode (0 = any free port)
+    #[arg(long, default_value_t = 8080, requires = "headless")]
+    pub port: u16,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    pub fn server_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind, self.port)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Connect to an outstation, run one request and exit
+    #[command(subcommand)]
+    Master(MasterCommand),
+    /// Run outstation simulators from a site file until stopped
+    Outstation(OutstationArgs),
+    /// Run scenario files (YAML or JSON) and report pass/fail
+    Scenario(ScenarioArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct OutstationArgs {
+    /// Site file: one outstation, or {"outstations": [...]}
+    #[arg(long)]
+    pub config: PathBuf,
+
+    /// Print protocol logs to stderr
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ScenarioArgs {
+    /// Scenario files, run one after another
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+
+    /// Also write a JUnit XML report to this file
+    #[arg(long)]
+    pub junit: Option<PathBuf>,
+
+    /// Also write a self-contained HTML report to this file
+    #[arg(long)]
+    pub html: Option<PathBuf>,
+
+    /// Print protocol logs to stderr
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MasterCommand {
+    /// Class scan; prints the received points
+    Read(ReadArgs),
+    /// Send a control to a binary or analog output
+    Operate(OperateArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct MasterConnection {
+    /// Outstation IP address or host name
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
+
+    /// Outstation TCP port
+    #[arg(long, default_value_t = 20000)]
+    pub port: u16,
+
+    /// Serial device instead of TCP (e.g. /dev/ttyUSB0, COM3)
+    #[arg(long, conflicts_with_all = ["host", "port"])]
+    pub serial: Option<String>,
+
+    /// Serial baud rate
+    #[arg(long, default_value_t = 9600, requires = "serial")]
+    pub baud: u32,
+
+    /// Master link address
+    #[arg(long, default_value_t = 1)]
+    pub local: u16,
+
+    /// Outstation link address
+    #[arg(long, default_value_t = 10)]
+    pub remote: u16,
+
+    /// Seconds to wait for the connection
+    #[arg(long, default_value_t = 10)]
+    pub timeout: u64,
+
+    /// Point table (same JSON as default_config.json) used for point names
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+
+    /// Print protocol logs to stderr
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub struct ReadArgs {
+    #[command(flatten)]
+    pub connection: MasterConnection,
+
+    /// Classes to scan, any of the digits 0-3 (e.g. 0123, 123, 0)
+    #[arg(long, default_value = "0123")]
+    pub class: String,
+}
+
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("target").required(true).args(["bo", "ao"])))]
+#[command(group(ArgGroup::new("command").args(["latch_on", "latch_off", "pulse_on", "pulse_off", "value"])))]
+#[command(group(ArgGroup::new("mode").args(["direct", "direct_no_ack", "sbo"])))]
+pub struct OperateArgs {
+    #[command(flatten)]
+    pub connection: MasterConnection,
+
+    /// Binary output (CROB) index
+    #[arg(long)]
+    pub bo: Option<u16>,
+
+    /// Analog output index
+    #[arg(long)]
+    pub ao: Option<u16>,
+
+    #[arg(long, requires = "bo")]
+    pub latch_on: bool,
+    #[arg(long, requires = "bo")]
+    pub latch_off: bool,
+    #[arg(long, requires = "bo")]
+    pub pulse_on: bool,
+    #[arg(long, requires = "bo")]
+    pub pulse_off: bool,
+
+    /// Analog output value
+    #[arg(long, requires = "ao", allow_negative_numbers = true)]
+    pub value: Option<f64>,
+
+    /// Direct operate (default)
+    #[arg(long)]
+    pub direct: bool,
+    /// Direct operate, no acknowledgement
+    #[arg(long)]
+    pub direct_no_ack: bool,
+    /// Select before operate
+    #[arg(long)]
+    pub sbo: bool,
+}
@@ -0,0 +1,416 @@
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::cli::{MasterCommand, MasterConnection, OperateArgs, OutputFormat, OutstationArgs, ReadArgs, ScenarioArgs};
+use crate::dnp3_service::{Dnp3Service, LogStore};
+use crate::live_events::LiveEvent;
+use crate::models::*;
+use crate::scenario::{self, parse_classes, ScenarioReport, StepStatus};
+use crate::site_config;
+use crate::test_report;
+use crate::soe::SoeRecord;
+
+// Subcommands for shell scripts and services. They drive the same
+// Dnp3Service as the UI (start_master, read_classes, execute_control,
+// start_outstation) in sessions of their own; results go to stdout,
+// diagnostics to stderr.
+
+/// Exit codes
+pub const EXIT_OK: i32 = 0;
+/// The request was sent but failed or was rejected by the outstation
+pub const EXIT_FAILED: i32 = 1;
+/// Invalid arguments (clap uses the same code)
+pub const EXIT_USAGE: i32 = 2;
+/// A channel could not be started, or the master did not connect in time
+pub const EXIT_NO_CONNECTION: i32 = 3;
+
+const CLI_SESSION: &str = "cli";
+
+/// A value received in response to the command
+#[derive(Serialize)]
+struct ReceivedPoint {
+    point_type: DataPointType,
+    index: u16,
+    name: String,
+    value: f64,
+    quality: DataQuality,
+    flags: u8,
+    variation: String,
+    device_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub async fn run_master(command: MasterCommand) -> i32 {
+    match command {
+        MasterCommand::Read(args) => read(args).await,
+        MasterCommand::Operate(args) => operate(args).await,
+    }
+}
+
+async fn read(args: ReadArgs) -> i32 {
+    let Some(classes) = parse_classes(&args.class) else {
+        eprintln!("❌ Invalid --class '{}': use digits 0-3, e.g. 0123", args.class);
+        return EXIT_USAGE;
+    };
+    let service = match connect(&args.connection).await {
+        Ok(service) => service,
+        Err(code) => return code,
+    };
+
+    let last_id = last_soe_id(&service);
+    let result = service.read_classes(classes).await;
+    let points = received_since(&service, last_id, None).await;
+    service.disconnect().await;
+
+    match result {
+        Ok(()) => {
+            print_points(args.connection.format, &points);
+            EXIT_OK
+        }
+        Err(e) => {
+            report_error(args.connection.format, &e);
+            EXIT_FAILED
+        }
+    }
+}
+
+async fn operate(args: OperateArgs) -> i32 {
+    let (point_type, index, value, cmd_type) = match (args.bo, args.ao) {
+        (Some(index), _) => {
+            let (value, cmd_type) = if args.latch_on {
+                (1.0, "Latch")
+            } else if args.latch_off {
+                (0.0, "Latch")
+            } else if args.pulse_on {
+                (1.0, "Pulse")
+            } else if args.pulse_off {
+                (0.0, "Pulse")
+            } else {
+                eprintln!("❌ --bo needs one of --latch-on, --latch-off, --pulse-on, --pulse-off");
+                return EXIT_USAGE;
+            };
+            (DataPointType::BinaryOutput, index, value, cmd_type)
+        }
+        (None, Some(index)) => {
+            let Some(value) = args.value else {
+                eprintln!("❌ --ao needs --value");
+                return EXIT_USAGE;
+            };
+            (DataPointType::AnalogOutput, index, value, "Latch")
+        }
+        (None, None) => return EXIT_USAGE, // enforced by clap
+    };
+    // Same names as the control panel's modes
+    let op_mode = if args.sbo {
+        "SBO"
+    } else if args.direct_no_ack {
+        "DirectNoAck"
+    } else {
+        "Direct"
+    };
+
+    let service = match connect(&args.connection).await {
+        Ok(service) => service,
+        Err(code) => return code,
+    };
+
+    let last_id = last_soe_id(&service);
+    let result = service
+        .execute_control(point_type, index, value, op_mode.to_string(), cmd_type.to_string())
+        .await;
+    // Values of the controlled point from the verification read
+    let points = received_since(&service, last_id, Some((point_type, index))).await;
+    service.disconnect().await;
+
+    match result {
+        Ok(message) => {
+            match args.connection.format {
+                OutputFormat::Json => {
+                    emit(&serde_json::json!({ "success": true, "message": message, "points": points }).to_string())
+                }
+                OutputFormat::Table => {
+                    emit(&format!("✅ {}", message));
+                    if !points.is_empty() {
+                        print_points(OutputFormat::Table, &points);
+                    }
+                }
+            }
+            EXIT_OK
+        }
+        Err(e) => {
+            report_error(args.connection.format, &e);
+            EXIT_FAILED
+        }
+    }
+}
+
+/// Start every outstation in the site file and run until SIGINT/SIGTERM
+pub async fn run_outstation(args: OutstationArgs) -> i32 {
+    init_tracing(args.verbose);
+    let plans = match site_config::load(&args.config) {
+        Ok(plans) => plans,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return EXIT_USAGE;
+        }
+    };
+
+    let log_store = Arc::new(LogStore::new());
+    let mut events = log_store.events.subscribe();
+    let mut services: Vec<Arc<Dnp3Service>> = Vec::with_capacity(plans.len());
+    for plan in &plans {
+        let service = Arc::new(Dnp3Service::new(&plan.name, log_store.clone()));
+        if let Some(points) = plan.points.clone() {
+            service.update_config(points).await;
+        }
+        if let Err(e) = service.start_outstation(&plan.config).await {
+            eprintln!("❌ Outstation '{}': {}", plan.name, e);
+            for service in &services {
+                service.disconnect().await;
+            }
+            return EXIT_NO_CONNECTION;
+        }
+        emit(&format!(
+            "🏭 {}: listening on {} (outstation {}, master {}, {} point(s))",
+            plan.name,
+            plan.endpoint(),
+            plan.config.local_address,
+            plan.config.remote_address,
+            service.get_data().await.len()
+        ));
+        services.push(service);
+    }
+    emit(&format!("✅ {} outstation(s) running, Ctrl+C to stop", services.len()));
+
+    let signal = crate::shutdown_signal();
+    tokio::pin!(signal);
+    loop {
+        tokio::select! {
+            name = &mut signal => {
+                emit(&format!("🛑 {} received, stopping", name));
+                break;
+            }
+            event = events.recv() => match event {
+                Ok(LiveEvent::Connection(e)) => emit(&format!("🔗 {}: {} ({})", e.session_id, e.state, e.message)),
+                Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+    for service in &services {
+        service.disconnect().await;
+    }
+    EXIT_OK
+}
+
+/// Run each scenario file in turn; exits 1 if any step failed
+pub async fn run_scenario(args: ScenarioArgs) -> i32 {
+    init_tracing(args.verbose);
+    let mut scenarios = Vec::with_capacity(args.files.len());
+    for path in &args.files {
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+            .and_then(|text| scenario::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)));
+        match parsed {
+            Ok(scenario) => scenarios.push(scenario),
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                return EXIT_USAGE;
+            }
+        }
+    }
+
+    let log_store = Arc::new(LogStore::new());
+    let mut reports = Vec::with_capacity(scenarios.len());
+    for (i, scenario) in scenarios.iter().enumerate() {
+        let service = Arc::new(Dnp3Service::new(&format!("scenario-{}", i + 1), log_store.clone()));
+        let report = scenario::run(scenario, service).await;
+        if args.format == OutputFormat::Table {
+            print_report(&report);
+        }
+        reports.push(report);
+    }
+    if args.format == OutputFormat::Json {
+        emit(&serde_json::to_string_pretty(&reports).unwrap_or_default());
+    }
+    if let Some(path) = &args.junit {
+        if !write_report(path, &test_report::junit(&reports)) {
+            return EXIT_FAILED;
+        }
+    }
+    if let Some(path) = &args.html {
+        if !write_report(path, &test_report::html(&reports)) {
+            return EXIT_FAILED;
+        }
+    }
+
+    if reports.iter().all(|r| r.passed) {
+        EXIT_OK
+    } else {
+        EXIT_FAILED
+    }
+}
+
+fn write_report(path: &std::path::Path, text: &str) -> bool {
+    match std::fs::write(path, text) {
+        Ok(()) => {
+            eprintln!("📄 Report written to {}", path.display());
+            true
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to write {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+fn print_report(report: &ScenarioReport) {
+    emit(&format!("🧪 {}", report.name));
+    for step in &report.steps {
+        let icon = match step.status {
+            StepStatus::Passed => "✅",
+            StepStatus::Failed => "❌",
+            StepStatus::Skipped => "⏭ ",
+        };
+        let line = format!("  {} {:>2}. {:<48} {:>6} ms", icon, step.index, step.description, step.duration_ms);
+        if step.message.is_empty() || step.status == StepStatus::Skipped {
+            emit(&line);
+        } else {
+            emit(&format!("{}  {}", line, step.message));
+        }
+    }
+    emit(&format!(
+        "{} {}: {} passed, {} failed, {} skipped in {} ms",
+        if report.passed { "✅ PASS" } else { "❌ FAIL" },
+        report.name,
+        report.count(StepStatus::Passed),
+        report.count(StepStatus::Failed),
+        report.count(StepStatus::Skipped),
+        report.duration_ms
+    ));
+}
+
+/// Start a master in its own session and wait until the shim reaches the outstation
+async fn connect(args: &MasterConnection) -> Result<Arc<Dnp3Service>, i32> {
+    init_tracing(args.verbose);
+
+    let service = Arc::new(Dnp3Service::new(CLI_SESSION, Arc::new(LogStore::new())));
+    if let Some(path) = &args.config {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            eprintln!("❌ Failed to read {}: {}", path.display(), e);
+            EXIT_USAGE
+        })?;
+        let device_config = serde_json::from_str::<DeviceConfiguration>(&text).map_err(|e| {
+            eprintln!("❌ Failed to parse {}: {}", path.display(), e);
+            EXIT_USAGE
+        })?;
+        service.update_config(device_config).await;
+    }
+
+    let config = Configuration {
+        role: DeviceRole::Master,
+        connection_type: if args.serial.is_some() { ConnectionType::Serial } else { ConnectionType::TcpClient },
+        ip_address: args.host.clone(),
+        port: args.port,
+        local_address: args.local,
+        remote_address: args.remote,
+        serial_port: args.serial.clone(),
+        baud_rate: Some(args.baud),
+        ..Configuration::default()
+    };
+    if let Err(e) = service.start_master(&config).await {
+        eprintln!("❌ {}", e);
+        return Err(EXIT_NO_CONNECTION);
+    }
+
+    if let Err(e) = service.wait_connected(Duration::from_secs(args.timeout)).await {
+        eprintln!("❌ Could not connect: {}", e);
+        service.disconnect().await;
+        return Err(EXIT_NO_CONNECTION);
+    }
+    Ok(service)
+}
+
+fn init_tracing(verbose: bool) {
+    let default = if verbose { "info" } else { "error" };
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(default));
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .with_writer(std::io::stderr)
+        .try_init();
+}
+
+fn last_soe_id(service: &Dnp3Service) -> u64 {
+    service.soe.lock().map(|soe| soe.last_id()).unwrap_or(0)
+}
+
+/// Values from our own polls after `last_id` (startup integrity and
+/// unsolicited responses are left out), named from the point table
+async fn received_since(
+    service: &Dnp3Service,
+    last_id: u64,
+    only: Option<(DataPointType, u16)>,
+) -> Vec<ReceivedPoint> {
+    let records: Vec<SoeRecord> = service.soe.lock().map(|soe| soe.list(Some(last_id), None)).unwrap_or_default();
+    let table = service.get_data().await;
+    records
+        .into_iter()
+        .filter(|r| r.read_type == "SinglePoll")
+        .filter(|r| only.is_none_or(|(t, i)| r.point_type == t && r.index == i))
+        .map(|r| ReceivedPoint {
+            name: table
+                .iter()
+                .find(|p| p.point_type == r.point_type && p.index == r.index)
+                .map(|p| p.name.clone())
+                .unwrap_or_default(),
+            point_type: r.point_type,
+            index: r.index,
+            value: r.value,
+            quality: if r.flags & 0x01 != 0 { DataQuality::Online } else { DataQuality::Offline },
+            flags: r.flags,
+            variation: r.variation,
+            device_time: r.device_time,
+        })
+        .collect()
+}
+
+fn print_points(format: OutputFormat, points: &[ReceivedPoint]) {
+    match format {
+        OutputFormat::Json => emit(&serde_json::to_string_pretty(points).unwrap_or_default()),
+        OutputFormat::Table => {
+            emit(&format!(
+                "{:<14} {:>6}  {:>14}  {:<8} {:>5}  {:<16} NAME",
+                "TYPE", "INDEX", "VALUE", "QUALITY", "FLAGS", "VARIATION"
+            ));
+            for p in points {
+                emit(&format!(
+                    "{:<14} {:>6}  {:>14}  {:<8} {:>#5x}  {:<16} {}",
+                    format!("{:?}", p.point_type),
+                    p.index,
+                    p.value,
+                    format!("{:?}", p.quality),
+                    p.flags,
+                    p.variation,
+                    p.name
+                ));
+            }
+            emit(&format!("{} point(s)", points.len()));
+        }
+    }
+}
+
+fn report_error(format: OutputFormat, error: &str) {
+    match format {
+        OutputFormat::Json => emit(&serde_json::json!({ "success": false, "error": error }).to_string()),
+        OutputFormat::Table => eprintln!("❌ {}", error),
+    }
+}
+
+/// Write a line to stdout; a closed pipe (`| head`) is not an error
+fn emit(text: &str) {
+    let _ = writeln!(std::io::stdout().lock(), "{}", text);
+}
@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use dnp3::app::{Listener, MaybeAsync};
+use dnp3::outstation::ConnectionState;
+use dnp3::tcp::ClientState;
+use serde::Serialize;
+
+use crate::dnp3_service::LogStore;
+
+// Channel state per session. The dnp3 stack always talks to a loopback
+// capture shim, so its own "connected" only means the loopback socket is up:
+// state transitions (connecting, waiting, shutdown) come from the stack's
+// listeners, while "connected" and connect errors come from the shim, which
+// sees the real peer.
+
+/// State changes kept per session
+const HISTORY_LEN: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StateChange {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub state: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionStatus {
+    pub session_id: String,
+    pub role: String, // "master", "outstation" or "" before the first start
+    /// idle, connecting, connected, waiting, disabled or shutdown
+    pub state: String,
+    pub since: chrono::DateTime<chrono::Utc>,
+    /// Delay before the next connect attempt while waiting
+    pub retry_in_ms: Option<u64>,
+    /// Peer of the current connection (address or serial device)
+    pub peer: Option<String>,
+    pub connect_count: u64,
+    /// Connections after the first since the channel was started
+    pub reconnect_count: u64,
+    pub disconnect_count: u64,
+    pub last_connected: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_disconnected: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub history: VecDeque<StateChange>,
+}
+
+impl ConnectionStatus {
+    fn new(session_id: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            role: String::new(),
+            state: "idle".to_string(),
+            since: chrono::Utc::now(),
+            retry_in_ms: None,
+            peer: None,
+            connect_count: 0,
+            reconnect_count: 0,
+            disconnect_count: 0,
+            last_connected: None,
+            last_disconnected: None,
+            last_error: None,
+            last_error_at: None,
+            history: VecDeque::new(),
+        }
+    }
+}
+
+// --- Status Tracker ---
+#[derive(Clone)]
+pub struct StatusTracker {
+    status: Arc<Mutex<ConnectionStatus>>,
+    log_store: Arc<LogStore>,
+    // Bumped on every start, so a dropped channel's late updates are ignored
+    generation: Arc<AtomicU64>,
+}
+
+impl StatusTracker {
+    pub fn new(session_id: &str, log_store: Arc<LogStore>) -> Self {
+        Self {
+            status: Arc::new(Mutex::new(ConnectionStatus::new(session_id))),
+            log_store,
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn snapshot(&self) -> ConnectionStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    /// A channel is being (re)started: counters restart, history is kept
+    pub fn start(&self, role: &str, state: &str, detail: &str) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut s) = self.status.lock() {
+            s.role = role.to_string();
+            s.peer = None;
+            s.connect_count = 0;
+            s.reconnect_count = 0;
+            s.disconnect_count = 0;
+            s.last_error = None;
+            s.last_error_at = None;
+        }
+        self.set_state(state, detail, None);
+    }
+
+    /// Record a transition; repeats of the current state are ignored
+    pub fn set_state(&self, state: &str, detail: &str, retry_in: Option<std::time::Duration>) {
+        let now = chrono::Utc::now();
+        let session_id = {
+            let Ok(mut s) = self.status.lock() else {
+                return;
+            };
+            s.retry_in_ms = retry_in.map(|d| d.as_millis() as u64);
+            if s.state == state {
+                return;
+            }
+            s.state = state.to_string();
+            s.since = now;
+            if s.history.len() >= HISTORY_LEN {
+                s.history.pop_front();
+            }
+            s.history.push_back(StateChange {
+                timestamp: now,
+                state: state.to_string(),
+                detail: detail.to_string(),
+            });
+            s.session_id.clone()
+        };
+        self.log_store.publish_connection(&session_id, state, detail);
+    }
+
+    /// The shim reached the real peer
+    pub fn peer_connected(&self, peer: &str) {
+        if let Ok(mut s) = self.status.lock() {
+            if s.connect_count > 0 {
+                s.reconnect_count += 1;
+            }
+            s.connect_count += 1;
+            s.last_connected = Some(chrono::Utc::now());
+            s.peer = Some(peer.to_string());
+        }
+        self.set_state("connected", &format!("Connected to {}", peer), None);
+    }
+
+    /// The connection to the peer ended. Returns false if there was none.
+    pub fn peer_disconnected(&self, detail: &str) -> bool {
+        if let Ok(mut s) = self.status.lock() {
+            if s.peer.take().is_none() {
+                return false;
+            }
+            s.disconnect_count += 1;
+            s.last_disconnected = Some(chrono::Utc::now());
+        }
+        self.set_state("waiting", detail, None);
+        true
+    }
+
+    /// A connect attempt (or port open) failed
+    pub fn connect_failed(&self, peer: &str, error: &str) {
+        if let Ok(mut s) = self.status.lock() {
+            s.last_error = Some(format!("{}: {}", peer, error));
+            s.last_error_at = Some(chrono::Utc::now());
+        }
+    }
+
+    /// Starting the channel failed; nothing is running
+    pub fn start_failed(&self, error: &str) {
+        if let Ok(mut s) = self.status.lock() {
+            s.last_error = Some(error.to_string());
+            s.last_error_at = Some(chrono::Utc::now());
+        }
+        self.set_state("shutdown", error, None);
+    }
+
+    /// Listener for the channel being started; call after `start`
+    pub fn client_listener(&self) -> Box<dyn Listener<ClientState>> {
+        Box::new(ClientStateListener { tracker: self.clone(), generation: self.generation.load(Ordering::SeqCst) })
+    }
+
+    pub fn connection_listener(&self) -> Box<dyn Listener<ConnectionState>> {
+        Box::new(ConnectionStateListener { tracker: self.clone(), generation: self.generation.load(Ordering::SeqCst) })
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+}
+
+// --- dnp3 Listeners ---
+
+/// Master channel (TCP client to the shim)
+struct ClientStateListener {
+    tracker: StatusTracker,
+    generation: u64,
+}
+
+impl Listener<ClientState> for ClientStateListener {
+    fn update(&mut self, value: ClientState) -> MaybeAsync<()> {
+        let tracker = &self.tracker;
+        if !tracker.is_current(self.generation) {
+            return MaybeAsync::ready(());
+        }
+        match value {
+            ClientState::Disabled => tracker.set_state("disabled", "Channel disabled", None),
+            ClientState::Connecting => tracker.set_state("connecting", "Connecting", None),
+            // Only the loopback socket; the shim reports the real connection
+            ClientState::Connected => {}
+            ClientState::WaitAfterFailedConnect(delay) => {
+                tracker.set_state("waiting", "Connect failed, retrying", Some(delay))
+            }
+            ClientState::WaitAfterDisconnect(delay) => {
+                // Without a peer the shim dropped the loopback socket because the
+                // real connect failed
+                let detail = if tracker.peer_disconnected("Disconnected") {
+                    "Disconnected, retrying"
+                } else {
+                    "Connect failed, retrying"
+                };
+                tracker.set_state("waiting", detail, Some(delay))
+            }
+            ClientState::Shutdown => tracker.set_state("shutdown", "Channel shut down", None),
+        }
+        MaybeAsync::ready(())
+    }
+}
+
+/// Outstation session on the internal server; the shim connects it only once
+/// a master has connected to the real endpoint
+struct ConnectionStateListener {
+    tracker: StatusTracker,
+    generation: u64,
+}
+
+impl Listener<ConnectionState> for ConnectionStateListener {
+    fn update(&mut self, value: ConnectionState) -> MaybeAsync<()> {
+        if self.tracker.is_current(self.generation) && value == ConnectionState::Disconnected {
+            self.tracker.peer_disconnected("Master disconnected");
+        }
+        MaybeAsync::ready(())
+    }
+}
@@ -0,0 +1,396 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+
+use crate::dnp3_service::{LogStore, ProtocolLogEntry};
+use crate::live_events::{LiveEvent, StreamFilter};
+use crate::pcap::PcapngWriter;
+
+// Optional on-disk recording for soak tests. The recorder subscribes to the
+// live event stream and appends protocol logs as JSON lines (logs-*.jsonl)
+// and frames as PCAPNG (frames-*.pcapng), starting a new file whenever the
+// current one passes the size or age limit.
+
+const LOG_PREFIX: &str = "logs";
+const LOG_EXTENSION: &str = "jsonl";
+const FRAME_PREFIX: &str = "frames";
+const FRAME_EXTENSION: &str = "pcapng";
+
+/// Buffered data is flushed (and age limits checked) this often
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+// --- Configuration ---
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingConfig {
+    pub directory: String,
+    /// Start a new file after this many bytes (0 = no size limit)
+    pub max_file_bytes: u64,
+    /// Start a new file after this many seconds (0 = no time limit)
+    pub max_file_secs: u64,
+    /// Keep at most this many files of each kind, deleting the oldest (0 = keep all)
+    pub max_files: usize,
+    /// Record only this session's traffic (default: every session)
+    pub session: Option<String>,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            directory: "recordings".to_string(),
+            max_file_bytes: 50 * 1024 * 1024,
+            max_file_secs: 3600,
+            max_files: 0,
+            session: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecordingStats {
+    pub log_entries: u64,
+    pub frames: u64,
+    pub bytes_written: u64,
+    pub files_opened: u64,
+    /// Events dropped because the writer fell behind the event stream
+    pub missed_events: u64,
+    pub current_files: Vec<String>,
+    pub last_error: Option<String>,
+}
+
+/// Recording settings plus the running recorder, if any
+#[derive(Default)]
+pub struct Recording {
+    pub config: RecordingConfig,
+    pub active: Option<Recorder>,
+}
+
+// --- Recorder ---
+pub struct Recorder {
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    stats: Arc<Mutex<RecordingStats>>,
+    cancel: CancellationToken,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Recorder {
+    pub fn stats(&self) -> RecordingStats {
+        self.stats.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Stop recording and wait until the open files are flushed and closed
+    pub async fn stop(self) {
+        self.cancel.cancel();
+        let _ = self.task.await;
+    }
+}
+
+pub async fn start_recording(config: RecordingConfig, log_store: &LogStore) -> Result<Recorder, String> {
+    tokio::fs::create_dir_all(&config.directory)
+        .await
+        .map_err(|e| format!("Cannot create {}: {}", config.directory, e))?;
+
+    let receiver = log_store.events.subscribe();
+    let stats = Arc::new(Mutex::new(RecordingStats::default()));
+    let cancel = CancellationToken::new();
+    let task = tokio::spawn(record(config, receiver, stats.clone(), cancel.clone()));
+
+    Ok(Recorder {
+        started_at: chrono::Utc::now(),
+        stats,
+        cancel,
+        task,
+    })
+}
+
+async fn record(
+    config: RecordingConfig,
+    mut receiver: broadcast::Receiver<LiveEvent>,
+    stats: Arc<Mutex<RecordingStats>>,
+    cancel: CancellationToken,
+) {
+    let filter = StreamFilter {
+        types: Some(vec!["log".to_string(), "frame".to_string()]),
+        session: config.session.clone(),
+        ..Default::default()
+    };
+    let directory = PathBuf::from(&config.directory);
+    let mut logs = RotatingFile::new(&directory, LOG_PREFIX, LOG_EXTENSION);
+    let mut frames = RotatingFile::new(&directory, FRAME_PREFIX, FRAME_EXTENSION);
+    let mut pcap = PcapngWriter::new();
+    let mut tick = tokio::time::interval(FLUSH_INTERVAL);
+
+    tracing::info!("⏺️ Recording to {}", directory.display());
+    loop {
+        let event = tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tick.tick() => {
+                for file in [&mut logs, &mut frames] {
+                    if let Err(e) = file.flush_or_expire(&config).await {
+                        note_error(&stats, &e);
+                    }
+                }
+                update_files(&stats, &logs, &frames);
+                continue;
+            }
+            r = receiver.recv() => match r {
+                Ok(event) if filter.matches(&event) => event,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    if let Ok(mut s) = stats.lock() {
+                        s.missed_events += missed;
+                    }
+                    tracing::warn!("Recorder fell behind, {} events not written", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        let written = match &event {
+            LiveEvent::Log(entry) => {
+                let mut line = serde_json::to_vec(entry).unwrap_or_default();
+                line.push(b'\n');
+                logs.write(&config, &line).await
+            }
+            LiveEvent::Frame(frame) => write_frame(&mut frames, &mut pcap, &config, frame).await,
+            _ => continue,
+        };
+
+        match written {
+            Ok(bytes) => {
+                if let Ok(mut s) = stats.lock() {
+                    match event {
+                        LiveEvent::Log(_) => s.log_entries += 1,
+                        _ => s.frames += 1,
+                    }
+                    s.bytes_written += bytes;
+                    s.files_opened = logs.opened + frames.opened;
+                }
+            }
+            Err(e) => note_error(&stats, &e),
+        }
+    }
+
+    for file in [&mut logs, &mut frames] {
+        if let Err(e) = file.close().await {
+            note_error(&stats, &e);
+        }
+    }
+    update_files(&stats, &logs, &frames);
+    tracing::info!("⏹️ Recording to {} stopped", directory.display());
+}
+
+async fn write_frame(
+    file: &mut RotatingFile,
+    pcap: &mut PcapngWriter,
+    config: &RecordingConfig,
+    frame: &crate::dnp3_service::RawFrame,
+) -> std::io::Result<u64> {
+    // Every file is a complete capture with its own TCP sequence numbers
+    let mut written = 0;
+    if file.rotate_if_needed(config, frame.data.len()).await? {
+        *pcap = PcapngWriter::new();
+        written += file.append(&pcap.header()).await?;
+    }
+    written += file.append(&pcap.packet(frame)).await?;
+    Ok(written)
+}
+
+fn note_error(stats: &Mutex<RecordingStats>, error: &std::io::Error) {
+    tracing::warn!("Recorder write failed: {}", error);
+    if let Ok(mut s) = stats.lock() {
+        s.last_error = Some(error.to_string());
+    }
+}
+
+fn update_files(stats: &Mutex<RecordingStats>, logs: &RotatingFile, frames: &RotatingFile) {
+    if let Ok(mut s) = stats.lock() {
+        s.current_files = [logs, frames].iter().filter_map(|f| f.current_name()).collect();
+    }
+}
+
+// --- Rotating File ---
+struct RotatingFile {
+    directory: PathBuf,
+    prefix: &'static str,
+    extension: &'static str,
+    file: Option<BufWriter<tokio::fs::File>>,
+    path: PathBuf,
+    opened_at: Instant,
+    bytes: u64,
+    opened: u64,
+}
+
+impl RotatingFile {
+    fn new(directory: &Path, prefix: &'static str, extension: &'static str) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+            prefix,
+            extension,
+            file: None,
+            path: PathBuf::new(),
+            opened_at: Instant::now(),
+            bytes: 0,
+            opened: 0,
+        }
+    }
+
+    fn current_name(&self) -> Option<String> {
+        self.file.as_ref()?;
+        self.path.file_name().map(|n| n.to_string_lossy().to_string())
+    }
+
+    fn expired(&self, config: &RecordingConfig) -> bool {
+        config.max_file_secs > 0 && self.opened_at.elapsed() >= Duration::from_secs(config.max_file_secs)
+    }
+
+    /// Start a new file if none is open or the current one is full or too old.
+    /// Returns true when a new file was opened.
+    async fn rotate_if_needed(&mut self, config: &RecordingConfig, incoming: usize) -> std::io::Result<bool> {
+        let full = config.max_file_bytes > 0 && self.bytes > 0 && self.bytes + incoming as u64 > config.max_file_bytes;
+        if self.file.is_some() && !full && !self.expired(config) {
+            return Ok(false);
+        }
+        self.close().await?;
+        self.open_next(config).await?;
+        Ok(true)
+    }
+
+    /// Append `data`, rotating first if needed. Returns bytes written.
+    async fn write(&mut self, config: &RecordingConfig, data: &[u8]) -> std::io::Result<u64> {
+        self.rotate_if_needed(config, data.len()).await?;
+        self.append(data).await
+    }
+
+    async fn append(&mut self, data: &[u8]) -> std::io::Result<u64> {
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(data).await?;
+            self.bytes += data.len() as u64;
+        }
+        Ok(data.len() as u64)
+    }
+
+    async fn open_next(&mut self, config: &RecordingConfig) -> std::io::Result<()> {
+        let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S-%3f");
+        self.path = self.directory.join(format!("{}-{}.{}", self.prefix, stamp, self.extension));
+        let file = tokio::fs::OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        self.file = Some(BufWriter::new(file));
+        self.opened_at = Instant::now();
+        self.bytes = 0;
+        self.opened += 1;
+        if config.max_files > 0 {
+            self.prune(config.max_files).await;
+        }
+        Ok(())
+    }
+
+    /// Delete the oldest files of this kind so at most `keep` remain
+    async fn prune(&self, keep: usize) {
+        let Ok(files) = list_files(&self.directory.to_string_lossy()).await else {
+            return;
+        };
+        // Names embed the creation time, so name order is age order
+        let mut mine: Vec<_> = files.into_iter().filter(|f| f.kind == self.prefix).map(|f| f.name).collect();
+        mine.sort();
+        let excess = mine.len().saturating_sub(keep);
+        for name in &mine[..excess] {
+            if let Err(e) = tokio::fs::remove_file(self.directory.join(name)).await {
+                tracing::warn!("Could not delete old recording {}: {}", name, e);
+            }
+        }
+    }
+
+    async fn flush_or_expire(&mut self, config: &RecordingConfig) -> std::io::Result<()> {
+        if self.expired(config) {
+            // The next write starts a new file
+            self.close().await
+        } else if let Some(file) = self.file.as_mut() {
+            file.flush().await
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn close(&mut self) -> std::io::Result<()> {
+        match self.file.take() {
+            Some(mut file) => file.shutdown().await,
+            None => Ok(()),
+        }
+    }
+}
+
+// ============================================================================
+// BROWSING PREVIOUS RECORDINGS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingFile {
+    pub name: String,
+    pub kind: &'static str, // "logs" or "frames"
+    pub size: u64,
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Recording files in `directory`, oldest first
+pub async fn list_files(directory: &str) -> Result<Vec<RecordingFile>, String> {
+    let mut entries = tokio::fs::read_dir(directory)
+        .await
+        .map_err(|e| format!("Cannot read {}: {}", directory, e))?;
+    let mut files = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(kind) = file_kind(&name) else {
+            continue;
+        };
+        let Ok(meta) = entry.metadata().await else {
+            continue;
+        };
+        files.push(RecordingFile {
+            name,
+            kind,
+            size: meta.len(),
+            modified: meta.modified().ok().map(chrono::DateTime::from),
+        });
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+fn file_kind(name: &str) -> Option<&'static str> {
+    let (stem, extension) = name.rsplit_once('.')?;
+    match extension {
+        LOG_EXTENSION if stem.starts_with(LOG_PREFIX) => Some(LOG_PREFIX),
+        FRAME_EXTENSION if stem.starts_with(FRAME_PREFIX) => Some(FRAME_PREFIX),
+        _ => None,
+    }
+}
+
+/// Path of a recording file by name. Only bare names of recording files are
+/// accepted, so the API can't be used to read anything else on disk.
+pub fn resolve(directory: &str, name: &str) -> Result<PathBuf, String> {
+    let bare = Path::new(name).file_name().is_some_and(|n| n == name);
+    if !bare || file_kind(name).is_none() {
+        return Err(format!("Not a recording file: {}", name));
+    }
+    Ok(Path::new(directory).join(name))
+}
+
+/// Log entries from a JSON lines file, skipping `offset` entries
+pub async fn read_log_file(path: &Path, offset: usize, limit: usize) -> Result<Vec<ProtocolLogEntry>, String> {
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    // A partial last line (file still being written) is skipped
+    Ok(text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .skip(offset)
+        .take(limit)
+        .collect())
+}
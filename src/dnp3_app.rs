@@ -0,0 +1,689 @@
+// DNP3 application layer (IEEE 1815 clause 4) fragment decoding.
+// Covers the application control field, function code, IIN bits and object
+// headers, with values for the object groups this tool produces or consumes.
+
+use std::collections::HashMap;
+
+use crate::dnp3_service::RawFrame;
+use crate::dnp3_transport::{Reassembler, TransportHeader};
+
+// --- Decoded Application Fragment ---
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Apdu {
+    pub control: AppControl,
+    pub function: u8,
+    pub function_name: String,
+    pub iin: Option<Iin>,
+    pub headers: Vec<ObjectHeader>,
+    /// Set when decoding stopped early; headers decoded up to that point are kept
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct AppControl {
+    pub fir: bool,
+    pub fin: bool,
+    pub con: bool,
+    pub uns: bool,
+    pub seq: u8,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Iin {
+    pub iin1: u8,
+    pub iin2: u8,
+    pub flags: Vec<String>, // Names of the bits that are set
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ObjectHeader {
+    pub group: u8,
+    pub variation: u8,
+    pub name: String,
+    pub qualifier: u8,
+    pub range: Range,
+    pub objects: Vec<ObjectValue>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Range {
+    StartStop { start: u32, stop: u32 },
+    Count { count: u32 },
+    All,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ObjectValue {
+    pub index: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u8>, // Command status (g12 / g41 / g43)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<u64>, // Absolute time, ms since epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relative_time: Option<u16>, // ms offset from the preceding CTO
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+pub fn function_name(code: u8) -> &'static str {
+    match code {
+        0 => "CONFIRM",
+        1 => "READ",
+        2 => "WRITE",
+        3 => "SELECT",
+        4 => "OPERATE",
+        5 => "DIRECT_OPERATE",
+        6 => "DIRECT_OPERATE_NR",
+        7 => "IMMEDIATE_FREEZE",
+        8 => "IMMEDIATE_FREEZE_NR",
+        9 => "FREEZE_CLEAR",
+        10 => "FREEZE_CLEAR_NR",
+        11 => "FREEZE_AT_TIME",
+        12 => "FREEZE_AT_TIME_NR",
+        13 => "COLD_RESTART",
+        14 => "WARM_RESTART",
+        15 => "INITIALIZE_DATA",
+        16 => "INITIALIZE_APPLICATION",
+        17 => "START_APPLICATION",
+        18 => "STOP_APPLICATION",
+        19 => "SAVE_CONFIGURATION",
+        20 => "ENABLE_UNSOLICITED",
+        21 => "DISABLE_UNSOLICITED",
+        22 => "ASSIGN_CLASS",
+        23 => "DELAY_MEASURE",
+        24 => "RECORD_CURRENT_TIME",
+        25 => "OPEN_FILE",
+        26 => "CLOSE_FILE",
+        27 => "DELETE_FILE",
+        28 => "GET_FILE_INFO",
+        29 => "AUTHENTICATE_FILE",
+        30 => "ABORT_FILE",
+        31 => "ACTIVATE_CONFIG",
+        32 => "AUTH_REQUEST",
+        33 => "AUTH_REQUEST_NO_ACK",
+        129 => "RESPONSE",
+        130 => "UNSOLICITED_RESPONSE",
+        131 => "AUTH_RESPONSE",
+        _ => "UNKNOWN",
+    }
+}
+
+const IIN1_NAMES: [&str; 8] = [
+    "BROADCAST",
+    "CLASS_1_EVENTS",
+    "CLASS_2_EVENTS",
+    "CLASS_3_EVENTS",
+    "NEED_TIME",
+    "LOCAL_CONTROL",
+    "DEVICE_TROUBLE",
+    "DEVICE_RESTART",
+];
+
+const IIN2_NAMES: [&str; 8] = [
+    "NO_FUNC_CODE_SUPPORT",
+    "OBJECT_UNKNOWN",
+    "PARAMETER_ERROR",
+    "EVENT_BUFFER_OVERFLOW",
+    "ALREADY_EXECUTING",
+    "CONFIG_CORRUPT",
+    "RESERVED_2",
+    "RESERVED_1",
+];
+
+impl Iin {
+    fn new(iin1: u8, iin2: u8) -> Self {
+        let mut flags = set_bit_names(iin1, &IIN1_NAMES);
+        flags.extend(set_bit_names(iin2, &IIN2_NAMES));
+        Self { iin1, iin2, flags }
+    }
+}
+
+fn set_bit_names(byte: u8, names: &[&str; 8]) -> Vec<String> {
+    names
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| byte & (1 << bit) != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Short description of an object group, e.g. "Analog Input Event"
+pub fn group_name(group: u8) -> &'static str {
+    match group {
+        0 => "Device Attributes",
+        1 => "Binary Input",
+        2 => "Binary Input Event",
+        3 => "Double-bit Binary Input",
+        4 => "Double-bit Binary Input Event",
+        10 => "Binary Output",
+        11 => "Binary Output Event",
+        12 => "Binary Command (CROB)",
+        13 => "Binary Output Command Event",
+        20 => "Counter",
+        21 => "Frozen Counter",
+        22 => "Counter Event",
+        23 => "Frozen Counter Event",
+        30 => "Analog Input",
+        31 => "Frozen Analog Input",
+        32 => "Analog Input Event",
+        33 => "Frozen Analog Input Event",
+        34 => "Analog Input Deadband",
+        40 => "Analog Output Status",
+        41 => "Analog Output Command",
+        42 => "Analog Output Event",
+        43 => "Analog Output Command Event",
+        50 => "Time and Date",
+        51 => "Common Time of Occurrence",
+        52 => "Time Delay",
+        60 => "Class Data",
+        80 => "Internal Indications",
+        110 => "Octet String",
+        111 => "Octet String Event",
+        120 => "Authentication",
+        _ => "Unknown",
+    }
+}
+
+// --- Object layouts ---
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Flags,
+    Status,
+    U16,
+    U32,
+    I16,
+    I32,
+    F32,
+    F64,
+    Time48,
+    Time16,
+    Interval, // u32 interval + u8 units (g50v4)
+    Crob,     // code, count, on-time, off-time, status (g12v1)
+}
+
+impl Field {
+    fn size(self) -> usize {
+        match self {
+            Field::Flags | Field::Status => 1,
+            Field::U16 | Field::I16 | Field::Time16 => 2,
+            Field::U32 | Field::I32 | Field::F32 => 4,
+            Field::Time48 => 6,
+            Field::Interval => 5,
+            Field::F64 => 8,
+            Field::Crob => 11,
+        }
+    }
+}
+
+enum Layout {
+    Fields(&'static [Field]),
+    Bits(usize), // Packed, n bits per object
+    Octets(usize),
+    Empty, // Header only (class data, requests)
+}
+
+fn layout(group: u8, variation: u8) -> Option<Layout> {
+    use Field::*;
+    let fields: &'static [Field] = match (group, variation) {
+        (1, 1) | (10, 1) | (80, 1) => return Some(Layout::Bits(1)),
+        (3, 1) => return Some(Layout::Bits(2)),
+        (60, _) => return Some(Layout::Empty),
+        (110, v) | (111, v) => return Some(Layout::Octets(v as usize)),
+
+        (1, 2) | (2, 1) | (3, 2) | (4, 1) | (10, 2) | (11, 1) | (13, 1) => &[Flags],
+        (2, 2) | (4, 2) | (11, 2) | (13, 2) => &[Flags, Time48],
+        (2, 3) | (4, 3) => &[Flags, Time16],
+
+        (12, 1) => &[Crob],
+
+        (20, 1) | (21, 1) | (22, 1) | (23, 1) => &[Flags, U32],
+        (20, 2) | (21, 2) | (22, 2) | (23, 2) => &[Flags, U16],
+        (20, 5) | (21, 9) => &[U32],
+        (20, 6) | (21, 10) => &[U16],
+        (21, 5) | (22, 5) | (23, 5) => &[Flags, U32, Time48],
+        (21, 6) | (22, 6) | (23, 6) => &[Flags, U16, Time48],
+
+        (30, 1) | (31, 1) | (32, 1) | (33, 1) | (40, 1) | (42, 1) => &[Flags, I32],
+        (30, 2) | (31, 2) | (32, 2) | (33, 2) | (40, 2) | (42, 2) => &[Flags, I16],
+        (30, 3) | (31, 5) => &[I32],
+        (30, 4) | (31, 6) => &[I16],
+        (30, 5) | (31, 7) | (32, 5) | (33, 5) | (40, 3) | (42, 5) => &[Flags, F32],
+        (30, 6) | (31, 8) | (32, 6) | (33, 6) | (40, 4) | (42, 6) => &[Flags, F64],
+        (31, 3) | (32, 3) | (33, 3) | (42, 3) => &[Flags, I32, Time48],
+        (31, 4) | (32, 4) | (33, 4) | (42, 4) => &[Flags, I16, Time48],
+        (32, 7) | (33, 7) | (42, 7) => &[Flags, F32, Time48],
+        (32, 8) | (33, 8) | (42, 8) => &[Flags, F64, Time48],
+
+        (34, 1) => &[U16],
+        (34, 2) => &[U32],
+        (34, 3) => &[F32],
+
+        (41, 1) => &[I32, Status],
+        (41, 2) => &[I16, Status],
+        (41, 3) => &[F32, Status],
+        (41, 4) => &[F64, Status],
+
+        (43, 1) => &[Status, I32],
+        (43, 2) => &[Status, I16],
+        (43, 3) => &[Status, I32, Time48],
+        (43, 4) => &[Status, I16, Time48],
+        (43, 5) => &[Status, F32],
+        (43, 6) => &[Status, F64],
+        (43, 7) => &[Status, F32, Time48],
+        (43, 8) => &[Status, F64, Time48],
+
+        (50, 1) | (50, 3) | (51, 1) | (51, 2) => &[Time48],
+        (50, 4) => &[Time48, Interval],
+        (52, 1) | (52, 2) => &[U16],
+        _ => return None,
+    };
+    Some(Layout::Fields(fields))
+}
+
+/// Groups whose "value" is the state bit(s) carried in the flags byte
+fn state_from_flags(group: u8, flags: u8) -> Option<f64> {
+    match group {
+        1 | 2 | 10 | 11 | 13 => Some(if flags & 0x80 != 0 { 1.0 } else { 0.0 }),
+        3 | 4 => Some(((flags >> 6) & 0x03) as f64),
+        _ => None,
+    }
+}
+
+fn crob_op_name(code: u8) -> &'static str {
+    match code & 0x0F {
+        0 => "NUL",
+        1 => "PULSE_ON",
+        2 => "PULSE_OFF",
+        3 => "LATCH_ON",
+        4 => "LATCH_OFF",
+        _ => "UNDEFINED",
+    }
+}
+
+// --- Cursor ---
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.remaining() < n {
+            return Err(format!("need {} bytes at offset {}, {} left", n, self.pos, self.remaining()));
+        }
+        let slice = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint(&mut self, size: usize) -> Result<u32, String> {
+        let b = self.take(size)?;
+        Ok(b.iter().rev().fold(0u32, |acc, x| (acc << 8) | *x as u32))
+    }
+}
+
+fn le_u48(b: &[u8]) -> u64 {
+    b[..6].iter().rev().fold(0u64, |acc, x| (acc << 8) | *x as u64)
+}
+
+fn decode_fields(fields: &[Field], data: &[u8], index: u32, group: u8) -> ObjectValue {
+    let mut obj = ObjectValue { index, ..Default::default() };
+    let mut pos = 0;
+    for field in fields {
+        let b = &data[pos..pos + field.size()];
+        match field {
+            Field::Flags => {
+                obj.flags = Some(b[0]);
+                if let Some(state) = state_from_flags(group, b[0]) {
+                    obj.value = Some(state);
+                }
+            }
+            Field::Status => obj.status = Some(b[0]),
+            Field::U16 => obj.value = Some(u16::from_le_bytes([b[0], b[1]]) as f64),
+            Field::U32 => obj.value = Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64),
+            Field::I16 => obj.value = Some(i16::from_le_bytes([b[0], b[1]]) as f64),
+            Field::I32 => obj.value = Some(i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64),
+            Field::F32 => obj.value = Some(f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64),
+            Field::F64 => obj.value = Some(f64::from_le_bytes(b.try_into().unwrap_or_default())),
+            Field::Time48 => obj.time = Some(le_u48(b)),
+            Field::Time16 => obj.relative_time = Some(u16::from_le_bytes([b[0], b[1]])),
+            Field::Interval => {
+                let interval = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                obj.detail = Some(format!("interval={} units={}", interval, b[4]));
+            }
+            Field::Crob => {
+                let on = u32::from_le_bytes([b[2], b[3], b[4], b[5]]);
+                let off = u32::from_le_bytes([b[6], b[7], b[8], b[9]]);
+                obj.status = Some(b[10]);
+                obj.detail = Some(format!(
+                    "{} code=0x{:02X} count={} on={}ms off={}ms",
+                    crob_op_name(b[0]), b[0], b[1], on, off
+                ));
+            }
+        }
+        pos += field.size();
+    }
+    obj
+}
+
+/// Function codes whose request headers carry object data
+fn request_has_objects(function: u8) -> bool {
+    matches!(function, 2 | 3 | 4 | 5 | 6 | 11 | 12 | 24 | 31)
+}
+
+/// Decode a complete application fragment
+pub fn decode_apdu(data: &[u8]) -> Result<Apdu, String> {
+    if data.len() < 2 {
+        return Err(format!("APDU too short ({} bytes)", data.len()));
+    }
+    let ac = data[0];
+    let control = AppControl {
+        fir: ac & 0x80 != 0,
+        fin: ac & 0x40 != 0,
+        con: ac & 0x20 != 0,
+        uns: ac & 0x10 != 0,
+        seq: ac & 0x0F,
+    };
+    let function = data[1];
+    let is_response = function >= 129;
+
+    let mut cursor = Cursor { data, pos: 2 };
+    let iin = if is_response {
+        let iin1 = cursor.u8()?;
+        let iin2 = cursor.u8()?;
+        Some(Iin::new(iin1, iin2))
+    } else {
+        None
+    };
+
+    let with_objects = is_response || request_has_objects(function);
+    let mut headers = Vec::new();
+    let mut error = None;
+    while cursor.remaining() > 0 {
+        match decode_header(&mut cursor, with_objects) {
+            Ok(header) => headers.push(header),
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    Ok(Apdu {
+        control,
+        function,
+        function_name: function_name(function).to_string(),
+        iin,
+        headers,
+        error,
+    })
+}
+
+fn decode_header(cursor: &mut Cursor, with_objects: bool) -> Result<ObjectHeader, String> {
+    let group = cursor.u8()?;
+    let variation = cursor.u8()?;
+    let qualifier = cursor.u8()?;
+    let prefix_code = (qualifier >> 4) & 0x07;
+    let range_code = qualifier & 0x0F;
+
+    let range = match range_code {
+        0x00 | 0x03 => Range::StartStop { start: cursor.uint(1)?, stop: cursor.uint(1)? },
+        0x01 | 0x04 => Range::StartStop { start: cursor.uint(2)?, stop: cursor.uint(2)? },
+        0x02 | 0x05 => Range::StartStop { start: cursor.uint(4)?, stop: cursor.uint(4)? },
+        0x06 => Range::All,
+        0x07 => Range::Count { count: cursor.uint(1)? },
+        0x08 => Range::Count { count: cursor.uint(2)? },
+        0x09 => Range::Count { count: cursor.uint(4)? },
+        0x0B => Range::Count { count: cursor.uint(1)? },
+        _ => return Err(format!("g{}v{}: unsupported qualifier 0x{:02X}", group, variation, qualifier)),
+    };
+
+    let mut header = ObjectHeader {
+        group,
+        variation,
+        name: format!("g{}v{} {}", group, variation, group_name(group)),
+        qualifier,
+        range: range.clone(),
+        objects: Vec::new(),
+    };
+
+    let count = match range {
+        Range::StartStop { start, stop } if stop >= start => (stop - start) as usize + 1,
+        Range::StartStop { .. } => return Err(format!("g{}v{}: stop < start", group, variation)),
+        Range::Count { count } => count as usize,
+        Range::All => 0,
+    };
+    if !with_objects || count == 0 {
+        return Ok(header);
+    }
+
+    let prefix_size = match prefix_code {
+        0 => 0,
+        1 | 4 => 1,
+        2 | 5 => 2,
+        3 | 6 => 4,
+        _ => return Err(format!("g{}v{}: unsupported prefix code {}", group, variation, prefix_code)),
+    };
+    let layout = layout(group, variation)
+        .ok_or_else(|| format!("g{}v{}: object decoding not supported", group, variation))?;
+
+    let start = match range {
+        Range::StartStop { start, .. } => start,
+        _ => 0,
+    };
+
+    // The range comes from the peer: check the objects are there before decoding them
+    let object_size = match &layout {
+        Layout::Octets(0) => return Err(format!("g{}v{}: zero-length octet string objects", group, variation)),
+        Layout::Octets(size) => prefix_size + size,
+        Layout::Fields(fields) => prefix_size + fields.iter().map(|f| f.size()).sum::<usize>(),
+        Layout::Bits(_) | Layout::Empty => 0, // Bits are taken in one piece below
+    };
+    let needed = count.saturating_mul(object_size);
+    if needed > cursor.remaining() {
+        return Err(format!(
+            "g{}v{}: {} objects need {} bytes, {} left",
+            group, variation, count, needed, cursor.remaining()
+        ));
+    }
+
+    match layout {
+        Layout::Empty => {}
+        Layout::Bits(bits) => {
+            let bytes = cursor.take((count * bits).div_ceil(8))?;
+            for i in 0..count {
+                let bit = i * bits;
+                let raw = (bytes[bit / 8] >> (bit % 8)) & ((1 << bits) - 1);
+                header.objects.push(ObjectValue {
+                    index: start + i as u32,
+                    value: Some(raw as f64),
+                    ..Default::default()
+                });
+            }
+        }
+        Layout::Octets(size) => {
+            for i in 0..count {
+                let prefix = if prefix_size > 0 { Some(cursor.uint(prefix_size)?) } else { None };
+                let index = match prefix {
+                    Some(index) if prefix_code <= 3 => index,
+                    _ => start + i as u32,
+                };
+                let bytes = cursor.take(size)?;
+                header.objects.push(ObjectValue {
+                    index,
+                    detail: Some(bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")),
+                    ..Default::default()
+                });
+            }
+        }
+        Layout::Fields(fields) => {
+            let size: usize = fields.iter().map(|f| f.size()).sum();
+            for i in 0..count {
+                // Prefix codes 1-3 carry the point index, 4-6 the object size
+                let prefix = if prefix_size > 0 { Some(cursor.uint(prefix_size)?) } else { None };
+                let index = match prefix {
+                    Some(index) if prefix_code <= 3 => index,
+                    _ => start + i as u32,
+                };
+                let bytes = cursor.take(size)?;
+                header.objects.push(decode_fields(fields, bytes, index, group));
+            }
+        }
+    }
+
+    Ok(header)
+}
+
+// --- Frame View Decoding ---
+
+/// A captured frame together with its transport segment and, on the segment
+/// that completes a fragment, the decoded APDU
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DecodedFrame {
+    #[serde(flatten)]
+    pub frame: RawFrame,
+    pub transport: Option<TransportHeader>,
+    pub transport_error: Option<String>,
+    pub apdu: Option<Apdu>,
+    pub apdu_error: Option<String>,
+}
+
+/// Run captured frames (in capture order) through transport reassembly and
+/// application decoding. Each session is reassembled separately, since a
+/// master and outstation running in one process capture the same traffic.
+pub fn decode_frames<'a>(frames: impl IntoIterator<Item = &'a RawFrame>) -> Vec<DecodedFrame> {
+    let mut reassemblers: HashMap<&str, Reassembler> = HashMap::new();
+    frames
+        .into_iter()
+        .map(|frame| {
+            let reassembler = reassemblers.entry(frame.session_id.as_str()).or_insert_with(Reassembler::new);
+            let segment = frame.link.as_ref().and_then(|link| reassembler.push(link));
+            let (apdu, apdu_error) = match segment.as_ref().and_then(|s| s.apdu.as_ref()) {
+                Some(bytes) => match decode_apdu(bytes) {
+                    Ok(apdu) => (Some(apdu), None),
+                    Err(e) => (None, Some(e)),
+                },
+                None => (None, None),
+            };
+            DecodedFrame {
+                frame: frame.clone(),
+                transport: segment.as_ref().map(|s| s.header),
+                transport_error: segment.and_then(|s| s.error),
+                apdu,
+                apdu_error,
+            }
+        })
+        .collect()
+}
+
+/// Frames decoded ahead of a page so fragments that started before it still
+/// reassemble (a 64 KiB APDU spans at most ~270 link frames)
+const REASSEMBLY_CONTEXT: usize = 300;
+
+/// Decode one page of frames (ordered by id). With `since`, the page starts
+/// after that id; without it, it is the newest `limit` frames.
+pub fn decode_page(frames: &[&RawFrame], since: Option<u64>, limit: Option<usize>) -> Vec<DecodedFrame> {
+    let start = match since {
+        Some(since) => frames.partition_point(|f| f.id <= since),
+        None => frames.len().saturating_sub(limit.unwrap_or(frames.len())),
+    };
+    let end = limit.map_or(frames.len(), |l| (start + l).min(frames.len()));
+    let context_start = start.saturating_sub(REASSEMBLY_CONTEXT);
+    let mut decoded = decode_frames(frames[context_start..end].iter().copied());
+    decoded.drain(..start - context_start);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_class_poll_request() {
+        // READ g60v2 g60v3 g60v4 g60v1, all objects
+        let apdu = decode_apdu(&[0xC3, 0x01, 60, 2, 0x06, 60, 3, 0x06, 60, 4, 0x06, 60, 1, 0x06]).unwrap();
+        assert!(apdu.control.fir && apdu.control.fin);
+        assert_eq!(apdu.control.seq, 3);
+        assert_eq!(apdu.function_name, "READ");
+        assert!(apdu.iin.is_none());
+        let groups: Vec<(u8, u8)> = apdu.headers.iter().map(|h| (h.group, h.variation)).collect();
+        assert_eq!(groups, vec![(60, 2), (60, 3), (60, 4), (60, 1)]);
+        assert!(apdu.error.is_none());
+    }
+
+    #[test]
+    fn decodes_response_objects() {
+        let mut data = vec![0xC0, 0x81, 0x80, 0x00];
+        // g1v2 indexes 0-1: OFF online, ON online
+        data.extend_from_slice(&[1, 2, 0x00, 0, 1, 0x01, 0x81]);
+        // g30v5 index 3, prefixed count: 12.5 online
+        data.extend_from_slice(&[30, 5, 0x17, 1, 3, 0x01]);
+        data.extend_from_slice(&12.5f32.to_le_bytes());
+        let apdu = decode_apdu(&data).unwrap();
+
+        let iin = apdu.iin.unwrap();
+        assert_eq!(iin.iin1, 0x80);
+        assert!(iin.flags.iter().any(|f| f == "DEVICE_RESTART"));
+        assert!(apdu.error.is_none());
+
+        let binaries = &apdu.headers[0].objects;
+        assert_eq!(binaries.iter().map(|o| o.value).collect::<Vec<_>>(), vec![Some(0.0), Some(1.0)]);
+        assert_eq!(binaries[1].flags, Some(0x81));
+
+        let analog = &apdu.headers[1].objects[0];
+        assert_eq!((analog.index, analog.value, analog.flags), (3, Some(12.5), Some(0x01)));
+    }
+
+    #[test]
+    fn truncated_header_keeps_decoded_part() {
+        let mut data = vec![0xC0, 0x81, 0x00, 0x00, 1, 2, 0x00, 0, 0, 0x81];
+        // g30v5 start/stop 0-3, but the data stops inside the first object
+        data.extend_from_slice(&[30, 5, 0x00, 0, 3, 0x01, 0, 0]);
+        let apdu = decode_apdu(&data).unwrap();
+        assert_eq!(apdu.headers.len(), 1);
+        assert_eq!(apdu.error.as_deref(), Some("g30v5: 4 objects need 20 bytes, 3 left"));
+    }
+
+    #[test]
+    fn rejects_ranges_larger_than_the_data() {
+        // g110v0 with start/stop 0-0x0FFFFFFF: zero-length objects, no bytes needed
+        let apdu = decode_apdu(&[0xC0, 0x81, 0x00, 0x00, 0x6E, 0x00, 0x02, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0x0F]).unwrap();
+        assert!(apdu.headers.is_empty());
+        assert_eq!(apdu.error.as_deref(), Some("g110v0: zero-length octet string objects"));
+
+        // g111v4 and g32v5 with a 4-byte count of 0xFFFFFFFF and a few bytes of data
+        let apdu = decode_apdu(&[0xC0, 0x81, 0x00, 0x00, 0x6F, 0x04, 0x09, 0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3, 4]).unwrap();
+        assert!(apdu.headers.is_empty() && apdu.error.unwrap().contains("4294967295 objects"));
+        let apdu = decode_apdu(&[0xC0, 0x81, 0x00, 0x00, 0x20, 0x05, 0x39, 0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3]).unwrap();
+        assert!(apdu.headers.is_empty() && apdu.error.is_some());
+    }
+
+    #[test]
+    fn every_truncation_decodes_without_panic() {
+        let mut data = vec![0xC0, 0x81, 0x00, 0x00, 1, 2, 0x00, 0, 1, 0x01, 0x81];
+        data.extend_from_slice(&[12, 1, 0x28, 1, 0, 5, 0, 0x03, 1, 100, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[50, 1, 0x07, 1, 1, 2, 3, 4, 5, 6]);
+        assert!(decode_apdu(&data).unwrap().error.is_none());
+        for len in 0..data.len() {
+            match decode_apdu(&data[..len]) {
+                Ok(apdu) => assert!(apdu.headers.len() < 3, "{} bytes", len),
+                Err(_) => assert!(len < 4, "{} bytes", len),
+            }
+        }
+    }
+}
@@ -1,38 +1,86 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::span::{Attributes, Id};
 use tracing::Subscriber;
 use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::Layer;
 
-use crate::dnp3_service::RawFrame;
-
-/// Custom tracing layer to capture DNP3 hex frames from library output
+/// Custom tracing layer to capture important DNP3 library output as system logs.
+/// Raw frames are no longer scraped from log text; see `frame_capture`.
 use crate::dnp3_service::ProtocolLogEntry;
+use crate::live_events::LiveEvent;
 
 pub struct Dnp3FrameLayer {
-    pub frames: Arc<RwLock<VecDeque<RawFrame>>>,
     logs: Arc<RwLock<VecDeque<ProtocolLogEntry>>>,
-    frame_counter: Arc<std::sync::atomic::AtomicU64>,
     log_counter: Arc<std::sync::atomic::AtomicU64>,
+    session_endpoints: Arc<std::sync::RwLock<HashMap<String, String>>>,
+    events: tokio::sync::broadcast::Sender<LiveEvent>,
 }
 
 impl Dnp3FrameLayer {
     pub fn new(
-        frames: Arc<RwLock<VecDeque<RawFrame>>>, 
         logs: Arc<RwLock<VecDeque<ProtocolLogEntry>>>,
-        frame_counter: Arc<std::sync::atomic::AtomicU64>,
-        log_counter: Arc<std::sync::atomic::AtomicU64>
+        log_counter: Arc<std::sync::atomic::AtomicU64>,
+        session_endpoints: Arc<std::sync::RwLock<HashMap<String, String>>>,
+        events: tokio::sync::broadcast::Sender<LiveEvent>,
     ) -> Self {
-        Self { frames, logs, frame_counter, log_counter }
+        Self { logs, log_counter, session_endpoints, events }
+    }
+}
+
+/// Session a span belongs to, stored in the span's extensions
+struct SessionTag(String);
+
+/// Picks the fields that identify a session out of a span:
+/// our own `session_id`, or the address dnp3 records as `endpoint`/`listen`
+#[derive(Default)]
+struct SessionFieldVisitor {
+    session_id: Option<String>,
+    address: Option<String>,
+}
+
+impl tracing::field::Visit for SessionFieldVisitor {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.record_value(field.name(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.record_value(field.name(), format!("{:?}", value).trim_matches('"').to_string());
     }
 }
 
-impl<S: Subscriber> Layer<S> for Dnp3FrameLayer {
+impl SessionFieldVisitor {
+    fn record_value(&mut self, name: &str, value: String) {
+        match name {
+            "session_id" => self.session_id = Some(value),
+            "endpoint" | "listen" => self.address = Some(value),
+            _ => {}
+        }
+    }
+}
+
+impl<S> Layer<S> for Dnp3FrameLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = SessionFieldVisitor::default();
+        attrs.record(&mut visitor);
+        let session = visitor.session_id.or_else(|| {
+            let address = visitor.address?;
+            self.session_endpoints.read().ok()?.get(&address).cloned()
+        });
+        if let (Some(session), Some(span)) = (session, ctx.span(id)) {
+            span.extensions_mut().insert(SessionTag(session));
+        }
+    }
+
     fn on_event(
         &self,
         event: &tracing::Event<'_>,
-        _ctx: Context<'_, S>,
+        ctx: Context<'_, S>,
     ) {
         use tracing::field::{Field, Visit};
         
@@ -69,50 +117,19 @@ impl<S: Subscriber> Layer<S> for Dnp3FrameLayer {
         
         event.record(&mut visitor);
         
-        // CRITICAL FIX: format!("{:?}", value) escapes newlines as "\n".
-        // We must unescape them to let extract_hex_bytes work properly.
-        let raw_msg = visitor.message.clone();
-        let msg = raw_msg
-            .replace("\\n", " ")
-            .replace("\\r", " ")
-            .replace("\\t", " ")
-            .replace("\\\"", "\""); // Unescape quotes too just in case
         // Capture from dnp3 crate OR our own service
         if !visitor.target.starts_with("dnp3") && !visitor.target.contains("dnp3_tester") {
             return;
         }
 
-        let msg_clean = raw_msg
+        // format!("{:?}", value) escapes newlines as "\n"; flatten them for the log view
+        let msg_clean = visitor.message
             .replace("\\n", " ")
             .replace("\\r", " ")
             .replace("\\t", " ")
             .replace("\\\"", "\"");
 
-        // 1. Try to extract hex frames
-        if let Some(hex_data) = extract_hex_bytes(&msg_clean) {
-            if hex_data.len() >= 2 {
-                 let direction_str = if msg_clean.contains("TX") { "TX" } else { "RX" };
-                 
-                 let frames = self.frames.clone();
-                 // ... push frame ...
-                  if let Ok(mut q) = frames.try_write() {
-                       if q.len() >= 1000 { q.pop_front(); }
-                       let id = self.frame_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                       q.push_back(RawFrame {
-                           id,
-                           timestamp: chrono::Utc::now(),
-                           direction: direction_str.to_string(),
-                           data: hex_data,
-                       });
-                  }
-                 // Frames are handled, return or continue?
-                 // If it is a frame log, we might not want to duplicate it in System Log unless verbose.
-                 // Let's return to avoid cluttering System Log with "Phys TX..." text which is redundant with Frame view.
-                 return;
-            }
-        }
-
-        // 2. Capture important System Logs (Warnings, Errors, Connectivity)
+        // Capture important System Logs (Warnings, Errors, Connectivity)
         let level = *event.metadata().level();
         let is_important = level <= tracing::Level::WARN 
             || msg_clean.contains("connected") 
@@ -120,6 +137,14 @@ impl<S: Subscriber> Layer<S> for Dnp3FrameLayer {
             || msg_clean.contains("waiting");
 
         if is_important {
+             // Attribute the line to the innermost span that knows its session
+             let session_id = ctx
+                 .event_scope(event)
+                 .and_then(|scope| {
+                     scope.into_iter().find_map(|span| span.extensions().get::<SessionTag>().map(|t| t.0.clone()))
+                 })
+                 .unwrap_or_default();
+
              // Use self.logs directly, no need to clone Arc for try_write
              if let Ok(mut q) = self.logs.try_write() {
                  if q.len() >= 1000 { q.pop_front(); }
@@ -127,46 +152,17 @@ impl<S: Subscriber> Layer<S> for Dnp3FrameLayer {
                  let direction = if level == tracing::Level::ERROR { "Error" } else { "System" };
                  let id = self.log_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                  
-                 q.push_back(ProtocolLogEntry {
+                 let entry = ProtocolLogEntry {
                       id,
+                      session_id,
                       timestamp: chrono::Utc::now(),
                       direction: direction.to_string(),
                       message: msg_clean, // Use the cleaned message
                       transaction_id: 0,
-                 });
+                 };
+                 q.push_back(entry.clone());
+                 let _ = self.events.send(LiveEvent::Log(entry));
              }
         }
     }
 }
-
-/// Extract hex bytes from dnp3 library log message
-/// Handle format: "PHYS TX - 15 bytes\n05 64 08 ..."
-fn extract_hex_bytes(msg: &str) -> Option<Vec<u8>> {
-    let mut all_bytes = Vec::new();
-
-    // Tokenize the message by whitespace
-    for word in msg.split_whitespace() {
-        // Strip common punctuation if present (though log seems clean)
-        let clean = word.trim_matches(|c| c == ',' || c == ':' || c == '[' || c == ']');
-        
-        // Attempt to parse 2-character hex strings
-        if clean.len() == 2 {
-            if let Ok(byte) = u8::from_str_radix(clean, 16) {
-                all_bytes.push(byte);
-            }
-        }
-    }
-
-    // DNP3 frames ALWAYS start with 0x05 0x64.
-    // This is the most robust way to find the frame within the log noise.
-    if let Some(start_idx) = all_bytes.windows(2).position(|w| w == [0x05, 0x64]) {
-        let frame_bytes = all_bytes[start_idx..].to_vec();
-        
-        // Basic length check (header is min 10 bytes)
-        if frame_bytes.len() >= 10 {
-            return Some(frame_bytes);
-        }
-    }
-
-    None
-}
@@ -0,0 +1,318 @@
+// DNP3 link layer (IEEE 1815 clause 9) frame decoding.
+// Frame layout: `05 64 LEN CTRL DEST(LE) SRC(LE) CRC` header followed by
+// user data in blocks of up to 16 bytes, each block followed by a 2-byte CRC.
+
+/// Link layer start bytes
+pub const START_BYTES: [u8; 2] = [0x05, 0x64];
+/// Size of the link header including its CRC
+pub const HEADER_SIZE: usize = 10;
+
+const BLOCK_SIZE: usize = 16;
+
+/// DNP3 CRC-16 (polynomial 0x3D65, reflected, inverted output)
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            if crc & 0x0001 != 0 {
+                crc = (crc >> 1) ^ 0xA6BC;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    !crc
+}
+
+fn crc_matches(data: &[u8], crc_bytes: &[u8]) -> bool {
+    crc_bytes.len() == 2 && crc16(data) == u16::from_le_bytes([crc_bytes[0], crc_bytes[1]])
+}
+
+/// Number of user data bytes announced by a LEN field, if the LEN is legal
+fn user_data_len(len: u8) -> Option<usize> {
+    if len < 5 {
+        None
+    } else {
+        Some(len as usize - 5)
+    }
+}
+
+/// Total on-the-wire size of a frame with the given LEN field
+pub fn frame_size_for_len(len: u8) -> Option<usize> {
+    let data_len = user_data_len(len)?;
+    Some(HEADER_SIZE + data_len + 2 * data_len.div_ceil(BLOCK_SIZE))
+}
+
+/// Human readable link function name for a control byte
+pub fn function_name(prm: bool, function: u8) -> &'static str {
+    if prm {
+        match function {
+            0 => "RESET_LINK_STATES",
+            2 => "TEST_LINK_STATES",
+            3 => "CONFIRMED_USER_DATA",
+            4 => "UNCONFIRMED_USER_DATA",
+            9 => "REQUEST_LINK_STATUS",
+            _ => "UNKNOWN",
+        }
+    } else {
+        match function {
+            0 => "ACK",
+            1 => "NACK",
+            11 => "LINK_STATUS",
+            15 => "NOT_SUPPORTED",
+            _ => "UNKNOWN",
+        }
+    }
+}
+
+// --- Decoded Link Frame ---
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LinkFrameInfo {
+    pub length: u8,
+    pub control: u8,
+    pub dir: bool,         // true = sent by a master
+    pub prm: bool,         // true = primary (initiating) frame
+    pub fcb: bool,         // Frame count bit (primary frames)
+    pub fcv: bool,         // Frame count valid (primary) / DFC (secondary)
+    pub function: u8,
+    pub function_name: String,
+    pub destination: u16,
+    pub source: u16,
+    pub header_crc_ok: bool,
+    pub data_crc_ok: Vec<bool>, // One entry per user data block
+    pub crc_ok: bool,           // Header and every data block valid
+    pub payload: Vec<u8>,       // Transport segment with CRCs stripped
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    TooShort(usize),
+    BadStart,
+    BadLength(u8),
+    Truncated { expected: usize, actual: usize },
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::TooShort(n) => write!(f, "frame too short ({} bytes)", n),
+            LinkError::BadStart => write!(f, "missing 05 64 start bytes"),
+            LinkError::BadLength(len) => write!(f, "invalid length field {}", len),
+            LinkError::Truncated { expected, actual } => {
+                write!(f, "truncated frame ({} of {} bytes)", actual, expected)
+            }
+        }
+    }
+}
+
+/// Decode one link frame from the start of `bytes`.
+/// Returns the decoded frame and the number of bytes it occupied.
+pub fn parse_frame(bytes: &[u8]) -> Result<(LinkFrameInfo, usize), LinkError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(LinkError::TooShort(bytes.len()));
+    }
+    if bytes[0..2] != START_BYTES {
+        return Err(LinkError::BadStart);
+    }
+
+    let length = bytes[2];
+    let data_len = user_data_len(length).ok_or(LinkError::BadLength(length))?;
+    let total = frame_size_for_len(length).ok_or(LinkError::BadLength(length))?;
+    if bytes.len() < total {
+        return Err(LinkError::Truncated { expected: total, actual: bytes.len() });
+    }
+
+    let control = bytes[3];
+    let prm = control & 0x40 != 0;
+    let function = control & 0x0F;
+    let header_crc_ok = crc_matches(&bytes[0..8], &bytes[8..10]);
+
+    let mut payload = Vec::with_capacity(data_len);
+    let mut data_crc_ok = Vec::new();
+    let mut pos = HEADER_SIZE;
+    let mut remaining = data_len;
+    while remaining > 0 {
+        let block = remaining.min(BLOCK_SIZE);
+        let data = &bytes[pos..pos + block];
+        data_crc_ok.push(crc_matches(data, &bytes[pos + block..pos + block + 2]));
+        payload.extend_from_slice(data);
+        pos += block + 2;
+        remaining -= block;
+    }
+
+    let crc_ok = header_crc_ok && data_crc_ok.iter().all(|ok| *ok);
+
+    Ok((
+        LinkFrameInfo {
+            length,
+            control,
+            dir: control & 0x80 != 0,
+            prm,
+            fcb: control & 0x20 != 0,
+            fcv: control & 0x10 != 0,
+            function,
+            function_name: function_name(prm, function).to_string(),
+            destination: u16::from_le_bytes([bytes[4], bytes[5]]),
+            source: u16::from_le_bytes([bytes[6], bytes[7]]),
+            header_crc_ok,
+            data_crc_ok,
+            crc_ok,
+            payload,
+        },
+        total,
+    ))
+}
+
+/// Cuts a raw byte stream (TCP or serial) into link frames. Bytes that can't
+/// start a valid header are emitted as their own chunk rather than dropped, so
+/// the capture still accounts for every byte on the wire.
+pub struct LinkFramer {
+    buf: Vec<u8>,
+}
+
+impl LinkFramer {
+    pub fn new() -> Self {
+        Self { buf: Vec::with_capacity(512) }
+    }
+
+    /// Append received bytes and return every complete chunk
+    pub fn push(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        self.buf.extend_from_slice(data);
+        let mut out = Vec::new();
+        let mut junk = Vec::new();
+
+        loop {
+            // Find the first plausible frame start (a trailing 0x05 may still become one)
+            let start = (0..self.buf.len()).find(|&i| {
+                self.buf[i] == START_BYTES[0] && self.buf.get(i + 1).is_none_or(|b| *b == START_BYTES[1])
+            });
+            match start {
+                None => {
+                    junk.append(&mut self.buf);
+                    break;
+                }
+                Some(i) if i > 0 => {
+                    junk.extend(self.buf.drain(..i));
+                    continue;
+                }
+                Some(_) => {}
+            }
+
+            if self.buf.len() < HEADER_SIZE {
+                break; // Wait for the rest of the header
+            }
+            let size = match frame_size_for_len(self.buf[2]) {
+                Some(size) if crc_matches(&self.buf[0..8], &self.buf[8..10]) => size,
+                _ => {
+                    // Not a real header; treat the start byte as noise and resync
+                    junk.push(self.buf.remove(0));
+                    continue;
+                }
+            };
+            if self.buf.len() < size {
+                break; // Wait for the rest of the frame
+            }
+            if !junk.is_empty() {
+                out.push(std::mem::take(&mut junk));
+            }
+            out.push(self.buf.drain(..size).collect());
+        }
+
+        if !junk.is_empty() {
+            out.push(junk);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frame with valid CRCs: header, then user data in 16-byte blocks
+    fn frame(control: u8, destination: u16, source: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x05, 0x64, (data.len() + 5) as u8, control];
+        bytes.extend_from_slice(&destination.to_le_bytes());
+        bytes.extend_from_slice(&source.to_le_bytes());
+        bytes.extend_from_slice(&crc16(&bytes).to_le_bytes());
+        for block in data.chunks(BLOCK_SIZE) {
+            bytes.extend_from_slice(block);
+            bytes.extend_from_slice(&crc16(block).to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn crc_matches_reference_header() {
+        // RESET_LINK_STATES from 1024 to 1, as in the IEEE 1815 examples
+        let header = [0x05, 0x64, 0x05, 0xC0, 0x01, 0x00, 0x00, 0x04];
+        assert_eq!(crc16(&header).to_le_bytes(), [0xE9, 0x21]);
+    }
+
+    #[test]
+    fn parses_valid_frame() {
+        let data: Vec<u8> = (0..20).collect();
+        let bytes = frame(0xC4, 10, 1, &data);
+        let (info, size) = parse_frame(&bytes).unwrap();
+
+        assert_eq!(size, bytes.len());
+        assert_eq!(size, frame_size_for_len(25).unwrap());
+        assert!(info.dir && info.prm);
+        assert_eq!(info.function_name, "UNCONFIRMED_USER_DATA");
+        assert_eq!((info.destination, info.source), (10, 1));
+        assert!(info.crc_ok);
+        assert_eq!(info.data_crc_ok, vec![true, true]);
+        assert_eq!(info.payload, data);
+    }
+
+    #[test]
+    fn reports_crc_errors_per_block() {
+        let mut bytes = frame(0xC4, 10, 1, &[0xAA; 20]);
+        bytes[HEADER_SIZE] ^= 0xFF;
+        let (info, _) = parse_frame(&bytes).unwrap();
+        assert!(info.header_crc_ok);
+        assert_eq!(info.data_crc_ok, vec![false, true]);
+        assert!(!info.crc_ok);
+
+        let mut bytes = frame(0xC4, 10, 1, &[]);
+        bytes[8] ^= 0x01;
+        let (info, _) = parse_frame(&bytes).unwrap();
+        assert!(!info.header_crc_ok && !info.crc_ok);
+    }
+
+    #[test]
+    fn rejects_malformed_frames() {
+        let bytes = frame(0xC4, 10, 1, &[1, 2, 3]);
+        assert_eq!(parse_frame(&bytes[..4]).unwrap_err(), LinkError::TooShort(4));
+        assert_eq!(
+            parse_frame(&bytes[..bytes.len() - 1]).unwrap_err(),
+            LinkError::Truncated { expected: bytes.len(), actual: bytes.len() - 1 }
+        );
+        let mut bad = bytes.clone();
+        bad[2] = 4;
+        assert_eq!(parse_frame(&bad).unwrap_err(), LinkError::BadLength(4));
+        assert_eq!(parse_frame(&[0u8; 12]).unwrap_err(), LinkError::BadStart);
+    }
+
+    #[test]
+    fn framer_joins_a_frame_split_across_reads() {
+        let bytes = frame(0x44, 1, 10, &[0xC0, 0x81, 0x00, 0x00]);
+        let mut framer = LinkFramer::new();
+        assert!(framer.push(&bytes[..7]).is_empty());
+        assert!(framer.push(&bytes[7..12]).is_empty());
+        assert_eq!(framer.push(&bytes[12..]), vec![bytes]);
+    }
+
+    #[test]
+    fn framer_keeps_noise_as_its_own_chunk() {
+        let first = frame(0xC0, 1, 1024, &[]);
+        let second = frame(0x44, 1, 10, &[0xC0, 0x81, 0x00, 0x00]);
+        let mut stream = vec![0x00, 0x05, 0x13];
+        stream.extend_from_slice(&first);
+        stream.extend_from_slice(&second);
+
+        let mut framer = LinkFramer::new();
+        assert_eq!(framer.push(&stream), vec![vec![0x00, 0x05, 0x13], first, second]);
+    }
+}
@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use tokio::sync::RwLock;
 use tracing::{info, warn};
 
@@ -13,14 +13,25 @@ use dnp3::master::*;
 use dnp3::outstation::*;
 use dnp3::outstation::database::*;
 use dnp3::tcp::*;
-use dnp3::serial::{SerialSettings, DataBits, FlowControl, Parity, StopBits};
 
 use crate::models::*;
+use crate::dnp3_link::{self, LinkFrameInfo};
+use crate::point_history::PointHistory;
+use crate::soe::{FragmentInfo, SoeRecord, SoeStore};
+use crate::offline_capture::OfflineSession;
+use crate::frame_capture::{self, CaptureEndpoint, CaptureSink, ShimHandle};
+use crate::serial_proxy::{self, SerialParams};
+use crate::connection_status::StatusTracker;
+use crate::protocol_stats::{ProtocolStats, StatsCounter};
+use crate::outstation_script::{CommandInfo, HookOutcome, OutstationScript, PointWrite};
+use crate::replay::{MeasurementRecording, ReplayHandle, ReplayOptions, ReplayProgress};
+use crate::live_events::{ConnectionEvent, LiveEvent, PointEvent, EVENT_CHANNEL_CAPACITY};
 
 // --- Protocol Log Entry ---
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ProtocolLogEntry {
     pub id: u64, // Global log sequence ID
+    pub session_id: String, // Session that produced the entry; empty for process-wide messages
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub direction: String,
     pub message: String,
@@ -31,9 +42,42 @@ pub struct ProtocolLogEntry {
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct RawFrame {
     pub id: u64, // Global frame sequence ID
+    pub session_id: String, // Session whose channel produced the frame
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub timestamp_us: i64, // Capture time, microseconds since the Unix epoch
     pub direction: String, // "TX" or "RX"
-    pub data: Vec<u8>,     // Raw binary data
+    pub data: Vec<u8>,     // Raw binary data, exactly as read from the socket/port
+    pub endpoint: Option<CaptureEndpoint>, // Connection the bytes travelled on
+    pub link: Option<LinkFrameInfo>,  // Decoded link header, CRC status and payload
+    pub decode_error: Option<String>, // Why `link` could not be decoded
+}
+
+impl RawFrame {
+    /// Build a frame record, decoding the link layer from the raw bytes
+    pub fn new(
+        id: u64,
+        session_id: &str,
+        direction: &str,
+        data: Vec<u8>,
+        timestamp: chrono::DateTime<chrono::Utc>,
+        endpoint: Option<CaptureEndpoint>,
+    ) -> Self {
+        let (link, decode_error) = match dnp3_link::parse_frame(&data) {
+            Ok((info, _)) => (Some(info), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Self {
+            id,
+            session_id: session_id.to_string(),
+            timestamp,
+            timestamp_us: timestamp.timestamp_micros(),
+            direction: direction.to_string(),
+            data,
+            endpoint,
+            link,
+            decode_error,
+        }
+    }
 }
 
 // --- Log Store (Shared between Master and Outstation) ---
@@ -42,6 +86,14 @@ pub struct LogStore {
     pub log_counter: Arc<std::sync::atomic::AtomicU64>,
     pub raw_frames: Arc<RwLock<VecDeque<RawFrame>>>,
     pub frame_counter: Arc<std::sync::atomic::AtomicU64>,
+    // Imported captures, keyed by "offline-<n>"; read-only once stored
+    pub offline_sessions: Arc<RwLock<HashMap<String, OfflineSession>>>,
+    pub offline_counter: Arc<std::sync::atomic::AtomicU64>,
+    // Loopback address -> session id. dnp3 names the address in its task spans,
+    // which lets the tracing layer attribute library log lines to a session.
+    pub session_endpoints: Arc<std::sync::RwLock<HashMap<String, String>>>,
+    // Live feed for /api/stream subscribers
+    pub events: tokio::sync::broadcast::Sender<LiveEvent>,
 }
 
 impl LogStore {
@@ -51,15 +103,94 @@ impl LogStore {
             log_counter: Arc::new(std::sync::atomic::AtomicU64::new(0)),
             raw_frames: Arc::new(RwLock::new(VecDeque::with_capacity(500))),
             frame_counter: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            offline_sessions: Arc::new(RwLock::new(HashMap::new())),
+            offline_counter: Arc::new(std::sync::atomic::AtomicU64::new(1)),
+            session_endpoints: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            events: tokio::sync::broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Send an event to stream subscribers; a no-op when nobody is listening
+    pub fn publish(&self, event: LiveEvent) {
+        let _ = self.events.send(event);
+    }
+
+    pub fn publish_point(&self, session_id: &str, point: &DataPoint) {
+        self.publish(LiveEvent::Point(PointEvent::new(session_id, point)));
+    }
+
+    pub fn publish_connection(&self, session_id: &str, state: &str, message: &str) {
+        self.publish(LiveEvent::Connection(ConnectionEvent {
+            session_id: session_id.to_string(),
+            state: state.to_string(),
+            message: message.to_string(),
+            timestamp: chrono::Utc::now(),
+        }));
+    }
+
+    pub fn register_endpoint(&self, addr: std::net::SocketAddr, session_id: &str) {
+        if let Ok(mut map) = self.session_endpoints.write() {
+            map.insert(addr.to_string(), session_id.to_string());
+        }
+    }
+
+    pub fn unregister_session(&self, session_id: &str) {
+        if let Ok(mut map) = self.session_endpoints.write() {
+            map.retain(|_, s| s != session_id);
         }
     }
+
+    /// Append a log entry. The id is taken while holding the lock, so ids are
+    /// strictly increasing in ring order and usable as a `since` cursor.
+    pub async fn push_log(&self, session_id: &str, direction: &str, message: &str, transaction_id: u32) {
+        let mut logs = self.logs.write().await;
+        if logs.len() >= 1000 {
+            logs.pop_front();
+        }
+        let id = self.log_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let entry = ProtocolLogEntry {
+            id,
+            session_id: session_id.to_string(),
+            timestamp: chrono::Utc::now(),
+            direction: direction.to_string(),
+            message: message.to_string(),
+            transaction_id,
+        };
+        logs.push_back(entry.clone());
+        self.publish(LiveEvent::Log(entry));
+    }
+
+    /// Record a captured frame. Awaits the lock so no frame is dropped under contention.
+    pub async fn push_frame(
+        &self,
+        session_id: &str,
+        direction: &str,
+        data: Vec<u8>,
+        timestamp: chrono::DateTime<chrono::Utc>,
+        endpoint: Option<CaptureEndpoint>,
+    ) {
+        let mut frames = self.raw_frames.write().await;
+        if frames.len() >= 500 {
+            frames.pop_front();
+        }
+        let id = self.frame_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let frame = RawFrame::new(id, session_id, direction, data, timestamp, endpoint);
+        frames.push_back(frame.clone());
+        self.publish(LiveEvent::Frame(frame));
+    }
 }
 
 // --- DNP3 Service State ---
 pub struct Dnp3Service {
+    pub session_id: String,
     pub data_points: Arc<RwLock<Vec<DataPoint>>>,
-    pub stats: Arc<RwLock<Statistics>>,
+    pub stats: StatsCounter,
     pub connected: Arc<RwLock<bool>>,
+    // Channel state from the dnp3 listeners and the capture shim
+    pub status: StatusTracker,
+    pub history: Arc<std::sync::Mutex<PointHistory>>,
+    // Written synchronously from ReadHandler callbacks to keep arrival order
+    pub soe: Arc<std::sync::Mutex<SoeStore>>,
     
     // Shared Logs/Frames
     pub log_store: Arc<LogStore>,
@@ -71,32 +202,40 @@ pub struct Dnp3Service {
     // Outstation components  
     outstation_server: Arc<RwLock<Option<dnp3::tcp::ServerHandle>>>,
     outstation_handle: Arc<RwLock<Option<OutstationHandle>>>,
-}
 
-#[derive(Debug, Clone, Default)]
-pub struct Statistics {
-    pub tx_count: u32,
-    pub rx_count: u32,
-    pub error_count: u32,
+    // Byte-stream tap between the dnp3 channel and the socket/serial port
+    capture_shim: Arc<RwLock<Option<ShimHandle>>>,
+
+    // Recorded measurements being played into the outstation database
+    replay: Arc<std::sync::Mutex<Option<ReplayHandle>>>,
 }
 
 impl Dnp3Service {
-    pub fn new(log_store: Arc<LogStore>) -> Self {
+    pub fn new(session_id: &str, log_store: Arc<LogStore>) -> Self {
         Self {
+            session_id: session_id.to_string(),
             data_points: Arc::new(RwLock::new(Vec::new())),
-            stats: Arc::new(RwLock::new(Statistics::default())),
+            stats: StatsCounter::new(),
             connected: Arc::new(RwLock::new(false)),
+            status: StatusTracker::new(session_id, log_store.clone()),
+            history: Arc::new(std::sync::Mutex::new(PointHistory::new())),
+            soe: Arc::new(std::sync::Mutex::new(SoeStore::new())),
             log_store,
             master_channel: Arc::new(RwLock::new(None)),
             master_association: Arc::new(RwLock::new(None)),
             outstation_server: Arc::new(RwLock::new(None)),
             outstation_handle: Arc::new(RwLock::new(None)),
+            capture_shim: Arc::new(RwLock::new(None)),
+            replay: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 
     pub async fn update_config(&self, config: DeviceConfiguration) {
         let mut points = self.data_points.write().await;
         points.clear();
+        if let Ok(mut history) = self.history.lock() {
+            history.clear();
+        }
 
         info!("Updating device configuration: {:?}", config.name);
 
@@ -201,6 +340,9 @@ impl Dnp3Service {
         let mut points = self.data_points.write().await;
         let count = points.len();
         points.clear();
+        if let Ok(mut history) = self.history.lock() {
+            history.clear();
+        }
         info!("🗑️  Cleared all {} data points", count);
     }
 
@@ -212,6 +354,8 @@ impl Dnp3Service {
             *channel_lock = None; // Drop existing channel
             let mut assoc_lock = self.master_association.write().await;
             *assoc_lock = None; // Drop existing association
+            *self.capture_shim.write().await = None; // Stop forwarding to the old peer
+            self.log_store.unregister_session(&self.session_id);
              // Wait a bit for resources to be released
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
             
@@ -231,69 +375,46 @@ impl Dnp3Service {
                 .map_err(|e| format!("Invalid local address: {}", e))?
         );
         
-        // Enable FULL protocol decoding - dnp3 library will output hex dumps
+        // Enable protocol decoding in the library log; raw bytes come from the capture shim
         channel_config.decode_level = DecodeLevel {
             application: AppDecodeLevel::ObjectValues,
             transport: TransportDecodeLevel::Payload,  // Show hex payload
             link: LinkDecodeLevel::Payload,            // Show link layer hex
-            physical: PhysDecodeLevel::Data,           // Show raw TX/RX hex
+            physical: PhysDecodeLevel::Nothing,        // Frames are captured by frame_capture
         };
 
-        // Decide transport: Serial or TCP
-        let mut channel = match config.connection_type {
+        // Decide transport: Serial or TCP. Either way the dnp3 channel talks TCP to a
+        // loopback capture shim, which forwards the bytes to the real peer.
+        self.status.start("master", "connecting", "Master starting");
+        let sink = self.capture_sink();
+        let (shim, link_error_mode) = match config.connection_type {
             crate::models::ConnectionType::Serial => {
-                // Map configuration to SerialSettings
-                let port = config.serial_port.as_ref().ok_or("Serial port not configured")?;
-                let baud = config.baud_rate.unwrap_or(9600);
-                let data_bits = match config.data_bits.unwrap_or(8) {
-                    5 => DataBits::Five,
-                    6 => DataBits::Six,
-                    7 => DataBits::Seven,
-                    _ => DataBits::Eight,
-                };
-                let parity = match config.parity.as_deref().unwrap_or("none").to_lowercase().as_str() {
-                    "even" => Parity::Even,
-                    "odd" => Parity::Odd,
-                    _ => Parity::None,
-                };
-                let stop_bits = match config.stop_bits.unwrap_or(1.0) {
-                    x if (x - 2.0).abs() < f32::EPSILON => StopBits::Two,
-                    x if (x - 1.5).abs() < f32::EPSILON => StopBits::One, // Note: dnp3 doesn't have OnePointFive
-                    _ => StopBits::One,
-                };
-
-                let serial_settings = SerialSettings {
-                    baud_rate: baud,
-                    data_bits,
-                    flow_control: FlowControl::None,
-                    stop_bits,
-                    parity,
-                };
-
-                // Path is a single &str, not a Vec
-                let path = port.as_str();
-
-                // Spawn master using serial transport. Retry delay 1s.
-                dnp3::serial::spawn_master_serial(
-                    channel_config,
-                    path,
-                    serial_settings,
-                    std::time::Duration::from_secs(1),
-                    NullListener::create(),
-                )
+                let params = SerialParams::from_config(config)?;
+                let shim = serial_proxy::start_serial_proxy_server(params, sink)
+                    .await
+                    .map_err(|e| format!("Failed to start serial bridge: {}", e))?;
+                // Serial lines can carry noise; skip it instead of dropping the channel
+                (shim, LinkErrorMode::Discard)
             }
             _ => {
-                // TCP client channel
-                spawn_master_tcp_client(
-                    LinkErrorMode::Close,
-                    channel_config,
-                    EndpointList::new(format!("{}:{}", config.ip_address, config.port), &[]),
-                    ConnectStrategy::default(),
-                    NullListener::create(),
-                )
+                let remote = format!("{}:{}", config.ip_address, config.port);
+                let shim = frame_capture::start_client_shim(remote, sink)
+                    .await
+                    .map_err(|e| format!("Failed to start capture shim: {}", e))?;
+                (shim, LinkErrorMode::Close)
             }
         };
 
+        self.log_store.register_endpoint(shim.local_addr, &self.session_id);
+        let mut channel = spawn_master_tcp_client(
+            link_error_mode,
+            channel_config,
+            EndpointList::new(shim.local_addr.to_string(), &[]),
+            ConnectStrategy::default(),
+            self.status.client_listener(),
+        );
+        *self.capture_shim.write().await = Some(shim);
+
         // Create association configuration
         let mut assoc_config = AssociationConfig::new(
             EventClasses::all(),      // Disable unsolicited responses initially
@@ -307,8 +428,10 @@ impl Dnp3Service {
         // Create ReadHandler with shared state
         let read_handler = Box::new(MasterReadHandler::new(
             self.data_points.clone(),
-            self.log_store.logs.clone(),
-            self.stats.clone(),
+            self.history.clone(),
+            self.soe.clone(),
+            self.log_store.clone(),
+            self.session_id.clone(),
         ));
 
         // Add association
@@ -344,12 +467,26 @@ impl Dnp3Service {
 
     /// Start Outstation - Creates TCP server listening for Master
     pub async fn start_outstation(&self, config: &Configuration) -> Result<(), String> {
+        // Compile the behaviour script before touching the running outstation
+        let script = match &config.script {
+            Some(path) => Some(ScriptContext {
+                script: Arc::new(OutstationScript::load(std::path::Path::new(path))?),
+                data_points: self.data_points.clone(),
+                outstation: self.outstation_handle.clone(),
+                log_store: self.log_store.clone(),
+                session_id: self.session_id.clone(),
+            }),
+            None => None,
+        };
+
         // Cleanup existing outstation resources
         {
             let mut server_lock = self.outstation_server.write().await;
             *server_lock = None; // Drop existing server handle (stops listening)
             let mut handle_lock = self.outstation_handle.write().await;
             *handle_lock = None; 
+            *self.capture_shim.write().await = None; // Release the listening port
+            self.log_store.unregister_session(&self.session_id);
             // Wait a bit for port to be released
             tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
             
@@ -371,206 +508,149 @@ impl Dnp3Service {
                 .map_err(|e| format!("Invalid remote address: {}", e))?,
             event_buffer_config(),
         );
-        // Enable FULL protocol decoding - dnp3 library will output hex dumps
+        // Enable protocol decoding in the library log; raw bytes come from the capture shim
         outstation_config.decode_level = DecodeLevel {
             application: AppDecodeLevel::ObjectValues,
             transport: TransportDecodeLevel::Payload,
             link: LinkDecodeLevel::Payload,
-            physical: PhysDecodeLevel::Data,
+            physical: PhysDecodeLevel::Nothing,
         };
 
         // Create handlers with shared state
         let control_handler = Box::new(OutstationControlHandler::new(
             self.data_points.clone(),
-            self.log_store.logs.clone(),
+            self.log_store.clone(),
             self.stats.clone(),
+            self.session_id.clone(),
+            script.clone(),
         ));
 
-        // Decide transport: Serial or TCP server
-        match config.connection_type {
-            crate::models::ConnectionType::Serial => {
-                let port = config.serial_port.as_ref().ok_or("Serial port not configured")?;
-                let baud = config.baud_rate.unwrap_or(9600);
-                let data_bits = match config.data_bits.unwrap_or(8) {
-                    5 => DataBits::Five,
-                    6 => DataBits::Six,
-                    7 => DataBits::Seven,
-                    _ => DataBits::Eight,
-                };
-                let parity = match config.parity.as_deref().unwrap_or("none").to_lowercase().as_str() {
-                    "even" => Parity::Even,
-                    "odd" => Parity::Odd,
-                    _ => Parity::None,
-                };
-                let stop_bits = match config.stop_bits.unwrap_or(1.0) {
-                    x if (x - 2.0).abs() < f32::EPSILON => StopBits::Two,
-                    x if (x - 1.5).abs() < f32::EPSILON => StopBits::One, // Note: dnp3 doesn't have OnePointFive
-                    _ => StopBits::One,
-                };
-
-                let serial_settings = SerialSettings {
-                    baud_rate: baud,
-                    data_bits,
-                    flow_control: FlowControl::None,
-                    stop_bits,
-                    parity,
-                };
-
-                let path = port.as_str();
-
-                // Spawn outstation over serial. This returns a Result<OutstationHandle, _>
-                let outstation = dnp3::serial::spawn_outstation_serial(
-                    path,
-                    serial_settings,
-                    outstation_config,
-                    Box::new(OutstationApp),
-                    Box::new(OutstationInfo),
-                    control_handler,
-                ).map_err(|e| format!("Failed to spawn outstation on serial {}: {}", port, e))?;
-
-                // Initialize outstation database with current data points
-                let points = self.data_points.read().await;
-                outstation.transaction(|db| {
-                    for point in points.iter() {
-                        match point.point_type {
-                            DataPointType::BinaryInput => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    BinaryInputConfig::default(),
-                                );
-                            }
-                            DataPointType::BinaryOutput => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    BinaryOutputStatusConfig::default(),
-                                );
-                            }
-                            DataPointType::AnalogInput => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    AnalogInputConfig {
-                                        s_var: StaticAnalogInputVariation::Group30Var5,
-                                        e_var: EventAnalogInputVariation::Group32Var5,
-                                        deadband: 0.0,
-                                    },
-                                );
-                            }
-                            DataPointType::AnalogOutput => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    AnalogOutputStatusConfig::default(),
-                                );
-                            }
-                            DataPointType::Counter => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    CounterConfig::default(),
-                                );
-                            }
-                        }
+        // The dnp3 server always listens on a private loopback port; a capture shim
+        // owns the real endpoint (TCP listener or serial port) and forwards to it.
+        // Port 0 lets the OS pick it when the server binds, so nothing can take it first.
+        let loopback = std::net::SocketAddr::from(([127, 0, 0, 1], 0));
+        let link_error_mode = match config.connection_type {
+            // Serial lines can carry noise; skip it instead of dropping the session
+            crate::models::ConnectionType::Serial => LinkErrorMode::Discard,
+            _ => LinkErrorMode::Close,
+        };
+        self.status.start("outstation", "waiting", "Waiting for a master");
+        let mut server = Server::new_tcp_server(link_error_mode, loopback);
+
+        // Add outstation to server
+        let outstation = server.add_outstation(
+            outstation_config,
+            Box::new(OutstationApp { script: script.clone() }),
+            Box::new(OutstationInfo),
+            control_handler,
+            self.status.connection_listener(),
+            AddressFilter::Any,
+        ).map_err(|e| format!("Failed to add outstation: {}", e))?;
+
+        // Initialize outstation database with current data points
+        let points = self.data_points.read().await;
+        outstation.transaction(|db| {
+            for point in points.iter() {
+                match point.point_type {
+                    DataPointType::BinaryInput => {
+                        db.add(
+                            point.index,
+                            Some(EventClass::Class1),
+                            BinaryInputConfig::default(),
+                        );
                     }
-                });
-
-                *self.outstation_handle.write().await = Some(outstation.clone());
-                *self.connected.write().await = true;
+                    DataPointType::BinaryOutput => {
+                        db.add(
+                            point.index,
+                            Some(EventClass::Class1),
+                            BinaryOutputStatusConfig::default(),
+                        );
+                    }
+                    DataPointType::AnalogInput => {
+                        db.add(
+                            point.index,
+                            Some(EventClass::Class1),
+                            AnalogInputConfig {
+                                s_var: StaticAnalogInputVariation::Group30Var5,
+                                e_var: EventAnalogInputVariation::Group32Var5,
+                                deadband: 0.0,
+                            },
+                        );
+                    }
+                    DataPointType::AnalogOutput => {
+                        db.add(
+                            point.index,
+                            Some(EventClass::Class1),
+                            AnalogOutputStatusConfig::default(),
+                        );
+                    }
+                    DataPointType::Counter => {
+                        db.add(
+                            point.index,
+                            Some(EventClass::Class1),
+                            CounterConfig::default(),
+                        );
+                    }
+                }
+            }
+        });
+        if let Some(ctx) = &script {
+            ctx.script.sync_points(&points);
+        }
+        drop(points);
 
-                // Spawn simulation task to update outstation data periodically
-                self.spawn_outstation_simulation(outstation).await;
+        let server_handle = server.bind().await.map_err(|e| format!("Failed to bind server: {}", e))?;
+        let internal = server_handle.local_addr().ok_or("Failed to read the loopback server address")?;
+        self.log_store.register_endpoint(internal, &self.session_id);
 
-                self.add_log("System", &format!("Outstation started on serial {}", port), 0).await;
-                Ok(())
+        // Decide transport: Serial or TCP server
+        let sink = self.capture_sink();
+        let (shim, started_msg) = match config.connection_type {
+            crate::models::ConnectionType::Serial => {
+                let params = SerialParams::from_config(config)?;
+                let msg = format!("Outstation started on serial {}", params.device);
+                let shim = serial_proxy::start_serial_proxy_client(params, internal, sink)
+                    .await
+                    .map_err(|e| format!("Failed to start serial bridge: {}", e))?;
+                (shim, msg)
             }
             _ => {
-                // TCP server path (existing behavior)
-                let mut server = Server::new_tcp_server(
-                    LinkErrorMode::Close,
-                    format!("{}:{}", config.ip_address, config.port).parse()
-                        .map_err(|e| format!("Invalid address: {}", e))?,
-                );
-
-                // Add outstation to server
-                let outstation = server.add_outstation(
-                    outstation_config,
-                    Box::new(OutstationApp),
-                    Box::new(OutstationInfo),
-                    control_handler,
-                    NullListener::create(),
-                    AddressFilter::Any,
-                ).map_err(|e| format!("Failed to add outstation: {}", e))?;
-
-                // Initialize outstation database with current data points
-                let points = self.data_points.read().await;
-                outstation.transaction(|db| {
-                    for point in points.iter() {
-                        match point.point_type {
-                            DataPointType::BinaryInput => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    BinaryInputConfig::default(),
-                                );
-                            }
-                            DataPointType::BinaryOutput => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    BinaryOutputStatusConfig::default(),
-                                );
-                            }
-                            DataPointType::AnalogInput => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    AnalogInputConfig {
-                                        s_var: StaticAnalogInputVariation::Group30Var5,
-                                        e_var: EventAnalogInputVariation::Group32Var5,
-                                        deadband: 0.0,
-                                    },
-                                );
-                            }
-                            DataPointType::AnalogOutput => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    AnalogOutputStatusConfig::default(),
-                                );
-                            }
-                            DataPointType::Counter => {
-                                db.add(
-                                    point.index,
-                                    Some(EventClass::Class1),
-                                    CounterConfig::default(),
-                                );
-                            }
-                        }
-                    }
-                });
-                drop(points);
-
-                let server_handle = server.bind().await.map_err(|e| format!("Failed to bind server: {}", e))?;
+                let bind = format!("{}:{}", config.ip_address, config.port).parse()
+                    .map_err(|e| format!("Invalid address: {}", e))?;
+                let shim = frame_capture::start_server_shim(bind, internal, sink)
+                    .await
+                    .map_err(|e| format!("Failed to bind server: {}", e))?;
+                (shim, "Outstation started".to_string())
+            }
+        };
 
-                *self.outstation_server.write().await = Some(server_handle);
-                *self.outstation_handle.write().await = Some(outstation.clone());
-                *self.connected.write().await = true;
+        *self.capture_shim.write().await = Some(shim);
+        *self.outstation_server.write().await = Some(server_handle);
+        *self.outstation_handle.write().await = Some(outstation.clone());
+        *self.connected.write().await = true;
 
-                // Spawn simulation task to update outstation data periodically
-                self.spawn_outstation_simulation(outstation).await;
+        // Spawn simulation task to update outstation data periodically
+        self.spawn_outstation_simulation(outstation, script.clone()).await;
 
-                self.add_log("System", "Outstation started", 0).await;
-                Ok(())
-            }
+        self.add_log("System", &started_msg, 0).await;
+        if let Some(ctx) = script {
+            self.add_log("System", &format!("Script {} loaded", ctx.script.name), 0).await;
+            // Initial values and log lines from the script's top level
+            ctx.finish(ctx.script.take_pending(), false);
         }
+        Ok(())
     }
 
-    /// Outstation simulation - Updates data points periodically
-    async fn spawn_outstation_simulation(&self, outstation: OutstationHandle) {
+    /// Outstation simulation - Updates data points periodically. A script
+    /// with on_tick replaces the random input values.
+    async fn spawn_outstation_simulation(&self, outstation: OutstationHandle, script: Option<ScriptContext>) {
         let data_points = self.data_points.clone();
+        let history = self.history.clone();
         let connected = self.connected.clone();
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
+        let scripted = script.as_ref().is_some_and(|ctx| ctx.script.has_hook("on_tick"));
+        let replay = self.replay.clone();
 
         tokio::spawn(async move {
             loop {
@@ -580,13 +660,26 @@ impl Dnp3Service {
 
                 tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
 
+                // A replay owns the database until it is stopped
+                if replay.lock().is_ok_and(|r| r.is_some()) {
+                    continue;
+                }
+
                 // Update random data points
                 let mut points = data_points.write().await;
                 for point in points.iter_mut() {
+                    // Flags set by the script (e.g. COMM_LOST) stick until it changes them
+                    let flags = match &script {
+                        Some(ctx) => Flags::new(ctx.script.flags(point.point_type, point.index)),
+                        None => Flags::ONLINE,
+                    };
+                    let quality = if flags.value & Flags::ONLINE.value != 0 { DataQuality::Online } else { DataQuality::Offline };
                     match point.point_type {
                         DataPointType::AnalogInput => {
-                            point.value = 200.0 + (fastrand::f64() * 50.0) + (fastrand::f64() * 0.99); // Add fractional part
-                            point.quality = DataQuality::Online;
+                            if !scripted {
+                                point.value = 200.0 + (fastrand::f64() * 50.0) + (fastrand::f64() * 0.99); // Add fractional part
+                            }
+                            point.quality = quality;
                             point.timestamp = chrono::Utc::now();
                             
                             // Update outstation database
@@ -595,7 +688,7 @@ impl Dnp3Service {
                                     point.index,
                                     &AnalogInput::new(
                                         point.value,
-                                        Flags::ONLINE,
+                                        flags,
                                         Time::synchronized(point.timestamp.timestamp_millis().try_into().unwrap()),
                                     ),
                                     UpdateOptions::detect_event(),
@@ -603,14 +696,16 @@ impl Dnp3Service {
                             });
                         }
                         DataPointType::Counter => {
-                            point.value += fastrand::f64() * 10.0;
-                            point.quality = DataQuality::Online;
+                            if !scripted {
+                                point.value += fastrand::f64() * 10.0;
+                            }
+                            point.quality = quality;
                             point.timestamp = chrono::Utc::now();
                             
                             outstation.transaction(|db| {
                                 db.update(
                                     point.index,
-                                    &Counter::new(point.value as u32, Flags::ONLINE, Time::synchronized(point.timestamp.timestamp_millis().try_into().unwrap())),
+                                    &Counter::new(point.value as u32, flags, Time::synchronized(point.timestamp.timestamp_millis().try_into().unwrap())),
                                     UpdateOptions::detect_event(),
                                 );
                             });
@@ -618,9 +713,11 @@ impl Dnp3Service {
                         DataPointType::BinaryInput => {
                              // Keep value (or could toggle), assure ONLINE
                              // Simulate a boolean change and mark point Online
-                             let val = if fastrand::f64() > 0.5 { 1.0 } else { 0.0 };
-                             point.value = val;
-                             point.quality = DataQuality::Online;
+                             if !scripted {
+                                 point.value = if fastrand::f64() > 0.5 { 1.0 } else { 0.0 };
+                             }
+                             let val = point.value;
+                             point.quality = quality;
                              point.timestamp = chrono::Utc::now();
 
                              outstation.transaction(|db| {
@@ -628,7 +725,7 @@ impl Dnp3Service {
                                      point.index,
                                      &BinaryInput::new(
                                          val > 0.5,
-                                         Flags::ONLINE,
+                                         flags,
                                          Time::synchronized(point.timestamp.timestamp_millis().try_into().unwrap()),
                                      ),
                                      UpdateOptions::detect_event(),
@@ -639,7 +736,7 @@ impl Dnp3Service {
                              // Do NOT randomize BinaryOutput here. AO/BO must only change
                              // in response to control operations. Ensure DB reflects the
                              // current point value/status (read-only sync).
-                             point.quality = DataQuality::Online;
+                             point.quality = quality;
                              point.timestamp = chrono::Utc::now();
                              let status = point.value > 0.5;
                              let ts = Time::synchronized(point.timestamp.timestamp_millis().try_into().unwrap());
@@ -649,7 +746,7 @@ impl Dnp3Service {
                                      point.index,
                                      &BinaryOutputStatus::new(
                                          status,
-                                         Flags::ONLINE,
+                                         flags,
                                          ts,
                                      ),
                                      UpdateOptions::detect_event(),
@@ -659,7 +756,7 @@ impl Dnp3Service {
                         DataPointType::AnalogOutput => {
                              // Do NOT randomize AnalogOutput. Only reflect current value
                              // set by control operations or manual edits.
-                             point.quality = DataQuality::Online;
+                             point.quality = quality;
                              point.timestamp = chrono::Utc::now();
                              let val = point.value;
                              let ts = Time::synchronized(point.timestamp.timestamp_millis().try_into().unwrap());
@@ -669,7 +766,7 @@ impl Dnp3Service {
                                      point.index,
                                      &AnalogOutputStatus::new(
                                          val,
-                                         Flags::ONLINE,
+                                         flags,
                                          ts,
                                      ),
                                      UpdateOptions::detect_event(),
@@ -679,29 +776,82 @@ impl Dnp3Service {
                         _ => {}
                     }
                 }
+
+                // Script tick: its writes land in this cycle, on top of the values above
+                if let Some(ctx) = &script {
+                    ctx.script.sync_points(&points);
+                    if scripted {
+                        if let Some(mut outcome) = ctx.report(ctx.script.on_tick()) {
+                            let writes = std::mem::take(&mut outcome.writes);
+                            outstation.transaction(|db| {
+                                for write in &writes {
+                                    update_database(db, write, UpdateOptions::detect_event());
+                                }
+                            });
+                            for write in &writes {
+                                if let Some(point) = points.iter_mut().find(|p| p.point_type == write.point_type && p.index == write.index) {
+                                    apply_write(point, write);
+                                    if matches!(point.point_type, DataPointType::BinaryOutput | DataPointType::AnalogOutput) {
+                                        log_store.publish_point(&session_id, point);
+                                    }
+                                }
+                            }
+                            ctx.finish(outcome, true);
+                        }
+                    }
+                }
+
+                if let Ok(mut hist) = history.lock() {
+                    for point in points.iter() {
+                        let flags = match &script {
+                            Some(ctx) => ctx.script.flags(point.point_type, point.index),
+                            None => Flags::ONLINE.value,
+                        };
+                        let changed = hist.record(point.point_type, point.index, point.value, flags, point.timestamp, None);
+                        // Outputs only change through control handlers, which publish themselves
+                        let is_output = matches!(point.point_type, DataPointType::BinaryOutput | DataPointType::AnalogOutput);
+                        if changed && !is_output {
+                            log_store.publish_point(&session_id, point);
+                        }
+                    }
+                }
             }
         });
     }
 
     /// Manual read request (Master mode)
     pub async fn read_all(&self) -> Result<(), String> {
+        self.read_classes(Classes::all()).await
+    }
+
+    /// Class scan of the selected classes (Master mode)
+    pub async fn read_classes(&self, classes: Classes) -> Result<(), String> {
         let mut assoc_guard = self.master_association.write().await;
 
         if let Some(ref mut assoc) = *assoc_guard {
-            self.add_log("TX", "READ Class 0,1,2,3 (Integrity Poll)", 0).await;
-            assoc.read(ReadRequest::class_scan(Classes::all()))
+            let selected: Vec<&str> = [
+                (classes.class0, "0"),
+                (classes.events.class1, "1"),
+                (classes.events.class2, "2"),
+                (classes.events.class3, "3"),
+            ]
+            .iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, name)| *name)
+            .collect();
+            let label = if selected.len() == 4 { " (Integrity Poll)" } else { "" };
+            self.add_log("TX", &format!("READ Class {}{}", selected.join(","), label), 0).await;
+            assoc.read(ReadRequest::class_scan(classes))
                 .await
-                .map_err(|e| format!("Read failed: {}", e))?;
-            let mut stats = self.stats.write().await;
-            stats.tx_count += 1;
+                .map_err(|e| {
+                    self.stats.record_task_error(&e);
+                    format!("Read failed: {}", e)
+                })?;
             Ok(())
         } else {
             // If no real association but service is connected (e.g. serial-sim), simulate a read
             if *self.connected.read().await {
                 self.add_log("TX", "Simulated READ (serial)", 0).await;
-                let mut stats = self.stats.write().await;
-                stats.tx_count += 1;
-                stats.rx_count += 1;
                 Ok(())
             } else {
                 Err("Master not connected".to_string())
@@ -750,7 +900,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Direct Operate failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Direct Operate failed", e))?;
                         }
                         "DirectNoAck" => {
                             // FC 0x06: Direct Operate No Ack
@@ -763,7 +913,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Direct Operate No Ack failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Direct Operate No Ack failed", e))?;
                         }
                         "Select" => {
                             // FC 0x03: Select (part 1 of SBO)
@@ -778,7 +928,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Select failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Select failed", e))?;
                             
                             info!("Select + Operate completed by library");
                         }
@@ -793,7 +943,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Operate failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Operate failed", e))?;
                         }
                         "SBO" => {
                             // Auto SBO: Select (0x03) + Operate (0x04) in one call
@@ -804,7 +954,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("SBO failed: {}", e))?;
+                            .map_err(|e| self.control_failed("SBO failed", e))?;
                         }
                         _ => {
                             // Default: Direct Operate (FC 0x05)
@@ -815,7 +965,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Control failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Control failed", e))?;
                         }
                     }
                 }
@@ -832,7 +982,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Direct Operate failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Direct Operate failed", e))?;
                         }
                         "DirectNoAck" => {
                             // FC 0x06: Direct Operate No Ack
@@ -845,7 +995,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Direct Operate No Ack failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Direct Operate No Ack failed", e))?;
                         }
                         "Select" => {
                             info!("Sending Select (FC 0x03) for AnalogOutput[{}]", index);
@@ -855,7 +1005,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Select failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Select failed", e))?;
                         }
                         "Operate" => {
                             info!("Sending Operate (simulated FC 0x04) for AnalogOutput[{}]", index);
@@ -865,7 +1015,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Operate failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Operate failed", e))?;
                         }
                         "SBO" => {
                             info!("Sending Auto-SBO for AnalogOutput[{}]", index);
@@ -875,7 +1025,7 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("SBO failed: {}", e))?;
+                            .map_err(|e| self.control_failed("SBO failed", e))?;
                         }
                         _ => {
                             info!("Sending Direct Operate (FC 0x05) for AnalogOutput[{}]", index);
@@ -885,22 +1035,21 @@ impl Dnp3Service {
                                 builder
                             )
                             .await
-                            .map_err(|e| format!("Control failed: {}", e))?;
+                            .map_err(|e| self.control_failed("Control failed", e))?;
                         }
                     }
                 }
                 _ => return Err("Unsupported control point type".to_string()),
             }
 
-            let mut stats = self.stats.write().await;
-            stats.tx_count += 1;
-            stats.rx_count += 1;
+            self.stats.record_control(Ok(()));
             
             // Optional verification read (skip for Select to preserve SBO semantics)
             if op_mode != "Select" {
                 tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
                 
                 if let Err(e) = assoc.read(ReadRequest::class_scan(Classes::all())).await {
+                     self.stats.record_task_error(&e);
                      warn!("Verification read failed: {}", e);
                 }
             }
@@ -915,11 +1064,10 @@ impl Dnp3Service {
                         point.value = value;
                         point.quality = DataQuality::Online;
                         point.timestamp = chrono::Utc::now();
+                        self.log_store.publish_point(&self.session_id, point);
                         break;
                     }
                 }
-                let mut stats = self.stats.write().await;
-                stats.tx_count += 1;
                 Ok(format!("{} Control executed (simulated)", op_mode))
             } else {
                 Err("Master not connected".to_string())
@@ -938,28 +1086,86 @@ impl Dnp3Service {
         // Clear Outstation components
         *self.outstation_server.write().await = None;
         *self.outstation_handle.write().await = None;
+
+        // Stop the capture shim (closes the socket / serial port)
+        *self.capture_shim.write().await = None;
+        self.stop_replay();
+        self.log_store.unregister_session(&self.session_id);
         
+        self.status.set_state("shutdown", "Disconnected", None);
         self.add_log("System", "Disconnected", 0).await;
         info!("Disconnected");
     }
 
+    /// Play a recorded master session into this outstation's database
+    pub async fn start_replay(&self, recording: MeasurementRecording, options: ReplayOptions) -> Result<ReplayProgress, String> {
+        options.validate()?;
+        if self.outstation_handle.read().await.is_none() {
+            return Err("Replay needs a running outstation in this session".to_string());
+        }
+        let progress = Arc::new(std::sync::Mutex::new(ReplayProgress {
+            source_session: recording.session_id.clone(),
+            started: chrono::Utc::now(),
+            speed: options.speed,
+            repeat: options.repeat,
+            records: recording.records.len(),
+            played: 0,
+            skipped: 0,
+            passes: 0,
+            finished: false,
+        }));
+        let cancel = tokio_util::sync::CancellationToken::new();
+        let player = ReplayPlayer {
+            outstation: self.outstation_handle.clone(),
+            data_points: self.data_points.clone(),
+            history: self.history.clone(),
+            log_store: self.log_store.clone(),
+            session_id: self.session_id.clone(),
+            progress: progress.clone(),
+        };
+        let snapshot = progress.lock().map(|p| p.clone()).map_err(|e| e.to_string())?;
+        // Replaces (and so stops) a running replay
+        if let Ok(mut replay) = self.replay.lock() {
+            *replay = Some(ReplayHandle::new(cancel.clone(), progress));
+        }
+        self.add_log(
+            "SIM",
+            &format!(
+                "[Replay] Playing {} measurement(s) recorded by {} at {}x{}",
+                recording.records.len(),
+                recording.session_id,
+                options.speed,
+                if options.repeat { ", repeating" } else { "" }
+            ),
+            0,
+        )
+        .await;
+        tokio::spawn(player.run(recording, options, cancel));
+        Ok(snapshot)
+    }
+
+    /// Stop the replay; the random simulation takes over again
+    pub fn stop_replay(&self) -> Option<ReplayProgress> {
+        let handle = self.replay.lock().ok()?.take()?;
+        handle.progress()
+    }
+
+    pub fn replay_progress(&self) -> Option<ReplayProgress> {
+        self.replay.lock().ok()?.as_ref()?.progress()
+    }
+
+    /// Address the outstation listens on (port 0 resolved to the bound port)
+    pub async fn listen_addr(&self) -> Option<std::net::SocketAddr> {
+        self.outstation_server.read().await.as_ref()?;
+        self.capture_shim.read().await.as_ref().map(|shim| shim.local_addr)
+    }
+
     pub async fn get_data(&self) -> Vec<DataPoint> {
         self.data_points.read().await.clone()
     }
 
     async fn add_log(&self, direction: &str, message: &str, transaction_id: u32) {
-        let mut logs = self.log_store.logs.write().await;
-        if logs.len() >= 1000 {
-            logs.pop_front();
-        }
-        let id = self.log_store.log_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        logs.push_back(ProtocolLogEntry {
-            id,
-            timestamp: chrono::Utc::now(),
-            direction: direction.to_string(),
-            message: message.to_string(),
-            transaction_id,
-        });
+        self.log_store.push_log(&self.session_id, direction, message, transaction_id).await;
     }
 
     pub async fn get_logs(&self) -> Vec<ProtocolLogEntry> {
@@ -970,22 +1176,57 @@ impl Dnp3Service {
         self.log_store.raw_frames.read().await.iter().cloned().collect()
     }
 
-    async fn capture_raw_frame(&self, direction: &str, data: &[u8]) {
-        let mut frames = self.log_store.raw_frames.write().await;
-        if frames.len() >= 500 {
-            frames.pop_front();
+    fn capture_sink(&self) -> CaptureSink {
+        CaptureSink {
+            session_id: self.session_id.clone(),
+            log_store: self.log_store.clone(),
+            status: self.status.clone(),
+            stats: self.stats.clone(),
         }
-        let id = self.log_store.frame_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        frames.push_back(RawFrame {
-            id,
-            timestamp: chrono::Utc::now(),
-            direction: direction.to_string(),
-            data: data.to_vec(),
-        });
     }
 
-    pub async fn get_stats(&self) -> Statistics {
-        self.stats.read().await.clone()
+    pub fn get_stats(&self) -> ProtocolStats {
+        self.stats.snapshot()
+    }
+
+    /// Wait until the channel reaches its peer; the last connect error if it doesn't
+    pub async fn wait_connected(&self, timeout: std::time::Duration) -> Result<(), String> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let status = self.status.snapshot();
+            match status.state.as_str() {
+                "connected" => return Ok(()),
+                // The channel reports "disabled" briefly before it is enabled
+                "shutdown" => {}
+                _ if tokio::time::Instant::now() < deadline => {
+                    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+                    continue;
+                }
+                _ => {}
+            }
+            return Err(status
+                .last_error
+                .unwrap_or_else(|| format!("Not connected after {} ms", timeout.as_millis())));
+        }
+    }
+
+    /// Wait until every point is online; an outstation's points carry RESTART
+    /// until its first simulation cycle
+    pub async fn wait_points_online(&self, timeout: std::time::Duration) -> Result<(), String> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.data_points.read().await.iter().any(|p| p.quality != DataQuality::Online) {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(format!("Points not online after {} ms", timeout.as_millis()));
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        }
+        Ok(())
+    }
+
+    /// Count a failed control and turn the error into the API message
+    fn control_failed(&self, context: &str, error: CommandError) -> String {
+        self.stats.record_control(Err(&error));
+        format!("{}: {}", context, error)
     }
 }
 
@@ -993,19 +1234,48 @@ impl Dnp3Service {
 // MASTER HANDLERS
 // ============================================================================
 
+/// Received values of one object header: (index, value, flags, history changed)
+type PointUpdate = (DataPointType, Vec<(u16, f64, u8, bool)>, chrono::DateTime<chrono::Utc>);
+
 struct MasterReadHandler {
-    data_points: Arc<RwLock<Vec<DataPoint>>>,
-    logs: Arc<RwLock<VecDeque<ProtocolLogEntry>>>,
-    stats: Arc<RwLock<Statistics>>,
+    history: Arc<std::sync::Mutex<PointHistory>>,
+    soe: Arc<std::sync::Mutex<SoeStore>>,
+    updates: tokio::sync::mpsc::UnboundedSender<PointUpdate>,
+    fragment: Option<FragmentInfo>,
+    log_store: Arc<LogStore>,
+    session_id: String,
 }
 
 impl MasterReadHandler {
     fn new(
         data_points: Arc<RwLock<Vec<DataPoint>>>,
-        logs: Arc<RwLock<VecDeque<ProtocolLogEntry>>>,
-        stats: Arc<RwLock<Statistics>>,
+        history: Arc<std::sync::Mutex<PointHistory>>,
+        soe: Arc<std::sync::Mutex<SoeStore>>,
+        log_store: Arc<LogStore>,
+        session_id: String,
     ) -> Self {
-        Self { data_points, logs, stats }
+        // A single task applies the updates, so they land in the order they were received
+        let (updates, mut rx) = tokio::sync::mpsc::unbounded_channel::<PointUpdate>();
+        let publisher = log_store.clone();
+        let session = session_id.clone();
+        tokio::spawn(async move {
+            while let Some((point_type, values, now)) = rx.recv().await {
+                let mut pts = data_points.write().await;
+                for (index, value, flags, changed) in values {
+                    if let Some(point) = pts.iter_mut().find(|p|
+                        p.point_type == point_type && p.index == index
+                    ) {
+                        point.value = value;
+                        point.quality = if flags & 0x01 != 0 { DataQuality::Online } else { DataQuality::Offline };
+                        point.timestamp = now;
+                        if changed {
+                            publisher.publish_point(&session, point);
+                        }
+                    }
+                }
+            }
+        });
+        Self { history, soe, updates, fragment: None, log_store, session_id }
     }
 
     fn boxed(self) -> Box<Self> {
@@ -1013,42 +1283,75 @@ impl MasterReadHandler {
     }
 
     async fn log(&self, direction: &str, message: &str) {
-        let mut logs = self.logs.write().await;
-        if logs.len() >= 1000 {
-            logs.pop_front();
+        self.log_store.push_log(&self.session_id, direction, message, 0).await;
+    }
+
+    /// Apply received (index, value, flags, time) tuples to the data points,
+    /// their history and the sequence-of-events log. History and SOE are
+    /// written here, in arrival order and stamped with the receive time; the
+    /// device time is kept alongside.
+    fn store_values(&self, point_type: DataPointType, info: HeaderInfo, values: Vec<(u16, f64, u8, Option<Time>)>) {
+        let now = chrono::Utc::now();
+        let (read_type, fragment_seq) = match &self.fragment {
+            Some(f) => (f.read_type.clone(), f.seq),
+            None => ("Unknown".to_string(), 0),
+        };
+        let device_time = |time: &Option<Time>| {
+            time.and_then(|t| match t {
+                Time::Synchronized(ts) | Time::Unsynchronized(ts) => ts.to_datetime_utc(),
+            })
+        };
+
+        if let Ok(mut soe) = self.soe.lock() {
+            for (index, value, flags, time) in values.iter() {
+                soe.push(SoeRecord {
+                    id: 0,
+                    received: now,
+                    point_type,
+                    index: *index,
+                    value: *value,
+                    flags: *flags,
+                    variation: format!("{:?}", info.variation),
+                    is_event: info.is_event,
+                    read_type: read_type.clone(),
+                    fragment_seq,
+                    device_time: device_time(time),
+                    time_synchronized: time.map(|t| t.is_synchronized()),
+                });
+            }
         }
-        logs.push_back(ProtocolLogEntry {
-            id: 0,
-            timestamp: chrono::Utc::now(),
-            direction: direction.to_string(),
-            message: message.to_string(),
-            transaction_id: 0,
-        });
+
+        let mut hist = self.history.lock().ok();
+        let values = values
+            .iter()
+            .map(|(index, value, flags, time)| {
+                let changed = hist.as_mut().is_some_and(|h| {
+                    h.record(point_type, *index, *value, *flags, now, device_time(time))
+                });
+                (*index, *value, *flags, changed)
+            })
+            .collect();
+        drop(hist);
+        let _ = self.updates.send((point_type, values, now));
     }
 }
 
 impl ReadHandler for MasterReadHandler {
-    fn begin_fragment(&mut self, _read_type: ReadType, _header: ResponseHeader) -> MaybeAsync<()> {
+    fn begin_fragment(&mut self, read_type: ReadType, header: ResponseHeader) -> MaybeAsync<()> {
+        self.fragment = Some(FragmentInfo {
+            read_type: format!("{:?}", read_type),
+            seq: header.control.seq.value(),
+        });
         MaybeAsync::ready(())
     }
 
     fn end_fragment(&mut self, _read_type: ReadType, _header: ResponseHeader) -> MaybeAsync<()> {
-        let logs = self.logs.clone();
-        let stats = self.stats.clone();
+        self.fragment = None;
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
         
         tokio::spawn(async move {
-            let mut log_queue = logs.write().await;
-            if log_queue.len() >= 1000 { log_queue.pop_front(); }
-            log_queue.push_back(ProtocolLogEntry {
-                id: 0,
-                timestamp: chrono::Utc::now(),
-                direction: "RX".to_string(),
-                message: "Response received".to_string(),
-                transaction_id: 0,
-            });
-            
-            let mut s = stats.write().await;
-            s.rx_count += 1;
+            log_store.push_log(&session_id, "RX", "Response received", 0).await;
         });
         
         MaybeAsync::ready(())
@@ -1056,24 +1359,13 @@ impl ReadHandler for MasterReadHandler {
 
     fn handle_binary_input(
         &mut self,
-        _info: HeaderInfo,
+        info: HeaderInfo,
         iter: &mut dyn Iterator<Item = (BinaryInput, u16)>,
     ) {
-        let points = self.data_points.clone();
-        let values: Vec<_> = iter.collect();
-        
-        tokio::spawn(async move {
-            let mut pts = points.write().await;
-            for (measurement, index) in values {
-                if let Some(point) = pts.iter_mut().find(|p| 
-                    p.point_type == DataPointType::BinaryInput && p.index == index
-                ) {
-                    point.value = if measurement.value { 1.0 } else { 0.0 };
-                    point.quality = if measurement.flags.value & 0x01 != 0 { DataQuality::Online } else { DataQuality::Offline };
-                    point.timestamp = chrono::Utc::now();
-                }
-            }
-        });
+        let values = iter
+            .map(|(m, index)| (index, if m.value { 1.0 } else { 0.0 }, m.flags.value, m.time))
+            .collect();
+        self.store_values(DataPointType::BinaryInput, info, values);
     }
 
     fn handle_double_bit_binary_input(
@@ -1086,46 +1378,24 @@ impl ReadHandler for MasterReadHandler {
 
     fn handle_binary_output_status(
         &mut self,
-        _info: HeaderInfo,
+        info: HeaderInfo,
         iter: &mut dyn Iterator<Item = (BinaryOutputStatus, u16)>,
     ) {
-        let points = self.data_points.clone();
-        let values: Vec<_> = iter.collect();
-        
-        tokio::spawn(async move {
-            let mut pts = points.write().await;
-            for (measurement, index) in values {
-                if let Some(point) = pts.iter_mut().find(|p| 
-                    p.point_type == DataPointType::BinaryOutput && p.index == index
-                ) {
-                    point.value = if measurement.value { 1.0 } else { 0.0 };
-                    point.quality = if measurement.flags.value & 0x01 != 0 { DataQuality::Online } else { DataQuality::Offline };
-                    point.timestamp = chrono::Utc::now();
-                }
-            }
-        });
+        let values = iter
+            .map(|(m, index)| (index, if m.value { 1.0 } else { 0.0 }, m.flags.value, m.time))
+            .collect();
+        self.store_values(DataPointType::BinaryOutput, info, values);
     }
 
     fn handle_counter(
         &mut self,
-        _info: HeaderInfo,
+        info: HeaderInfo,
         iter: &mut dyn Iterator<Item = (Counter, u16)>,
     ) {
-        let points = self.data_points.clone();
-        let values: Vec<_> = iter.collect();
-        
-        tokio::spawn(async move {
-            let mut pts = points.write().await;
-            for (measurement, index) in values {
-                if let Some(point) = pts.iter_mut().find(|p| 
-                    p.point_type == DataPointType::Counter && p.index == index
-                ) {
-                    point.value = measurement.value as f64;
-                    point.quality = if measurement.flags.value & 0x01 != 0 { DataQuality::Online } else { DataQuality::Offline };
-                    point.timestamp = chrono::Utc::now();
-                }
-            }
-        });
+        let values = iter
+            .map(|(m, index)| (index, m.value as f64, m.flags.value, m.time))
+            .collect();
+        self.store_values(DataPointType::Counter, info, values);
     }
 
     fn handle_frozen_counter(
@@ -1138,46 +1408,24 @@ impl ReadHandler for MasterReadHandler {
 
     fn handle_analog_input(
         &mut self,
-        _info: HeaderInfo,
+        info: HeaderInfo,
         iter: &mut dyn Iterator<Item = (AnalogInput, u16)>,
     ) {
-        let points = self.data_points.clone();
-        let values: Vec<_> = iter.collect();
-        
-        tokio::spawn(async move {
-            let mut pts = points.write().await;
-            for (measurement, index) in values {
-                if let Some(point) = pts.iter_mut().find(|p| 
-                    p.point_type == DataPointType::AnalogInput && p.index == index
-                ) {
-                    point.value = measurement.value;
-                    point.quality = if measurement.flags.value & 0x01 != 0 { DataQuality::Online } else { DataQuality::Offline };
-                    point.timestamp = chrono::Utc::now();
-                }
-            }
-        });
+        let values = iter
+            .map(|(m, index)| (index, m.value, m.flags.value, m.time))
+            .collect();
+        self.store_values(DataPointType::AnalogInput, info, values);
     }
 
     fn handle_analog_output_status(
         &mut self,
-        _info: HeaderInfo,
+        info: HeaderInfo,
         iter: &mut dyn Iterator<Item = (AnalogOutputStatus, u16)>,
     ) {
-        let points = self.data_points.clone();
-        let values: Vec<_> = iter.collect();
-        
-        tokio::spawn(async move {
-            let mut pts = points.write().await;
-            for (measurement, index) in values {
-                if let Some(point) = pts.iter_mut().find(|p| 
-                    p.point_type == DataPointType::AnalogOutput && p.index == index
-                ) {
-                    point.value = measurement.value;
-                    point.quality = if measurement.flags.value & 0x01 != 0 { DataQuality::Online } else { DataQuality::Offline };
-                    point.timestamp = chrono::Utc::now();
-                }
-            }
-        });
+        let values = iter
+            .map(|(m, index)| (index, m.value, m.flags.value, m.time))
+            .collect();
+        self.store_values(DataPointType::AnalogOutput, info, values);
     }
 
     fn handle_octet_string(
@@ -1195,37 +1443,291 @@ impl AssociationHandler for MasterAssociationHandler {}
 struct MasterAssociationInfo;
 impl AssociationInformation for MasterAssociationInfo {}
 
+// ============================================================================
+// OUTSTATION SCRIPTS
+// ============================================================================
+
+/// A behaviour script attached to a running outstation, with what applying
+/// its changes needs
+#[derive(Clone)]
+struct ScriptContext {
+    script: Arc<OutstationScript>,
+    data_points: Arc<RwLock<Vec<DataPoint>>>,
+    outstation: Arc<RwLock<Option<OutstationHandle>>>,
+    log_store: Arc<LogStore>,
+    session_id: String,
+}
+
+impl ScriptContext {
+    /// Log a hook error; the caller then falls back to the built-in behaviour
+    fn report<T>(&self, result: Result<T, String>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                let log_store = self.log_store.clone();
+                let session_id = self.session_id.clone();
+                tokio::spawn(async move {
+                    log_store.push_log(&session_id, "Error", &format!("[Script] {}", e), 0).await;
+                });
+                None
+            }
+        }
+    }
+
+    /// Log lines, point table updates and scheduled changes of a hook call.
+    /// `in_database` says whether the writes already went to the database.
+    fn finish(&self, outcome: HookOutcome, in_database: bool) {
+        let ctx = self.clone();
+        tokio::spawn(async move {
+            for line in &outcome.logs {
+                ctx.log_store.push_log(&ctx.session_id, "SIM", &format!("[Script] {}", line), 0).await;
+            }
+            ctx.apply(&outcome.writes, in_database).await;
+            for (delay, write) in outcome.scheduled {
+                let ctx = ctx.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    ctx.apply(&[write], false).await;
+                });
+            }
+        });
+    }
+
+    async fn apply(&self, writes: &[PointWrite], in_database: bool) {
+        if writes.is_empty() {
+            return;
+        }
+        if !in_database {
+            let outstation = self.outstation.read().await;
+            let Some(outstation) = outstation.as_ref() else {
+                return; // Stopped meanwhile
+            };
+            outstation.transaction(|db| {
+                for write in writes {
+                    update_database(db, write, UpdateOptions::detect_event());
+                }
+            });
+        }
+        let mut points = self.data_points.write().await;
+        for write in writes {
+            self.script.record(write);
+            if let Some(point) = points.iter_mut().find(|p| p.point_type == write.point_type && p.index == write.index) {
+                apply_write(point, write);
+                self.log_store.publish_point(&self.session_id, point);
+            }
+        }
+    }
+}
+
+/// Current time for database updates
+fn now_time() -> Time {
+    Time::synchronized(chrono::Utc::now().timestamp_millis().max(0) as u64)
+}
+
+/// Write one point into the database; false if the point does not exist
+fn update_database(db: &mut Database, write: &PointWrite, options: UpdateOptions) -> bool {
+    let flags = Flags::new(write.flags);
+    let time = now_time();
+    match write.point_type {
+        DataPointType::BinaryInput => db.update(write.index, &BinaryInput::new(write.value > 0.5, flags, time), options),
+        DataPointType::BinaryOutput => {
+            db.update(write.index, &BinaryOutputStatus::new(write.value > 0.5, flags, time), options)
+        }
+        DataPointType::AnalogInput => db.update(write.index, &AnalogInput::new(write.value, flags, time), options),
+        DataPointType::AnalogOutput => db.update(write.index, &AnalogOutputStatus::new(write.value, flags, time), options),
+        DataPointType::Counter => db.update(write.index, &Counter::new(write.value as u32, flags, time), options),
+    }
+}
+
+fn apply_write(point: &mut DataPoint, write: &PointWrite) {
+    point.value = write.value;
+    point.quality = if write.flags & Flags::ONLINE.value != 0 { DataQuality::Online } else { DataQuality::Offline };
+    point.timestamp = chrono::Utc::now();
+}
+
+// ============================================================================
+// MEASUREMENT REPLAY
+// ============================================================================
+
+struct ReplayPlayer {
+    outstation: Arc<RwLock<Option<OutstationHandle>>>,
+    data_points: Arc<RwLock<Vec<DataPoint>>>,
+    history: Arc<std::sync::Mutex<PointHistory>>,
+    log_store: Arc<LogStore>,
+    session_id: String,
+    progress: Arc<std::sync::Mutex<ReplayProgress>>,
+}
+
+impl ReplayPlayer {
+    async fn run(self, recording: MeasurementRecording, options: ReplayOptions, cancel: tokio_util::sync::CancellationToken) {
+        loop {
+            let pass_start = tokio::time::Instant::now();
+            // Measurements that arrived in the same millisecond go in one transaction
+            for batch in recording.records.chunk_by(|a, b| a.offset_ms == b.offset_ms) {
+                tokio::select! {
+                    _ = cancel.cancelled() => return,
+                    _ = tokio::time::sleep_until(pass_start + options.delay(batch[0].offset_ms)) => {}
+                }
+                if !self.play(batch).await {
+                    return; // Outstation stopped
+                }
+            }
+            let passes = match self.progress.lock() {
+                Ok(mut progress) => {
+                    progress.passes += 1;
+                    progress.finished = !options.repeat;
+                    progress.passes
+                }
+                Err(_) => return,
+            };
+            if !options.repeat {
+                self.log_store
+                    .push_log(&self.session_id, "SIM", "[Replay] Finished; values are held until the replay is stopped", 0)
+                    .await;
+                return;
+            }
+            if recording.duration_ms() == 0 {
+                // Nothing to wait for between passes; don't spin
+                tokio::select! {
+                    _ = cancel.cancelled() => return,
+                    _ = tokio::time::sleep(tokio::time::Duration::from_secs(1)) => {}
+                }
+            }
+            info!("Replay pass {} finished", passes);
+        }
+    }
+
+    /// Write one batch into the database and the point table
+    async fn play(&self, batch: &[crate::replay::RecordedMeasurement]) -> bool {
+        let writes: Vec<(PointWrite, bool)> = batch
+            .iter()
+            .map(|r| {
+                let write = PointWrite { point_type: r.point_type, index: r.index, value: r.value, flags: r.flags };
+                (write, r.is_event)
+            })
+            .collect();
+        let mut skipped = 0;
+        {
+            let outstation = self.outstation.read().await;
+            let Some(outstation) = outstation.as_ref() else {
+                return false;
+            };
+            outstation.transaction(|db| {
+                for (write, is_event) in &writes {
+                    // Events are raised where the field device raised them, even for
+                    // an unchanged value; static values only refresh the database.
+                    let mode = if *is_event { EventMode::Force } else { EventMode::Suppress };
+                    if !update_database(db, write, UpdateOptions::new(true, mode)) {
+                        skipped += 1;
+                    }
+                }
+            });
+        }
+        let mut points = self.data_points.write().await;
+        if let Ok(mut hist) = self.history.lock() {
+            for (write, _) in &writes {
+                if let Some(point) = points.iter_mut().find(|p| p.point_type == write.point_type && p.index == write.index) {
+                    apply_write(point, write);
+                    if hist.record(point.point_type, point.index, point.value, write.flags, point.timestamp, None) {
+                        self.log_store.publish_point(&self.session_id, point);
+                    }
+                }
+            }
+        }
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.played += writes.len() as u64;
+            progress.skipped += skipped;
+        }
+        true
+    }
+}
+
 // ============================================================================
 // OUTSTATION HANDLERS
 // ============================================================================
 
 struct OutstationControlHandler {
     data_points: Arc<RwLock<Vec<DataPoint>>>,
-    logs: Arc<RwLock<VecDeque<ProtocolLogEntry>>>,
-    stats: Arc<RwLock<Statistics>>,
+    log_store: Arc<LogStore>,
+    stats: StatsCounter,
+    session_id: String,
+    script: Option<ScriptContext>,
 }
 
 impl OutstationControlHandler {
     fn new(
         data_points: Arc<RwLock<Vec<DataPoint>>>,
-        logs: Arc<RwLock<VecDeque<ProtocolLogEntry>>>,
-        stats: Arc<RwLock<Statistics>>,
+        log_store: Arc<LogStore>,
+        stats: StatsCounter,
+        session_id: String,
+        script: Option<ScriptContext>,
     ) -> Self {
-        Self { data_points, logs, stats }
+        Self { data_points, log_store, stats, session_id, script }
     }
 
     async fn log(&self, direction: &str, message: &str) {
-        let mut logs = self.logs.write().await;
-        if logs.len() >= 1000 {
-            logs.pop_front();
-        }
-        logs.push_back(ProtocolLogEntry {
-            id: 0,
-            timestamp: chrono::Utc::now(),
-            direction: direction.to_string(),
-            message: message.to_string(),
-            transaction_id: 0,
+        self.log_store.push_log(&self.session_id, direction, message, 0).await;
+    }
+
+    /// Ask the script about a select/operate. Its writes go to the database
+    /// right away; Some(status) rejects the command with that status.
+    fn script_decision(&self, hook: &str, cmd: CommandInfo, database: &mut DatabaseHandle) -> Option<CommandStatus> {
+        let ctx = self.script.as_ref()?;
+        let (status, outcome) = ctx.report(ctx.script.on_command(hook, &cmd))?;
+        database.transaction(|db| {
+            for write in &outcome.writes {
+                update_database(db, write, UpdateOptions::detect_event());
+            }
         });
+        ctx.finish(outcome, true);
+
+        let status = status.filter(|s| *s != CommandStatus::Success)?;
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
+        let (fc, name) = if hook == "on_select" { ("03", "SELECT") } else { ("04", "OPERATE") };
+        tokio::spawn(async move {
+            log_store
+                .push_log(&session_id, "RX", &format!("[FC={} {}] {:?}[{}] rejected by script", fc, name, cmd.point_type, cmd.index), 0)
+                .await;
+            log_store.push_log(&session_id, "TX", &format!("[FC=129] {} {:?}", name, status), 0).await;
+        });
+        Some(status)
+    }
+
+    /// Flags for a controlled output; a script's set_flags stick like in the simulation
+    fn output_flags(&self, point_type: DataPointType, index: u16) -> Flags {
+        match &self.script {
+            Some(ctx) => Flags::new(ctx.script.flags(point_type, index)),
+            None => Flags::ONLINE,
+        }
+    }
+
+    /// Keep the script's view of a controlled point current
+    fn script_sync(&self, point_type: DataPointType, index: u16, value: f64) {
+        if let Some(ctx) = &self.script {
+            ctx.script.sync_value(point_type, index, value);
+        }
+    }
+}
+
+fn op_name(op_type: OpType) -> &'static str {
+    match op_type {
+        OpType::LatchOn => "LatchOn",
+        OpType::LatchOff => "LatchOff",
+        OpType::PulseOn => "PulseOn",
+        OpType::PulseOff => "PulseOff",
+        _ => "Unknown",
+    }
+}
+
+/// Command info for on_select / on_operate
+fn command_info(point_type: DataPointType, index: u16, value: f64, op: &str, mode: Option<OperateType>) -> CommandInfo {
+    CommandInfo {
+        point_type,
+        index,
+        value,
+        op: op.to_string(),
+        mode: mode.map(|m| format!("{:?}", m)).unwrap_or_else(|| "Select".to_string()),
     }
 }
 
@@ -1237,35 +1739,21 @@ impl ControlSupport<Group12Var1> for OutstationControlHandler {
         &mut self,
         control: Group12Var1,
         index: u16,
-        _database: &mut DatabaseHandle,
+        database: &mut DatabaseHandle,
     ) -> CommandStatus {
-        let logs = self.logs.clone();
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
         
-        let op_name = match control.code.op_type {
-            OpType::LatchOn => "LatchOn",
-            OpType::LatchOff => "LatchOff",
-            OpType::PulseOn => "PulseOn",
-            OpType::PulseOff => "PulseOff",
-            _ => "Unknown",
-        };
+        let op_name = op_name(control.code.op_type);
+        let value = if matches!(control.code.op_type, OpType::LatchOn | OpType::PulseOn) { 1.0 } else { 0.0 };
+        let cmd = command_info(DataPointType::BinaryOutput, index, value, op_name, None);
+        if let Some(status) = self.script_decision("on_select", cmd, database) {
+            return status;
+        }
 
         tokio::spawn(async move {
-            let mut log_queue = logs.write().await;
-            if log_queue.len() >= 1000 { log_queue.pop_front(); }
-            log_queue.push_back(ProtocolLogEntry {
-                id: 0,
-                timestamp: chrono::Utc::now(),
-                direction: "RX".to_string(), 
-                message: format!("[FC=03 SELECT] BinaryOutput[{}] Op={}", index, op_name),
-                transaction_id: 0,
-            });
-            log_queue.push_back(ProtocolLogEntry {
-                id: 0,
-                timestamp: chrono::Utc::now(),
-                direction: "TX".to_string(),
-                message: "[FC=129] SELECT Success".to_string(),
-                transaction_id: 0,
-            });
+            log_store.push_log(&session_id, "RX", &format!("[FC=03 SELECT] BinaryOutput[{}] Op={}", index, op_name), 0).await;
+            log_store.push_log(&session_id, "TX", "[FC=129] SELECT Success", 0).await;
         });
 
         // Accept LatchOn, LatchOff, PulseOn, PulseOff.
@@ -1280,7 +1768,7 @@ impl ControlSupport<Group12Var1> for OutstationControlHandler {
         &mut self,
         control: Group12Var1,
         index: u16,
-        _op_type: OperateType,
+        op_type: OperateType,
         database: &mut DatabaseHandle,
     ) -> CommandStatus {
         // Determine status based on OpType
@@ -1291,15 +1779,21 @@ impl ControlSupport<Group12Var1> for OutstationControlHandler {
             _ => false,
         };
         let value = if status { 1.0 } else { 0.0 };
+
+        let cmd = command_info(DataPointType::BinaryOutput, index, value, op_name(control.code.op_type), Some(op_type));
+        if let Some(status) = self.script_decision("on_operate", cmd, database) {
+            return status;
+        }
         
         // Update database
+        let flags = self.output_flags(DataPointType::BinaryOutput, index);
         database.transaction(|db| {
             db.update(
                 index,
                 &BinaryOutputStatus::new(
                     status,
-                    Flags::ONLINE,
-                    Time::synchronized(chrono::Utc::now().timestamp_millis().try_into().unwrap()),
+                    flags,
+                    now_time(),
                 ),
                 UpdateOptions::detect_event(),
             );
@@ -1307,46 +1801,33 @@ impl ControlSupport<Group12Var1> for OutstationControlHandler {
         
         // Update our data points
         let points = self.data_points.clone();
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
         tokio::spawn(async move {
             let mut pts = points.write().await;
             if let Some(point) = pts.iter_mut().find(|p| 
                 p.point_type == DataPointType::BinaryOutput && p.index == index
             ) {
                 point.value = value;
-                point.quality = DataQuality::Online;
+                point.quality = if flags.value & Flags::ONLINE.value != 0 { DataQuality::Online } else { DataQuality::Offline };
                 point.timestamp = chrono::Utc::now();
+                log_store.publish_point(&session_id, point);
             }
         });
         
+        self.script_sync(DataPointType::BinaryOutput, index, value);
+
         // Log
-        let logs = self.logs.clone();
-        let op_name = match control.code.op_type {
-            OpType::LatchOn => "LatchOn",
-            OpType::LatchOff => "LatchOff",
-            OpType::PulseOn => "PulseOn",
-            OpType::PulseOff => "PulseOff",
-            _ => "Unknown",
-        };
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
+        let op_name = op_name(control.code.op_type);
 
         tokio::spawn(async move {
-            let mut log_queue = logs.write().await;
-            if log_queue.len() >= 1000 { log_queue.pop_front(); }
-            log_queue.push_back(ProtocolLogEntry {
-                id: 0,
-                timestamp: chrono::Utc::now(),
-                direction: "RX".to_string(),
-                message: format!("[FC=04 OPERATE] BinaryOutput[{}] {} -> {}", index, op_name, value),
-                transaction_id: 0,
-            });
-            log_queue.push_back(ProtocolLogEntry {
-                id: 0,
-                timestamp: chrono::Utc::now(),
-                direction: "TX".to_string(),
-                message: "[FC=129] OPERATE Success".to_string(),
-                transaction_id: 0,
-            });
+            log_store.push_log(&session_id, "RX", &format!("[FC=04 OPERATE] BinaryOutput[{}] {} -> {}", index, op_name, value), 0).await;
+            log_store.push_log(&session_id, "TX", "[FC=129] OPERATE Success", 0).await;
         });
         
+        self.stats.record_control(Ok(()));
         CommandStatus::Success
     }
 }
@@ -1356,10 +1837,14 @@ impl ControlSupport<Group12Var1> for OutstationControlHandler {
 impl ControlSupport<Group41Var1> for OutstationControlHandler {
     fn select(
         &mut self,
-        _control: Group41Var1,
-        _index: u16,
-        _database: &mut DatabaseHandle,
+        control: Group41Var1,
+        index: u16,
+        database: &mut DatabaseHandle,
     ) -> CommandStatus {
+        let cmd = command_info(DataPointType::AnalogOutput, index, control.value as f64, "", None);
+        if let Some(status) = self.script_decision("on_select", cmd, database) {
+            return status;
+        }
         // Always accept selection for Analog Outputs, regardless of index
         CommandStatus::Success
     }
@@ -1368,25 +1853,32 @@ impl ControlSupport<Group41Var1> for OutstationControlHandler {
         &mut self,
         control: Group41Var1,
         index: u16,
-        _op_type: OperateType,
+        op_type: OperateType,
         database: &mut DatabaseHandle,
     ) -> CommandStatus {
         let value = control.value as f64;
+        let cmd = command_info(DataPointType::AnalogOutput, index, value, "", Some(op_type));
+        if let Some(status) = self.script_decision("on_operate", cmd, database) {
+            return status;
+        }
+        self.script_sync(DataPointType::AnalogOutput, index, value);
         
+        let flags = self.output_flags(DataPointType::AnalogOutput, index);
         database.transaction(|db| {
             db.update(
                 index,
                 &AnalogOutputStatus::new(
                     value,
-                    Flags::ONLINE,
-                    Time::synchronized(chrono::Utc::now().timestamp_millis().try_into().unwrap()),
+                    flags,
+                    now_time(),
                 ),
                 UpdateOptions::detect_event(),
             );
         });
         
         let points = self.data_points.clone();
-        let logs = self.logs.clone();
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
         
         tokio::spawn(async move {
             let mut pts = points.write().await;
@@ -1394,101 +1886,149 @@ impl ControlSupport<Group41Var1> for OutstationControlHandler {
                 p.point_type == DataPointType::AnalogOutput && p.index == index
             ) {
                 point.value = value;
-                point.quality = DataQuality::Online;
+                point.quality = if flags.value & Flags::ONLINE.value != 0 { DataQuality::Online } else { DataQuality::Offline };
                 point.timestamp = chrono::Utc::now();
+                log_store.publish_point(&session_id, point);
             }
             
-            let mut log_queue = logs.write().await;
-            if log_queue.len() >= 1000 { log_queue.pop_front(); }
-            log_queue.push_back(ProtocolLogEntry {
-                id: 0,
-                timestamp: chrono::Utc::now(),
-                direction: "RX".to_string(),
-                message: format!("[FC=04 OPERATE] AnalogOutput[Int32][{}] = {}", index, value),
-                transaction_id: 0,
-            });
-            log_queue.push_back(ProtocolLogEntry {
-                id: 0,
-                timestamp: chrono::Utc::now(),
-                direction: "TX".to_string(),
-                message: "[FC=129] OPERATE Success".to_string(),
-                transaction_id: 0,
-            });
+            log_store.push_log(&session_id, "RX", &format!("[FC=04 OPERATE] AnalogOutput[Int32][{}] = {}", index, value), 0).await;
+            log_store.push_log(&session_id, "TX", "[FC=129] OPERATE Success", 0).await;
         });
         
+        self.stats.record_control(Ok(()));
         CommandStatus::Success
     }
 }
 
 impl ControlSupport<Group41Var2> for OutstationControlHandler {
-    fn select(&mut self, _control: Group41Var2, _index: u16, _database: &mut DatabaseHandle) -> CommandStatus {
-        CommandStatus::Success
+    fn select(&mut self, control: Group41Var2, index: u16, database: &mut DatabaseHandle) -> CommandStatus {
+        let cmd = command_info(DataPointType::AnalogOutput, index, control.value as f64, "", None);
+        self.script_decision("on_select", cmd, database).unwrap_or(CommandStatus::Success)
     }
     
-    fn operate(&mut self, control: Group41Var2, index: u16, _op_type: OperateType, database: &mut DatabaseHandle) -> CommandStatus {
+    fn operate(&mut self, control: Group41Var2, index: u16, op_type: OperateType, database: &mut DatabaseHandle) -> CommandStatus {
         let value = control.value;
+        let cmd = command_info(DataPointType::AnalogOutput, index, value as f64, "", Some(op_type));
+        if let Some(status) = self.script_decision("on_operate", cmd, database) {
+            return status;
+        }
+        self.script_sync(DataPointType::AnalogOutput, index, value as f64);
+        let flags = self.output_flags(DataPointType::AnalogOutput, index);
         database.transaction(|db| {
-            db.update(index, &AnalogOutputStatus::new(value as f64, Flags::ONLINE, Time::synchronized(chrono::Utc::now().timestamp_millis().try_into().unwrap())), UpdateOptions::detect_event());
+            db.update(index, &AnalogOutputStatus::new(value as f64, flags, now_time()), UpdateOptions::detect_event());
         });
         
         // Sync to internal state (simplified compared to Var1 for brevity, but same logic applies)
         let points = self.data_points.clone();
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
         tokio::spawn(async move {
             let mut pts = points.write().await;
             if let Some(point) = pts.iter_mut().find(|p| p.point_type == DataPointType::AnalogOutput && p.index == index) {
                 point.value = value as f64;
+                log_store.publish_point(&session_id, point);
             }
         });
 
+        self.stats.record_control(Ok(()));
         CommandStatus::Success
     }
 }
 
 impl ControlSupport<Group41Var3> for OutstationControlHandler {
-    fn select(&mut self, _control: Group41Var3, _index: u16, _database: &mut DatabaseHandle) -> CommandStatus {
-        CommandStatus::Success
+    fn select(&mut self, control: Group41Var3, index: u16, database: &mut DatabaseHandle) -> CommandStatus {
+        let cmd = command_info(DataPointType::AnalogOutput, index, control.value as f64, "", None);
+        self.script_decision("on_select", cmd, database).unwrap_or(CommandStatus::Success)
     }
     
-    fn operate(&mut self, control: Group41Var3, index: u16, _op_type: OperateType, database: &mut DatabaseHandle) -> CommandStatus {
+    fn operate(&mut self, control: Group41Var3, index: u16, op_type: OperateType, database: &mut DatabaseHandle) -> CommandStatus {
         let value = control.value;
+        let cmd = command_info(DataPointType::AnalogOutput, index, value as f64, "", Some(op_type));
+        if let Some(status) = self.script_decision("on_operate", cmd, database) {
+            return status;
+        }
+        self.script_sync(DataPointType::AnalogOutput, index, value as f64);
+        let flags = self.output_flags(DataPointType::AnalogOutput, index);
         database.transaction(|db| {
-            db.update(index, &AnalogOutputStatus::new(value as f64, Flags::ONLINE, Time::synchronized(chrono::Utc::now().timestamp_millis().try_into().unwrap())), UpdateOptions::detect_event());
+            db.update(index, &AnalogOutputStatus::new(value as f64, flags, now_time()), UpdateOptions::detect_event());
         });
         let points = self.data_points.clone();
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
         tokio::spawn(async move {
             let mut pts = points.write().await;
             if let Some(point) = pts.iter_mut().find(|p| p.point_type == DataPointType::AnalogOutput && p.index == index) {
                 point.value = value as f64;
+                log_store.publish_point(&session_id, point);
             }
         });
+        self.stats.record_control(Ok(()));
         CommandStatus::Success
     }
 }
 
 impl ControlSupport<Group41Var4> for OutstationControlHandler {
-    fn select(&mut self, _control: Group41Var4, _index: u16, _database: &mut DatabaseHandle) -> CommandStatus {
-        CommandStatus::Success
+    fn select(&mut self, control: Group41Var4, index: u16, database: &mut DatabaseHandle) -> CommandStatus {
+        let cmd = command_info(DataPointType::AnalogOutput, index, control.value, "", None);
+        self.script_decision("on_select", cmd, database).unwrap_or(CommandStatus::Success)
     }
     
-    fn operate(&mut self, control: Group41Var4, index: u16, _op_type: OperateType, database: &mut DatabaseHandle) -> CommandStatus {
+    fn operate(&mut self, control: Group41Var4, index: u16, op_type: OperateType, database: &mut DatabaseHandle) -> CommandStatus {
         let value = control.value;
+        let cmd = command_info(DataPointType::AnalogOutput, index, value, "", Some(op_type));
+        if let Some(status) = self.script_decision("on_operate", cmd, database) {
+            return status;
+        }
+        self.script_sync(DataPointType::AnalogOutput, index, value);
+        let flags = self.output_flags(DataPointType::AnalogOutput, index);
         database.transaction(|db| {
-            db.update(index, &AnalogOutputStatus::new(value as f64, Flags::ONLINE, Time::synchronized(chrono::Utc::now().timestamp_millis().try_into().unwrap())), UpdateOptions::detect_event());
+            db.update(index, &AnalogOutputStatus::new(value, flags, now_time()), UpdateOptions::detect_event());
         });
         
          let points = self.data_points.clone();
+        let log_store = self.log_store.clone();
+        let session_id = self.session_id.clone();
         tokio::spawn(async move {
             let mut pts = points.write().await;
             if let Some(point) = pts.iter_mut().find(|p| p.point_type == DataPointType::AnalogOutput && p.index == index) {
-                point.value = value as f64;
+                point.value = value;
+                log_store.publish_point(&session_id, point);
             }
         });
+        self.stats.record_control(Ok(()));
         CommandStatus::Success
     }
 }
 
-struct OutstationApp;
-impl OutstationApplication for OutstationApp {}
+struct OutstationApp {
+    script: Option<ScriptContext>,
+}
+
+impl OutstationApplication for OutstationApp {
+    /// Time sync is only accepted when the script handles it
+    fn write_absolute_time(&mut self, time: Timestamp) -> Result<(), RequestError> {
+        let Some(ctx) = self.script.as_ref().filter(|ctx| ctx.script.has_hook("on_time_sync")) else {
+            return Err(RequestError::NotSupported);
+        };
+        let (accepted, outcome) = ctx.report(ctx.script.on_time_sync(time.raw_value())).ok_or(RequestError::NotSupported)?;
+        ctx.finish(outcome, false);
+
+        let log_store = ctx.log_store.clone();
+        let session_id = ctx.session_id.clone();
+        let when = chrono::DateTime::from_timestamp_millis(time.raw_value() as i64)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| time.raw_value().to_string());
+        tokio::spawn(async move {
+            let result = if accepted { "accepted" } else { "rejected by script" };
+            log_store.push_log(&session_id, "RX", &format!("[FC=02 WRITE] Time sync {} {}", when, result), 0).await;
+        });
+        if accepted {
+            Ok(())
+        } else {
+            Err(RequestError::NotSupported)
+        }
+    }
+}
 
 struct OutstationInfo;
 impl OutstationInformation for OutstationInfo {}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use crate::dnp3_link::LinkFrameInfo;
+
+// DNP3 transport function (IEEE 1815 clause 8): a one byte header
+// `FIN FIR SEQ(6)` in front of each link frame's user data.
+
+/// Largest APDU we are willing to reassemble before giving up on a stream
+const MAX_APDU_SIZE: usize = 64 * 1024;
+
+// --- Transport Segment Header ---
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct TransportHeader {
+    pub fin: bool,
+    pub fir: bool,
+    pub seq: u8,
+}
+
+impl TransportHeader {
+    pub fn parse(byte: u8) -> Self {
+        Self {
+            fin: byte & 0x80 != 0,
+            fir: byte & 0x40 != 0,
+            seq: byte & 0x3F,
+        }
+    }
+}
+
+/// Result of feeding one link frame to the reassembler
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub header: TransportHeader,
+    /// Complete application fragment, present when this segment carried FIN
+    pub apdu: Option<Vec<u8>>,
+    /// Why the segment was dropped (bad sequence, missing FIR, ...)
+    pub error: Option<String>,
+}
+
+struct PartialApdu {
+    data: Vec<u8>,
+    last_seq: u8,
+}
+
+/// Reassembles transport segments into APDUs. Streams are keyed by link
+/// (source, destination) so both directions of a conversation, and several
+/// associations, can be fed through one instance in capture order.
+pub struct Reassembler {
+    streams: HashMap<(u16, u16), PartialApdu>,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self {
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Feed one decoded link frame. Returns `None` for frames that carry no
+    /// transport segment (link-only functions, CRC failures).
+    pub fn push(&mut self, link: &LinkFrameInfo) -> Option<Segment> {
+        // Only primary user data frames carry transport segments
+        if !link.prm || !(link.function == 3 || link.function == 4) || !link.crc_ok {
+            return None;
+        }
+        let (&first, body) = link.payload.split_first()?;
+        let header = TransportHeader::parse(first);
+        let key = (link.source, link.destination);
+
+        let mut error = None;
+        if header.fir {
+            if self.streams.contains_key(&key) {
+                error = Some("Discarded incomplete fragment (new FIR)".to_string());
+            }
+            self.streams.insert(key, PartialApdu { data: body.to_vec(), last_seq: header.seq });
+        } else {
+            match self.streams.get_mut(&key) {
+                Some(partial) if header.seq == (partial.last_seq + 1) & 0x3F => {
+                    partial.data.extend_from_slice(body);
+                    partial.last_seq = header.seq;
+                }
+                Some(partial) => {
+                    let expected = (partial.last_seq + 1) & 0x3F;
+                    self.streams.remove(&key);
+                    return Some(Segment {
+                        header,
+                        apdu: None,
+                        error: Some(format!("Unexpected sequence {} (expected {})", header.seq, expected)),
+                    });
+                }
+                None => {
+                    return Some(Segment {
+                        header,
+                        apdu: None,
+                        error: Some("Segment without preceding FIR".to_string()),
+                    });
+                }
+            }
+        }
+
+        if self.streams.get(&key).is_some_and(|p| p.data.len() > MAX_APDU_SIZE) {
+            self.streams.remove(&key);
+            return Some(Segment {
+                header,
+                apdu: None,
+                error: Some(format!("Fragment exceeds {} bytes", MAX_APDU_SIZE)),
+            });
+        }
+
+        let apdu = if header.fin {
+            self.streams.remove(&key).map(|p| p.data)
+        } else {
+            None
+        };
+
+        Some(Segment { header, apdu, error })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Primary unconfirmed user data from outstation 10 to master 1
+    fn link(transport: u8, body: &[u8]) -> LinkFrameInfo {
+        let mut payload = vec![transport];
+        payload.extend_from_slice(body);
+        LinkFrameInfo {
+            length: (payload.len() + 5) as u8,
+            control: 0x44,
+            dir: false,
+            prm: true,
+            fcb: false,
+            fcv: false,
+            function: 4,
+            function_name: "UNCONFIRMED_USER_DATA".to_string(),
+            destination: 1,
+            source: 10,
+            header_crc_ok: true,
+            data_crc_ok: vec![true],
+            crc_ok: true,
+            payload,
+        }
+    }
+
+    #[test]
+    fn single_segment_is_a_complete_apdu() {
+        let segment = Reassembler::new().push(&link(0xC0, &[1, 2, 3])).unwrap();
+        assert!(segment.header.fir && segment.header.fin);
+        assert_eq!(segment.apdu, Some(vec![1, 2, 3]));
+        assert!(segment.error.is_none());
+    }
+
+    #[test]
+    fn reassembles_from_fir_to_fin() {
+        let mut reassembler = Reassembler::new();
+        // FIR seq 62, then 63 and 0: the sequence wraps at 6 bits
+        let first = reassembler.push(&link(0x40 | 62, &[1, 2])).unwrap();
+        let middle = reassembler.push(&link(63, &[3])).unwrap();
+        let last = reassembler.push(&link(0x80, &[4, 5])).unwrap();
+
+        assert!(first.apdu.is_none() && middle.apdu.is_none());
+        assert_eq!(last.apdu, Some(vec![1, 2, 3, 4, 5]));
+        assert!([first, middle, last].iter().all(|s| s.error.is_none()));
+    }
+
+    #[test]
+    fn drops_fragment_on_sequence_gap() {
+        let mut reassembler = Reassembler::new();
+        reassembler.push(&link(0x40 | 5, &[1])).unwrap();
+        let gap = reassembler.push(&link(0x80 | 7, &[2])).unwrap();
+        assert!(gap.apdu.is_none());
+        assert_eq!(gap.error.as_deref(), Some("Unexpected sequence 7 (expected 6)"));
+
+        let orphan = reassembler.push(&link(0x80 | 8, &[3])).unwrap();
+        assert_eq!(orphan.error.as_deref(), Some("Segment without preceding FIR"));
+    }
+
+    #[test]
+    fn new_fir_discards_incomplete_fragment() {
+        let mut reassembler = Reassembler::new();
+        reassembler.push(&link(0x40, &[1])).unwrap();
+        let restart = reassembler.push(&link(0xC0 | 9, &[2])).unwrap();
+        assert_eq!(restart.apdu, Some(vec![2]));
+        assert!(restart.error.is_some());
+    }
+
+    #[test]
+    fn ignores_link_only_and_corrupt_frames() {
+        let mut reassembler = Reassembler::new();
+        let mut status = link(0xC0, &[]);
+        status.function = 9;
+        assert!(reassembler.push(&status).is_none());
+
+        let mut corrupt = link(0xC0, &[1]);
+        corrupt.crc_ok = false;
+        assert!(reassembler.push(&corrupt).is_none());
+    }
+}
@@ -0,0 +1,209 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn, Instrument};
+
+use crate::connection_status::StatusTracker;
+use crate::dnp3_link::LinkFramer;
+use crate::dnp3_service::LogStore;
+use crate::protocol_stats::StatsCounter;
+
+// Frame capture by tapping the byte stream between the dnp3 stack and the
+// wire. The stack is pointed at a loopback socket owned by a shim, and the
+// shim forwards bytes to the real peer (TCP socket or serial port), cutting
+// them into link frames as they pass.
+
+/// Distinguishes successive connections in a capture (one pcap stream each)
+static CONNECTION_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+// --- Capture Endpoint ---
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CaptureEndpoint {
+    pub transport: String, // "tcp" or "serial"
+    pub local: String,     // Our side, e.g. "192.168.1.10:50123"
+    pub remote: String,    // Peer side, e.g. "192.168.1.20:20000" or "/dev/ttyUSB0"
+    pub connection_id: u64,
+}
+
+impl CaptureEndpoint {
+    pub fn new(transport: &str, local: String, remote: String) -> Self {
+        Self {
+            transport: transport.to_string(),
+            local,
+            remote,
+            connection_id: CONNECTION_COUNTER.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Where captured frames go, tagged with the session that produced them.
+/// The shim also reports the real peer's connection state to `status` and
+/// counts every frame in `stats`.
+#[derive(Clone)]
+pub struct CaptureSink {
+    pub session_id: String,
+    pub log_store: Arc<LogStore>,
+    pub status: StatusTracker,
+    pub stats: StatsCounter,
+}
+
+/// Keeps a shim running; dropping it stops the listener and every pump
+pub struct ShimHandle {
+    cancel: CancellationToken,
+    pub local_addr: SocketAddr,
+}
+
+impl ShimHandle {
+    pub fn new(cancel: CancellationToken, local_addr: SocketAddr) -> Self {
+        Self { cancel, local_addr }
+    }
+}
+
+impl Drop for ShimHandle {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+/// Master side: the dnp3 TCP client connects to the returned loopback
+/// address, and each accepted connection is forwarded to `remote`.
+pub async fn start_client_shim(remote: String, sink: CaptureSink) -> std::io::Result<ShimHandle> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+
+    let span = tracing::info_span!("capture", session_id = %sink.session_id);
+    tokio::spawn(async move {
+        loop {
+            let accepted = tokio::select! {
+                _ = token.cancelled() => break,
+                r = listener.accept() => r,
+            };
+            let (stack, _) = match accepted {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("Capture shim accept failed: {}", e);
+                    continue;
+                }
+            };
+            let wire = match TcpStream::connect(&remote).await {
+                Ok(s) => s,
+                Err(e) => {
+                    // Dropping `stack` makes the dnp3 client back off and retry
+                    warn!("Capture shim failed to connect {}: {}", remote, e);
+                    sink.status.connect_failed(&remote, &e.to_string());
+                    continue;
+                }
+            };
+            let endpoint = tcp_endpoint(&wire);
+            info!("Capture shim connected {} -> {}", endpoint.local, endpoint.remote);
+            tokio::spawn(pump(stack, wire, endpoint, sink.clone(), token.child_token()).in_current_span());
+        }
+    }.instrument(span));
+
+    Ok(ShimHandle::new(cancel, local_addr))
+}
+
+/// Outstation side: listen on `bind` for masters and forward each
+/// connection to the dnp3 server listening on `internal`.
+pub async fn start_server_shim(bind: SocketAddr, internal: SocketAddr, sink: CaptureSink) -> std::io::Result<ShimHandle> {
+    let listener = TcpListener::bind(bind).await?;
+    let local_addr = listener.local_addr()?;
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+
+    let span = tracing::info_span!("capture", session_id = %sink.session_id);
+    tokio::spawn(async move {
+        loop {
+            let accepted = tokio::select! {
+                _ = token.cancelled() => break,
+                r = listener.accept() => r,
+            };
+            let (wire, peer) = match accepted {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("Capture shim accept failed: {}", e);
+                    continue;
+                }
+            };
+            let stack = match TcpStream::connect(internal).await {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("Capture shim failed to reach outstation {}: {}", internal, e);
+                    sink.status.connect_failed(&peer.to_string(), &e.to_string());
+                    continue;
+                }
+            };
+            let endpoint = tcp_endpoint(&wire);
+            info!("Capture shim accepted master {}", peer);
+            tokio::spawn(pump(stack, wire, endpoint, sink.clone(), token.child_token()).in_current_span());
+        }
+    }.instrument(span));
+
+    Ok(ShimHandle::new(cancel, local_addr))
+}
+
+fn tcp_endpoint(wire: &TcpStream) -> CaptureEndpoint {
+    let local = wire.local_addr().map(|a| a.to_string()).unwrap_or_default();
+    let remote = wire.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+    CaptureEndpoint::new("tcp", local, remote)
+}
+
+/// Forward bytes both ways until either side closes or the shim is stopped.
+/// Stack -> wire is recorded as TX, wire -> stack as RX.
+pub async fn pump<S, W>(stack: S, wire: W, endpoint: CaptureEndpoint, sink: CaptureSink, cancel: CancellationToken)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+    W: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut stack_rx, mut stack_tx) = tokio::io::split(stack);
+    let (mut wire_rx, mut wire_tx) = tokio::io::split(wire);
+
+    let tx = forward(&mut stack_rx, &mut wire_tx, "TX", &endpoint, &sink);
+    let rx = forward(&mut wire_rx, &mut stack_tx, "RX", &endpoint, &sink);
+
+    sink.status.peer_connected(&endpoint.remote);
+    let stopped = tokio::select! {
+        _ = cancel.cancelled() => true,
+        r = tx => { if let Err(e) = r { warn!("Capture shim TX ended: {}", e) }; false }
+        r = rx => { if let Err(e) = r { warn!("Capture shim RX ended: {}", e) }; false }
+    };
+    info!("Capture shim closed {} -> {}", endpoint.local, endpoint.remote);
+    // When the shim is stopped the session reports its own shutdown
+    if !stopped {
+        sink.status.peer_disconnected(&format!("Connection to {} closed", endpoint.remote));
+    }
+}
+
+async fn forward<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    direction: &str,
+    endpoint: &CaptureEndpoint,
+    sink: &CaptureSink,
+) -> std::io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut framer = LinkFramer::new();
+    let mut buf = vec![0u8; 4096];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        let timestamp = chrono::Utc::now();
+        writer.write_all(&buf[..n]).await?;
+        for frame in framer.push(&buf[..n]) {
+            sink.stats.record_frame(direction, &frame, timestamp);
+            sink.log_store
+                .push_frame(&sink.session_id, direction, frame, timestamp, Some(endpoint.clone()))
+                .await;
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+// Round-trip latency per request type. Bucket counts cover every sample
+// since the last reset; percentiles are exact over the most recent
+// SAMPLE_WINDOW samples.
+
+/// Upper bounds of the histogram buckets in milliseconds (plus +Inf)
+pub const BUCKET_BOUNDS_MS: [f64; 14] =
+    [1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0, 30000.0];
+
+/// Samples kept for percentiles
+const SAMPLE_WINDOW: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyBucket {
+    /// Upper bound in ms, `None` for +Inf
+    pub le_ms: Option<f64>,
+    /// Samples in this bucket (not cumulative)
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub sum_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+// --- Latency Histogram ---
+pub struct LatencyHistogram {
+    count: u64,
+    sum_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+    buckets: [u64; BUCKET_BOUNDS_MS.len() + 1],
+    samples: VecDeque<f64>,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            sum_ms: 0.0,
+            min_ms: f64::MAX,
+            max_ms: 0.0,
+            buckets: [0; BUCKET_BOUNDS_MS.len() + 1],
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, ms: f64) {
+        self.count += 1;
+        self.sum_ms += ms;
+        self.min_ms = self.min_ms.min(ms);
+        self.max_ms = self.max_ms.max(ms);
+        let bucket = BUCKET_BOUNDS_MS.iter().position(|&b| ms <= b).unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket] += 1;
+        if self.samples.len() >= SAMPLE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ms);
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let buckets = self
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| LatencyBucket { le_ms: BUCKET_BOUNDS_MS.get(i).copied(), count })
+            .collect();
+        LatencySummary {
+            count: self.count,
+            sum_ms: self.sum_ms,
+            min_ms: if self.count > 0 { self.min_ms } else { 0.0 },
+            max_ms: self.max_ms,
+            mean_ms: if self.count > 0 { self.sum_ms / self.count as f64 } else { 0.0 },
+            p50_ms: percentile(&sorted, 0.50),
+            p95_ms: percentile(&sorted, 0.95),
+            p99_ms: percentile(&sorted, 0.99),
+            buckets,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
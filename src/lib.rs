@@ -0,0 +1,31 @@
+// Protocol services shared by the app (desktop window, headless server and
+// CLI subcommands in main.rs) and the integration tests in tests/.
+
+// Stores and counters are built with `new()` and shared behind Arc
+#![allow(clippy::new_without_default)]
+
+pub mod models;
+pub mod dnp3_service;
+pub mod serial_proxy;
+pub mod dnp3_frame_layer;
+pub mod dnp3_link;
+pub mod dnp3_transport;
+pub mod dnp3_app;
+pub mod point_history;
+pub mod soe;
+pub mod frame_capture;
+pub mod pcap;
+pub mod offline_capture;
+pub mod live_events;
+pub mod disk_sink;
+pub mod connection_status;
+pub mod protocol_stats;
+pub mod latency;
+pub mod metrics;
+pub mod site_config;
+pub mod scenario;
+pub mod test_report;
+pub mod outstation_script;
+pub mod replay;
+pub mod self_test;
+pub mod point_import;
@@ -0,0 +1,115 @@
+use serde::Serialize;
+
+use crate::dnp3_service::{ProtocolLogEntry, RawFrame};
+use crate::models::{DataPoint, DataPointType, DataQuality};
+
+// Events pushed to /api/stream subscribers as they happen. Everything is
+// published on one broadcast channel in the LogStore; each subscriber
+// applies its own StreamFilter.
+
+/// Events buffered per subscriber before it starts missing them
+pub const EVENT_CHANNEL_CAPACITY: usize = 2048;
+
+// --- Live Event ---
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    Log(ProtocolLogEntry),
+    Frame(RawFrame),
+    Point(PointEvent),
+    Connection(ConnectionEvent),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PointEvent {
+    pub session_id: String,
+    pub point_type: DataPointType,
+    pub index: u16,
+    pub name: String,
+    pub value: f64,
+    pub quality: DataQuality,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl PointEvent {
+    pub fn new(session_id: &str, point: &DataPoint) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            point_type: point.point_type,
+            index: point.index,
+            name: point.name.clone(),
+            value: point.value,
+            quality: point.quality,
+            timestamp: point.timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionEvent {
+    pub session_id: String,
+    pub state: String, // connecting, connected, waiting, disabled, shutdown
+    pub message: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl LiveEvent {
+    /// SSE event name, also used for the `types` filter
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LiveEvent::Log(_) => "log",
+            LiveEvent::Frame(_) => "frame",
+            LiveEvent::Point(_) => "point",
+            LiveEvent::Connection(_) => "connection",
+        }
+    }
+
+    pub fn session_id(&self) -> &str {
+        match self {
+            LiveEvent::Log(e) => &e.session_id,
+            LiveEvent::Frame(e) => &e.session_id,
+            LiveEvent::Point(e) => &e.session_id,
+            LiveEvent::Connection(e) => &e.session_id,
+        }
+    }
+}
+
+// --- Subscriber Filter ---
+#[derive(Debug, Clone, Default)]
+pub struct StreamFilter {
+    pub types: Option<Vec<String>>,
+    /// Only this session's events, plus process-wide ones (empty session id)
+    pub session: Option<String>,
+    /// "TX" / "RX" for logs and frames
+    pub direction: Option<String>,
+    pub point_type: Option<DataPointType>,
+    pub index: Option<u16>,
+}
+
+impl StreamFilter {
+    pub fn matches(&self, event: &LiveEvent) -> bool {
+        if let Some(types) = &self.types {
+            if !types.iter().any(|t| t == event.kind()) {
+                return false;
+            }
+        }
+        if let Some(session) = &self.session {
+            let event_session = event.session_id();
+            if !(event_session.is_empty() || event_session == session) {
+                return false;
+            }
+        }
+        match event {
+            LiveEvent::Log(e) => self.direction_matches(&e.direction),
+            LiveEvent::Frame(e) => self.direction_matches(&e.direction),
+            LiveEvent::Point(e) => {
+                self.point_type.is_none_or(|t| t == e.point_type) && self.index.is_none_or(|i| i == e.index)
+            }
+            LiveEvent::Connection(_) => true,
+        }
+    }
+
+    fn direction_matches(&self, direction: &str) -> bool {
+        self.direction.as_ref().is_none_or(|d| d.eq_ignore_ascii_case(direction))
+    }
+}
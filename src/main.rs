@@ -1,12 +1,20 @@
-mod models;
-mod dnp3_service;
-mod serial_proxy;
-mod dnp3_frame_layer;
+mod cli;
+mod commands;
+
+// Modules of the library crate, so `crate::models` etc. resolve in cli/commands
+use dnp3_tester::{
+    dnp3_app, dnp3_frame_layer, dnp3_service, disk_sink, live_events, metrics, models, offline_capture, pcap,
+    point_import, replay, scenario, self_test, serial_proxy, site_config, soe, test_report,
+};
 
 use axum::{
-    extract::{State},
+    body::Bytes,
+    extract::{DefaultBodyLimit, Query, State},
     http::HeaderMap,
-    response::{IntoResponse, Response},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     routing::{get, post},
     Json, Router,
 };
@@ -14,8 +22,12 @@ use rust_embed::RustEmbed;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::collections::HashMap;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tower_http::trace::TraceLayer;
+use tokio_util::sync::CancellationToken;
+use clap::Parser;
+use futures::StreamExt;
+use std::net::SocketAddr;
 
 // Native Webview Imports
 use tao::{
@@ -29,8 +41,8 @@ use std::thread;
 use std::sync::mpsc;
 
 use models::*;
-use serial_proxy::{start_serial_proxy_server, start_serial_proxy_client};
 use dnp3_service::Dnp3Service;
+use replay::{MeasurementRecording, ReplayOptions};
 
 #[derive(RustEmbed)]
 #[folder = "frontend/"]
@@ -40,6 +52,9 @@ struct Assets;
 struct AppState {
     sessions: Arc<RwLock<HashMap<String, Arc<Dnp3Service>>>>,
     log_store: Arc<dnp3_service::LogStore>,
+    recording: Arc<RwLock<disk_sink::Recording>>,
+    // Cancelled on SIGINT/SIGTERM in headless mode; ends open SSE streams
+    shutdown: CancellationToken,
 }
 
 // Helper to get session ID from headers
@@ -58,14 +73,57 @@ async fn get_service(state: &AppState, session_id: &str) -> Arc<Dnp3Service> {
         return service.clone();
     }
     
-    // Create new service sharing global logs
-    // NOTE: This enables "Global View" logging (all tabs see all logs)
-    let service = Arc::new(Dnp3Service::new(state.log_store.clone()));
+    // Create new service sharing the global log store; entries are tagged
+    // with the session id so each tab sees its own (or all with ?scope=all)
+    let service = Arc::new(Dnp3Service::new(session_id, state.log_store.clone()));
     sessions.insert(session_id.to_string(), service.clone());
     service
 }
 
+/// Session named by `?session=` without creating it (404 if unknown),
+/// otherwise the caller's own
+async fn lookup_service(
+    state: &AppState,
+    headers: &HeaderMap,
+    session: Option<String>,
+) -> Result<Arc<Dnp3Service>, Response> {
+    let Some(session_id) = session else {
+        return Ok(get_service(state, &get_session_id(headers)).await);
+    };
+    match state.sessions.read().await.get(&session_id) {
+        Some(service) => Ok(service.clone()),
+        None => Err((
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "success": false, "error": format!("Unknown session: {}", session_id) })),
+        )
+            .into_response()),
+    }
+}
+
+/// Drop a session that only lived for one request (self-test, scenario run),
+/// so it doesn't stay in /metrics and /api/status
+async fn remove_service(state: &AppState, session_id: &str) {
+    state.sessions.write().await.remove(session_id);
+}
+
 fn main() {
+    let cli = cli::Cli::parse();
+    if let Some(command) = cli.command {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let code = rt.block_on(async move {
+            match command {
+                cli::Command::Master(cmd) => commands::run_master(cmd).await,
+                cli::Command::Outstation(args) => commands::run_outstation(args).await,
+                cli::Command::Scenario(args) => commands::run_scenario(args).await,
+            }
+        });
+        std::process::exit(code);
+    }
+    if cli.headless {
+        run_headless(cli.server_addr());
+        return;
+    }
+
     // 1. Create Event Loop (Must be on main thread for macOS)
     let event_loop = EventLoop::new();
 
@@ -82,7 +140,8 @@ fn main() {
     thread::spawn(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
-            run_server(tx).await;
+            let loopback = SocketAddr::from(([127, 0, 0, 1], 0));
+            run_server(loopback, tx, CancellationToken::new()).await;
         });
     });
 
@@ -114,7 +173,49 @@ fn main() {
     });
 }
 
-async fn run_server(tx: mpsc::Sender<u16>) {
+/// Web server only, until SIGINT/SIGTERM
+fn run_headless(addr: SocketAddr) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async move {
+        let shutdown = CancellationToken::new();
+        let signal = shutdown.clone();
+        tokio::spawn(async move {
+            let name = shutdown_signal().await;
+            println!("\n🛑 {} received, shutting down", name);
+            signal.cancel();
+        });
+
+        // No window waiting for the port; run_server logs the URL
+        let (tx, _rx) = mpsc::channel();
+        run_server(addr, tx, shutdown).await;
+    });
+    println!("👋 Server stopped");
+}
+
+/// Wait for Ctrl+C, or SIGTERM on Unix; returns the signal name
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = term.recv() => "SIGTERM",
+            },
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl+C"
+    }
+}
+
+async fn run_server(addr: SocketAddr, tx: mpsc::Sender<u16>, shutdown: CancellationToken) {
     // Initialize LogStore (Shared Global)
     let log_store = Arc::new(dnp3_service::LogStore::new());
     
@@ -126,10 +227,10 @@ async fn run_server(tx: mpsc::Sender<u16>) {
     
     // Configure logging/tracing
     let frame_layer = dnp3_frame_layer::Dnp3FrameLayer::new(
-        log_store.raw_frames.clone(),
         log_store.logs.clone(),
-        log_store.frame_counter.clone(),
-        log_store.log_counter.clone()
+        log_store.log_counter.clone(),
+        log_store.session_endpoints.clone(),
+        log_store.events.clone(),
     );
     
     // Set up tracing subscriber with EnvFilter and our custom layer
@@ -145,7 +246,8 @@ async fn run_server(tx: mpsc::Sender<u16>) {
         .with(frame_layer)
         .init();
 
-    let state = AppState { sessions, log_store };
+    let recording = Arc::new(RwLock::new(disk_sink::Recording::default()));
+    let state = AppState { sessions, log_store, recording, shutdown: shutdown.clone() };
 
     // Auto-apply disk `default_config.json` logic using tokio::fs
     let cfg_text = match tokio::fs::read_to_string("default_config.json").await {
@@ -177,28 +279,85 @@ async fn run_server(tx: mpsc::Sender<u16>) {
         .route("/api/serial_ports", get(serial_ports_handler))
         .route("/api/disconnect", post(disconnect_handler))
         .route("/api/config/apply", post(apply_config_handler))
+        .route("/api/config/import", post(import_config_handler).layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)))
         .route("/api/data", get(get_data_handler))
+        .route("/api/status", get(status_handler))
+        .route("/api/stats", get(stats_handler))
+        .route("/api/stats/reset", post(reset_stats_handler))
+        .route("/metrics", get(metrics_handler))
         .route("/api/logs", get(get_logs_handler))
         .route("/api/frames", get(get_frames_handler))
+        .route("/api/stream", get(stream_handler))
+        .route("/api/frames/export.pcapng", get(export_frames_handler))
+        .route("/api/frames/import", post(import_frames_handler).layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)))
+        .route("/api/frames/offline", get(list_offline_handler))
+        .route("/api/frames/offline/delete", post(delete_offline_handler))
+        .route("/api/recording", get(recording_status_handler))
+        .route("/api/recording/start", post(start_recording_handler))
+        .route("/api/recording/stop", post(stop_recording_handler))
+        .route("/api/recording/files", get(recording_files_handler))
+        .route("/api/recording/logs", get(recording_logs_handler))
+        .route("/api/recording/open", post(open_recording_handler))
+        .route("/api/history", get(get_history_handler))
+        .route("/api/soe", get(get_soe_handler))
+        .route("/api/soe/export.csv", get(export_soe_handler))
+        .route("/api/soe/clear", post(clear_soe_handler))
+        .route("/api/soe/record", get(soe_recording_handler))
+        .route("/api/soe/record/start", post(start_soe_recording_handler))
+        .route("/api/soe/record/stop", post(stop_soe_recording_handler))
+        .route("/api/soe/record/export.json", get(export_soe_recording_handler))
+        .route("/api/replay", get(replay_status_handler))
+        .route("/api/replay/start", post(start_replay_handler).layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)))
+        .route("/api/replay/stop", post(stop_replay_handler))
         .route("/api/host_ip", get(host_ip_handler))
         .route("/api/read", post(read_handler))
         .route("/api/control", post(control_handler))
+        .route("/api/scenario/run", post(run_scenario_handler))
+        .route("/api/selftest", post(self_test_handler))
         .route("/api/datapoints/add", post(add_datapoint_handler))
         .route("/api/datapoints/clear", post(clear_datapoints_handler))
-        .with_state(state)
+        .with_state(state.clone())
         .layer(TraceLayer::new_for_http());
 
-    // Bind to random free port
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let port = listener.local_addr().unwrap().port();
+    // Loopback with a random free port for the window, configurable when headless
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("❌ Failed to bind {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
+    let local = listener.local_addr().unwrap();
     
-    println!("\n🚀 DNP3 Tester Internal Server on port {}\n", port);
+    println!("\n🚀 DNP3 Tester Internal Server on port {}\n", local.port());
+    if local.ip().is_unspecified() {
+        // Only reached with an explicit --bind 0.0.0.0 / --bind ::
+        println!("⚠️ Listening on all interfaces as requested by --bind {}, the API has no authentication", local.ip());
+        println!("🌐 Open http://127.0.0.1:{}", local.port());
+    } else if !local.ip().is_loopback() || addr.port() != 0 {
+        println!("🌐 Open http://{}", local);
+    }
     
-    // Notify main thread
-    tx.send(port).unwrap();
+    // Notify main thread (nobody waits in headless mode)
+    let _ = tx.send(local.port());
+
+    // Start server; on shutdown stop accepting, end SSE streams, then let
+    // in-flight requests finish
+    let serve = axum::serve(listener, app).with_graceful_shutdown(shutdown.cancelled_owned());
+    if let Err(e) = serve.await {
+        eprintln!("❌ Server error: {}", e);
+    }
 
-    // Start server
-    axum::serve(listener, app).await.unwrap();
+    // Close DNP3 channels and flush an active recording
+    let sessions: Vec<_> = state.sessions.read().await.values().cloned().collect();
+    for service in sessions {
+        service.disconnect().await;
+    }
+    let recorder = state.recording.write().await.active.take();
+    if let Some(recorder) = recorder {
+        recorder.stop().await;
+        println!("💾 Recording stopped");
+    }
 }
 
 async fn index_handler() -> impl IntoResponse {
@@ -273,6 +432,9 @@ struct ConnectRequest {
     stop_bits: Option<f32>,
     #[serde(default)]
     timeout: Option<u32>,
+    /// Path of an outstation behaviour script on the server
+    #[serde(default)]
+    script: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -364,12 +526,16 @@ async fn connect_handler(
         data_bits: req.data_bits,
         parity: req.parity.clone(),
         stop_bits: req.stop_bits,
+        script: req.script.clone(),
     };
 
         let result = match config.role {
             DeviceRole::Master => service.start_master(&config).await,
             DeviceRole::Outstation => service.start_outstation(&config).await,
         };
+        if let Err(e) = &result {
+            service.status.start_failed(e);
+        }
 
     match result {
         Ok(_) => Json(ApiResponse {
@@ -400,6 +566,65 @@ async fn apply_config_handler(
     })
 }
 
+#[derive(Deserialize)]
+struct ConfigImportQuery {
+    /// File name of the upload; its extension selects the format
+    #[serde(default)]
+    name: Option<String>,
+    /// "csv" or "xlsx"; overrides the file name
+    #[serde(default)]
+    format: Option<String>,
+    /// Also apply the imported table to this session
+    #[serde(default)]
+    apply: bool,
+}
+
+// Point list from a CSV or XLSX upload (same columns as tools/excel_to_config.py)
+async fn import_config_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ConfigImportQuery>,
+    body: Bytes,
+) -> Json<serde_json::Value> {
+    let session_id = get_session_id(&headers);
+    let format = match point_import::Format::detect(query.format.as_deref(), query.name.as_deref(), &body) {
+        Ok(format) => format,
+        Err(e) => return Json(serde_json::json!({ "success": false, "error": e, "errors": [] })),
+    };
+    let config_name = query
+        .name
+        .as_deref()
+        .map(|name| name.rsplit_once('.').map_or(name, |(stem, _)| stem).to_string());
+    let result = tokio::task::spawn_blocking(move || point_import::import(&body, format, config_name))
+        .await
+        .unwrap_or_else(|e| Err((e.to_string(), Vec::new())));
+
+    match result {
+        Ok(import) => {
+            println!(
+                "📥 Imported point list {} [Session {}]: {} point(s), {} row error(s)",
+                query.name.as_deref().unwrap_or("upload"),
+                session_id,
+                import.imported,
+                import.errors.len()
+            );
+            if query.apply {
+                get_service(&state, &session_id).await.update_config(import.config.clone()).await;
+            }
+            Json(serde_json::json!({
+                "success": true,
+                "config": import.config,
+                "counts": import.counts(),
+                "rows": import.rows,
+                "imported": import.imported,
+                "errors": import.errors,
+                "applied": query.apply,
+            }))
+        }
+        Err((error, errors)) => Json(serde_json::json!({ "success": false, "error": error, "errors": errors })),
+    }
+}
+
 async fn disconnect_handler(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -429,15 +654,17 @@ struct SerializedDataPoint {
 
 #[derive(Serialize)]
 struct Stats {
-    tx: u32,
-    rx: u32,
-    errors: u32,
+    tx: u64,
+    rx: u64,
+    errors: u64,
 }
 
 #[derive(Serialize)]
 struct DataResponse {
     points: Vec<SerializedDataPoint>,
     stats: Stats,
+    /// Channel state, see /api/status
+    connection: String,
     logs: Vec<String>,
 }
 
@@ -451,7 +678,7 @@ async fn get_data_handler(
     let service = get_service(&state, &session_id).await;
     
     let points = service.get_data().await;
-    let stats = service.get_stats().await;
+    let stats = service.get_stats();
     
     let serialized_points: Vec<SerializedDataPoint> = points.iter().map(|p| {
         SerializedDataPoint {
@@ -467,14 +694,103 @@ async fn get_data_handler(
     Json(DataResponse {
         points: serialized_points,
         stats: Stats {
-            tx: stats.tx_count,
-            rx: stats.rx_count,
-            errors: stats.error_count,
+            tx: stats.link_frames_tx,
+            rx: stats.link_frames_rx,
+            errors: stats.error_total(),
         },
+        connection: service.status.snapshot().state,
         logs: vec![],
     })
 }
 
+#[derive(Deserialize)]
+struct StatusQuery {
+    /// Session to report (default: the caller's)
+    session: Option<String>,
+    /// "all" for every session
+    scope: Option<String>,
+}
+
+/// Channel state, reconnect counters and state history per session
+async fn status_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<StatusQuery>,
+) -> Response {
+    if query.scope.as_deref() == Some("all") {
+        let sessions = state.sessions.read().await;
+        let mut all: Vec<_> = sessions.values().map(|s| s.status.snapshot()).collect();
+        all.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        return Json(serde_json::json!({ "sessions": all })).into_response();
+    }
+    match lookup_service(&state, &headers, query.session).await {
+        Ok(service) => Json(serde_json::json!(service.status.snapshot())).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// Link, transport and application counters per session
+async fn stats_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<StatusQuery>,
+) -> Response {
+    if query.scope.as_deref() == Some("all") {
+        let sessions = state.sessions.read().await;
+        let mut all: Vec<_> = sessions
+            .iter()
+            .map(|(id, s)| serde_json::json!({ "session_id": id, "stats": s.get_stats() }))
+            .collect();
+        all.sort_by(|a, b| a["session_id"].as_str().cmp(&b["session_id"].as_str()));
+        return Json(serde_json::json!({ "sessions": all })).into_response();
+    }
+    match lookup_service(&state, &headers, query.session).await {
+        Ok(service) => Json(serde_json::json!(service.get_stats())).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// Prometheus scrape endpoint covering every session
+async fn metrics_handler(State(state): State<AppState>) -> Response {
+    let services: Vec<Arc<Dnp3Service>> = state.sessions.read().await.values().cloned().collect();
+    let mut sessions = Vec::with_capacity(services.len());
+    for service in services {
+        sessions.push(metrics::SessionMetrics {
+            status: service.status.snapshot(),
+            stats: service.get_stats(),
+            points: service.get_data().await,
+        });
+    }
+    sessions.sort_by(|a, b| a.status.session_id.cmp(&b.status.session_id));
+    Response::builder()
+        .header("content-type", metrics::CONTENT_TYPE)
+        .body(metrics::render(&sessions).into())
+        .unwrap()
+}
+
+#[derive(Deserialize)]
+struct ResetStatsQuery {
+    /// Session to reset (default: the caller's)
+    session: Option<String>,
+}
+
+async fn reset_stats_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ResetStatsQuery>,
+) -> Response {
+    let service = match lookup_service(&state, &headers, query.session).await {
+        Ok(service) => service,
+        Err(response) => return response,
+    };
+    service.stats.reset();
+    Json(serde_json::json!({
+        "success": true,
+        "message": "Statistics reset"
+    }))
+    .into_response()
+}
+
 // Manual read handler (Master only)
 async fn read_handler(
     State(state): State<AppState>,
@@ -552,6 +868,79 @@ async fn control_handler(
     }
 }
 
+/// Loopback self-test of a point table (body: DeviceConfiguration)
+async fn self_test_handler(
+    State(state): State<AppState>,
+    Json(config): Json<DeviceConfiguration>,
+) -> Json<serde_json::Value> {
+    let prefix = format!("selftest-{}", chrono::Utc::now().timestamp_millis());
+    let (outstation_id, master_id) = (format!("{}-outstation", prefix), format!("{}-master", prefix));
+    let outstation = get_service(&state, &outstation_id).await;
+    let master = get_service(&state, &master_id).await;
+
+    println!("🔁 Self-test '{}' [Session {}]", config.name.as_deref().unwrap_or("unnamed"), prefix);
+    let report = self_test::run(config, outstation, master).await;
+    remove_service(&state, &outstation_id).await;
+    remove_service(&state, &master_id).await;
+    println!(
+        "{} Self-test [Session {}]: {} point(s), {} failure(s){}",
+        if report.passed { "✅" } else { "❌" },
+        prefix,
+        report.points,
+        report.failures.len(),
+        report.error.as_ref().map(|e| format!(" ({})", e)).unwrap_or_default()
+    );
+    Json(serde_json::json!({ "success": true, "report": report }))
+}
+
+#[derive(Deserialize)]
+struct ScenarioQuery {
+    /// Session to run in (default: a new scenario-<millis> session)
+    session: Option<String>,
+    /// "junit" or "html" for a report document instead of JSON
+    format: Option<String>,
+}
+
+/// Run a scenario file (YAML or JSON body) and return its report
+async fn run_scenario_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ScenarioQuery>,
+    body: String,
+) -> Response {
+    let scenario = match scenario::parse(&body) {
+        Ok(scenario) => scenario,
+        Err(e) => return Json(serde_json::json!({ "success": false, "error": e })).into_response(),
+    };
+    let temporary = query.session.is_none();
+    let session_id = query
+        .session
+        .unwrap_or_else(|| format!("scenario-{}", chrono::Utc::now().timestamp_millis()));
+    let service = get_service(&state, &session_id).await;
+
+    println!("🧪 Scenario '{}' [Session {}]: {} step(s)", scenario.name, session_id, scenario.steps.len());
+    let report = scenario::run(&scenario, service).await;
+    if temporary {
+        remove_service(&state, &session_id).await;
+    }
+    println!(
+        "{} Scenario '{}': {} passed, {} failed, {} skipped",
+        if report.passed { "✅" } else { "❌" },
+        report.name,
+        report.count(scenario::StepStatus::Passed),
+        report.count(scenario::StepStatus::Failed),
+        report.count(scenario::StepStatus::Skipped)
+    );
+    let (content_type, body) = match query.format.as_deref() {
+        Some("junit") => (test_report::JUNIT_CONTENT_TYPE, test_report::junit(std::slice::from_ref(&report))),
+        Some("html") => (test_report::HTML_CONTENT_TYPE, test_report::html(std::slice::from_ref(&report))),
+        _ => return Json(serde_json::json!({ "success": true, "report": report })).into_response(),
+    };
+    Response::builder()
+        .header("content-type", content_type)
+        .body(body.into())
+        .unwrap()
+}
+
 #[derive(Serialize)]
 struct LogsResponse {
     logs: Vec<SerializedLogEntry>,
@@ -560,17 +949,55 @@ struct LogsResponse {
 #[derive(Serialize)]
 struct SerializedLogEntry {
     id: u64,
+    session_id: String,
     timestamp: i64,
     direction: String,
     message: String,
 }
 
-async fn get_logs_handler(State(state): State<AppState>) -> Json<LogsResponse> {
+#[derive(Deserialize)]
+struct LogsQuery {
+    /// "all" for the global view across sessions; default is the caller's session
+    scope: Option<String>,
+    /// Only entries with an id greater than this
+    since: Option<u64>,
+    /// Maximum entries returned (oldest first after `since`, otherwise the newest)
+    limit: Option<usize>,
+}
+
+/// Live entries visible to `session_id`: its own plus process-wide (untagged) ones.
+/// `None` means the global view.
+fn visible_to(entry_session: &str, session_id: Option<&str>) -> bool {
+    match session_id {
+        None => true,
+        Some(id) => entry_session == id || entry_session.is_empty(),
+    }
+}
+
+async fn get_logs_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LogsQuery>,
+) -> Json<LogsResponse> {
+    let session_id = get_session_id(&headers);
+    let filter = (query.scope.as_deref() != Some("all")).then_some(session_id.as_str());
     let logs = state.log_store.logs.read().await;
     
-    let serialized: Vec<SerializedLogEntry> = logs.iter().map(|log| {
+    let visible: Vec<_> = logs
+        .iter()
+        .filter(|log| visible_to(&log.session_id, filter))
+        .filter(|log| query.since.is_none_or(|since| log.id > since))
+        .collect();
+    let page = match (query.since, query.limit) {
+        (Some(_), Some(limit)) => &visible[..limit.min(visible.len())],
+        (None, Some(limit)) => &visible[visible.len().saturating_sub(limit)..],
+        (_, None) => &visible[..],
+    };
+
+    let serialized: Vec<SerializedLogEntry> = page.iter().map(|log| {
         SerializedLogEntry {
             id: log.id,
+            session_id: log.session_id.clone(),
             timestamp: log.timestamp.timestamp_millis(),
             direction: log.direction.clone(),
             message: log.message.clone(),
@@ -582,10 +1009,579 @@ async fn get_logs_handler(State(state): State<AppState>) -> Json<LogsResponse> {
     })
 }
 
-async fn get_frames_handler(State(state): State<AppState>) -> Json<serde_json::Value> {
+#[derive(Deserialize)]
+struct FramesQuery {
+    /// Session to view: a live session id or an offline one ("offline-<n>").
+    /// Defaults to the caller's session.
+    session: Option<String>,
+    /// "all" for the global view across live sessions
+    scope: Option<String>,
+    /// Only frames with an id greater than this
+    since: Option<u64>,
+    /// Maximum frames returned (oldest first after `since`, otherwise the newest)
+    limit: Option<usize>,
+}
+
+async fn get_frames_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<FramesQuery>,
+) -> Response {
+    if let Some(id) = query.session.as_deref().filter(|s| s.starts_with("offline-")) {
+        let offline = state.log_store.offline_sessions.read().await;
+        return match offline.get(id) {
+            Some(session) => Json(serde_json::json!({
+                "session": session.info(),
+                "offline": true,
+                "frames": dnp3_app::decode_page(&session.frames.iter().collect::<Vec<_>>(), query.since, query.limit),
+            }))
+            .into_response(),
+            None => (
+                axum::http::StatusCode::NOT_FOUND,
+                Json(serde_json::json!({
+                    "success": false,
+                    "message": format!("Offline session {} not found", id),
+                    "frames": [],
+                })),
+            )
+                .into_response(),
+        };
+    }
+
+    let session_id = query.session.unwrap_or_else(|| get_session_id(&headers));
+    let filter = (query.scope.as_deref() != Some("all")).then_some(session_id.as_str());
     let frames = state.log_store.raw_frames.read().await;
-    let frames_vec: Vec<_> = frames.iter().cloned().collect();
-    Json(serde_json::json!({ "frames": frames_vec }))
+    let visible: Vec<_> = frames.iter().filter(|f| visible_to(&f.session_id, filter)).collect();
+    // Link, transport and application layers decoded for each frame
+    let decoded = dnp3_app::decode_page(&visible, query.since, query.limit);
+    Json(serde_json::json!({ "frames": decoded })).into_response()
+}
+
+#[derive(Deserialize)]
+struct StreamQuery {
+    /// Comma-separated event types: log, frame, point, connection (default: all)
+    types: Option<String>,
+    /// Session to follow; EventSource can't set headers, so it comes as a query param.
+    /// Omit for the global view.
+    session: Option<String>,
+    /// "TX" / "RX" for logs and frames
+    direction: Option<String>,
+    /// Point filters, e.g. point_type=AI&index=3
+    point_type: Option<String>,
+    index: Option<u16>,
+}
+
+/// Server-sent events: point changes, log entries, frames and connection state as they happen
+async fn stream_handler(
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+) -> Response {
+    let point_type = match query.point_type.as_deref().map(DataPointType::from_name) {
+        Some(None) => {
+            return (axum::http::StatusCode::BAD_REQUEST, "Unknown point_type").into_response();
+        }
+        other => other.flatten(),
+    };
+    let filter = live_events::StreamFilter {
+        types: query.types.map(|t| t.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).collect()),
+        session: query.session,
+        direction: query.direction,
+        point_type,
+        index: query.index,
+    };
+
+    let receiver = state.log_store.events.subscribe();
+    let shutdown = state.shutdown.clone().cancelled_owned();
+    let stream = futures::stream::unfold((receiver, filter), |(mut receiver, filter)| async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) if filter.matches(&event) => SseEvent::default()
+                    .event(event.kind())
+                    .json_data(&event)
+                    .unwrap_or_else(|e| SseEvent::default().event("error").data(e.to_string())),
+                Ok(_) => continue,
+                // Slow client: tell it how many events it missed and carry on
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    SseEvent::default().event("lagged").data(missed.to_string())
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            return Some((Ok::<_, std::convert::Infallible>(event), (receiver, filter)));
+        }
+    });
+
+    Sse::new(stream.take_until(shutdown)).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// Largest capture accepted by /api/frames/import
+const MAX_IMPORT_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    /// Original file name, shown in the session list
+    name: Option<String>,
+}
+
+async fn import_frames_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Json<serde_json::Value> {
+    let n = state.log_store.offline_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let id = format!("offline-{}", n);
+    let name = query.name.unwrap_or_else(|| format!("capture {}", n));
+
+    // Parsing a large capture is CPU bound; keep it off the async workers
+    let parse_id = id.clone();
+    let parse_name = name.clone();
+    let result = tokio::task::spawn_blocking(move || offline_capture::import_capture(&parse_id, &parse_name, &body))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+
+    match result {
+        Ok(session) => {
+            let info = session.info();
+            println!("📂 Imported {} as {} ({} frames)", name, id, info.frame_count);
+            state.log_store.offline_sessions.write().await.insert(id, session);
+            Json(serde_json::json!({ "success": true, "session": info }))
+        }
+        Err(e) => Json(serde_json::json!({ "success": false, "message": e })),
+    }
+}
+
+async fn list_offline_handler(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let offline = state.log_store.offline_sessions.read().await;
+    let mut sessions: Vec<_> = offline.values().map(|s| s.info()).collect();
+    sessions.sort_by_key(|s| s.imported_at);
+    Json(serde_json::json!({ "sessions": sessions }))
+}
+
+async fn delete_offline_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FramesQuery>,
+) -> Json<ApiResponse> {
+    let removed = match query.session {
+        Some(id) => state.log_store.offline_sessions.write().await.remove(&id).is_some(),
+        None => false,
+    };
+    Json(ApiResponse {
+        success: removed,
+        error: (!removed).then(|| "Offline session not found".to_string()),
+    })
+}
+
+// --- Disk Recording ---
+
+async fn recording_status_handler(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let recording = state.recording.read().await;
+    Json(serde_json::json!({
+        "active": recording.active.is_some(),
+        "config": recording.config,
+        "started_at": recording.active.as_ref().map(|r| r.started_at),
+        "stats": recording.active.as_ref().map(|r| r.stats()),
+    }))
+}
+
+/// Start recording to disk (restarting with the new settings if already running).
+/// An empty body keeps the previous settings.
+async fn start_recording_handler(
+    State(state): State<AppState>,
+    body: Bytes,
+) -> Json<ApiResponse> {
+    let mut recording = state.recording.write().await;
+    if !body.is_empty() {
+        match serde_json::from_slice::<disk_sink::RecordingConfig>(&body) {
+            Ok(config) => recording.config = config,
+            Err(e) => {
+                return Json(ApiResponse { success: false, error: Some(format!("Invalid recording settings: {}", e)) });
+            }
+        }
+    }
+    if let Some(previous) = recording.active.take() {
+        previous.stop().await;
+    }
+    match disk_sink::start_recording(recording.config.clone(), &state.log_store).await {
+        Ok(recorder) => {
+            println!("⏺️ Recording to {}", recording.config.directory);
+            recording.active = Some(recorder);
+            Json(ApiResponse { success: true, error: None })
+        }
+        Err(e) => Json(ApiResponse { success: false, error: Some(e) }),
+    }
+}
+
+async fn stop_recording_handler(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let recorder = state.recording.write().await.active.take();
+    match recorder {
+        Some(recorder) => {
+            let stats = recorder.stats();
+            recorder.stop().await;
+            Json(serde_json::json!({ "success": true, "stats": stats }))
+        }
+        None => Json(serde_json::json!({ "success": false, "error": "Not recording" })),
+    }
+}
+
+#[derive(Deserialize)]
+struct RecordingFileQuery {
+    /// Recording directory to browse (default: the configured one)
+    dir: Option<String>,
+    /// File name as returned by /api/recording/files
+    file: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+impl RecordingFileQuery {
+    async fn directory(&self, state: &AppState) -> String {
+        match &self.dir {
+            Some(dir) => dir.clone(),
+            None => state.recording.read().await.config.directory.clone(),
+        }
+    }
+
+    async fn path(&self, state: &AppState) -> Result<std::path::PathBuf, String> {
+        let file = self.file.as_deref().ok_or("Missing file parameter")?;
+        disk_sink::resolve(&self.directory(state).await, file)
+    }
+}
+
+async fn recording_files_handler(
+    State(state): State<AppState>,
+    Query(query): Query<RecordingFileQuery>,
+) -> Json<serde_json::Value> {
+    let directory = query.directory(&state).await;
+    match disk_sink::list_files(&directory).await {
+        Ok(files) => Json(serde_json::json!({ "success": true, "directory": directory, "files": files })),
+        Err(e) => Json(serde_json::json!({ "success": false, "error": e, "files": [] })),
+    }
+}
+
+/// Browse a recorded log file, `limit` entries (default 500) from `offset`
+async fn recording_logs_handler(
+    State(state): State<AppState>,
+    Query(query): Query<RecordingFileQuery>,
+) -> Json<serde_json::Value> {
+    let result = match query.path(&state).await {
+        Ok(path) => disk_sink::read_log_file(&path, query.offset.unwrap_or(0), query.limit.unwrap_or(500)).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(logs) => Json(serde_json::json!({ "success": true, "logs": logs })),
+        Err(e) => Json(serde_json::json!({ "success": false, "error": e, "logs": [] })),
+    }
+}
+
+/// Reopen a recorded frames file as an offline session, browsable like an imported capture
+async fn open_recording_handler(
+    State(state): State<AppState>,
+    Query(query): Query<RecordingFileQuery>,
+) -> Json<serde_json::Value> {
+    let bytes = match query.path(&state).await {
+        Ok(path) => tokio::fs::read(&path).await.map_err(|e| format!("Cannot read {}: {}", path.display(), e)),
+        Err(e) => Err(e),
+    };
+    let bytes = match bytes {
+        Ok(bytes) => bytes,
+        Err(e) => return Json(serde_json::json!({ "success": false, "message": e })),
+    };
+
+    let n = state.log_store.offline_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let id = format!("offline-{}", n);
+    let name = query.file.clone().unwrap_or_default();
+    let parse_id = id.clone();
+    let result = tokio::task::spawn_blocking(move || offline_capture::import_capture(&parse_id, &name, &bytes))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+
+    match result {
+        Ok(session) => {
+            let info = session.info();
+            state.log_store.offline_sessions.write().await.insert(id, session);
+            Json(serde_json::json!({ "success": true, "session": info }))
+        }
+        Err(e) => Json(serde_json::json!({ "success": false, "message": e })),
+    }
+}
+
+#[derive(Deserialize)]
+struct FrameExportQuery {
+    /// Only export frames captured by this session (default: all sessions)
+    session: Option<String>,
+}
+
+async fn export_frames_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FrameExportQuery>,
+) -> Response {
+    let capture = match query.session.as_deref().filter(|s| s.starts_with("offline-")) {
+        Some(id) => {
+            let offline = state.log_store.offline_sessions.read().await;
+            let Some(session) = offline.get(id) else {
+                return (axum::http::StatusCode::NOT_FOUND, format!("Unknown offline session: {}", id)).into_response();
+            };
+            pcap::write_pcapng(&session.frames)
+        }
+        None => {
+            let frames = state.log_store.raw_frames.read().await;
+            let selected = frames
+                .iter()
+                .filter(|f| query.session.as_ref().is_none_or(|s| &f.session_id == s));
+            pcap::write_pcapng(selected)
+        }
+    };
+    let filename = format!("dnp3_{}.pcapng", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
+    Response::builder()
+        .header("content-type", "application/x-pcapng")
+        .header("content-disposition", format!("attachment; filename=\"{}\"", filename))
+        .body(capture.into())
+        .unwrap()
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    #[serde(rename = "type")]
+    point_type: String,
+    index: u16,
+    /// Window start, Unix epoch milliseconds
+    #[serde(default)]
+    from: Option<i64>,
+    /// Window end, Unix epoch milliseconds
+    #[serde(default)]
+    to: Option<i64>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct SerializedSample {
+    timestamp: i64,
+    value: f64,
+    flags: u8,
+    online: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device_time: Option<i64>,
+}
+
+// Per-point change history for trend plots
+async fn get_history_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<HistoryQuery>,
+) -> Json<serde_json::Value> {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+
+    let Some(point_type) = DataPointType::from_name(&query.point_type) else {
+        return Json(serde_json::json!({
+            "success": false,
+            "error": format!("Invalid point type: {}", query.point_type)
+        }));
+    };
+
+    let from = query.from.and_then(chrono::DateTime::from_timestamp_millis);
+    let to = query.to.and_then(chrono::DateTime::from_timestamp_millis);
+
+    let samples: Vec<SerializedSample> = service.history.lock()
+        .map(|history| history.query(point_type, query.index, from, to, query.limit))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|s| SerializedSample {
+            timestamp: s.timestamp.timestamp_millis(),
+            value: s.value,
+            flags: s.flags,
+            online: s.flags & 0x01 != 0,
+            device_time: s.device_time.map(|t| t.timestamp_millis()),
+        })
+        .collect();
+
+    Json(serde_json::json!({
+        "success": true,
+        "type": format!("{:?}", point_type),
+        "index": query.index,
+        "samples": samples
+    }))
+}
+
+#[derive(Deserialize)]
+struct SoeQuery {
+    #[serde(default)]
+    since: Option<u64>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+// Sequence-of-events records (static vs event data, read type, device time)
+async fn get_soe_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SoeQuery>,
+) -> Json<serde_json::Value> {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+    let records = service.soe.lock().map(|soe| soe.list(query.since, query.limit)).unwrap_or_default();
+    Json(serde_json::json!({ "records": records }))
+}
+
+async fn export_soe_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+    let csv = service.soe.lock().map(|soe| soe.to_csv()).unwrap_or_default();
+    Response::builder()
+        .header("content-type", "text/csv; charset=utf-8")
+        .header("content-disposition", format!("attachment; filename=\"soe_{}.csv\"", session_id))
+        .body(csv.into())
+        .unwrap()
+}
+
+async fn clear_soe_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<ApiResponse> {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+    if let Ok(mut soe) = service.soe.lock() {
+        soe.clear();
+    }
+    Json(ApiResponse {
+        success: true,
+        error: None,
+    })
+}
+
+// --- Measurement Recording & Replay ---
+
+async fn soe_recording_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<serde_json::Value> {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+    let info = service.soe.lock().ok().and_then(|soe| soe.recording().map(|r| r.info()));
+    Json(serde_json::json!({ "recording": info }))
+}
+
+// Record every measurement this master session receives, with its arrival time
+async fn start_soe_recording_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<serde_json::Value> {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+    let info = service.soe.lock().ok().and_then(|mut soe| {
+        soe.start_recording(&session_id);
+        soe.recording().map(|r| r.info())
+    });
+    println!("⏺️ Measurement recording started [Session {}]", session_id);
+    Json(serde_json::json!({ "success": true, "recording": info }))
+}
+
+async fn stop_soe_recording_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<serde_json::Value> {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+    let info = service.soe.lock().ok().and_then(|mut soe| soe.stop_recording().map(|r| r.info()));
+    match info {
+        Some(info) => {
+            println!("⏹️ Measurement recording stopped [Session {}]: {} record(s)", session_id, info["records"]);
+            Json(serde_json::json!({ "success": true, "recording": info }))
+        }
+        None => Json(serde_json::json!({ "success": false, "error": "No recording in this session" })),
+    }
+}
+
+async fn export_soe_recording_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+    let json = service
+        .soe
+        .lock()
+        .ok()
+        .and_then(|soe| soe.recording().map(|r| serde_json::to_string_pretty(r).unwrap_or_default()));
+    let Some(json) = json else {
+        return (axum::http::StatusCode::NOT_FOUND, "No recording in this session").into_response();
+    };
+    Response::builder()
+        .header("content-type", "application/json")
+        .header("content-disposition", format!("attachment; filename=\"measurements_{}.json\"", session_id))
+        .body(json.into())
+        .unwrap()
+}
+
+#[derive(Deserialize)]
+struct ReplayQuery {
+    /// Take the recording held by this master session instead of the body
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    speed: Option<f64>,
+    #[serde(default)]
+    repeat: bool,
+}
+
+/// Play a recording (exported JSON body, or `?from=<session>`) into this outstation
+async fn start_replay_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ReplayQuery>,
+    body: String,
+) -> Response {
+    let session_id = get_session_id(&headers);
+    let recording = match &query.from {
+        Some(from) => {
+            let source = state.sessions.read().await.get(from).cloned();
+            source
+                .and_then(|source| source.soe.lock().ok().and_then(|soe| soe.recording().cloned()))
+                .ok_or_else(|| format!("No recording in session {}", from))
+                .and_then(MeasurementRecording::playable)
+        }
+        None => MeasurementRecording::parse(&body),
+    };
+    let recording = match recording {
+        Ok(recording) => recording,
+        Err(e) => return (axum::http::StatusCode::BAD_REQUEST, Json(serde_json::json!({ "success": false, "error": e }))).into_response(),
+    };
+    let options = ReplayOptions {
+        speed: query.speed.unwrap_or(1.0),
+        repeat: query.repeat,
+    };
+    let service = get_service(&state, &session_id).await;
+    match service.start_replay(recording, options).await {
+        Ok(progress) => {
+            println!("▶️ Replay started [Session {}]: {} record(s) from {}", session_id, progress.records, progress.source_session);
+            Json(serde_json::json!({ "success": true, "replay": progress })).into_response()
+        }
+        Err(e) => (axum::http::StatusCode::BAD_REQUEST, Json(serde_json::json!({ "success": false, "error": e }))).into_response(),
+    }
+}
+
+async fn stop_replay_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<serde_json::Value> {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+    let progress = service.stop_replay();
+    Json(serde_json::json!({ "success": progress.is_some(), "replay": progress }))
+}
+
+async fn replay_status_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<serde_json::Value> {
+    let session_id = get_session_id(&headers);
+    let service = get_service(&state, &session_id).await;
+    Json(serde_json::json!({ "replay": service.replay_progress() }))
 }
 
 async fn host_ip_handler() -> Json<serde_json::Value> {
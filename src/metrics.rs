@@ -0,0 +1,149 @@
+use std::fmt::Write;
+
+use crate::connection_status::ConnectionStatus;
+use crate::models::{DataPoint, DataPointType};
+use crate::protocol_stats::ProtocolStats;
+
+// Prometheus text exposition (format 0.0.4) for /metrics. Every series
+// carries `session` and `role` labels; all samples of a family are written
+// together, as the format requires.
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Channel states exported as one 0/1 series each
+const STATES: [&str; 6] = ["idle", "connecting", "connected", "waiting", "disabled", "shutdown"];
+
+/// What one session contributes to a scrape
+pub struct SessionMetrics {
+    pub status: ConnectionStatus,
+    pub stats: ProtocolStats,
+    pub points: Vec<DataPoint>,
+}
+
+impl SessionMetrics {
+    fn labels(&self) -> String {
+        let role = if self.status.role.is_empty() { "none" } else { &self.status.role };
+        format!("session=\"{}\",role=\"{}\"", escape(&self.status.session_id), escape(role))
+    }
+}
+
+pub fn render(sessions: &[SessionMetrics]) -> String {
+    let mut out = String::new();
+
+    family(&mut out, "dnp3_sessions", "gauge", "Sessions known to the tester");
+    let _ = writeln!(out, "dnp3_sessions {}", sessions.len());
+
+    family(&mut out, "dnp3_connection_state", "gauge", "Channel state, 1 for the current state");
+    for s in sessions {
+        for state in STATES {
+            let value = u8::from(s.status.state == state);
+            let _ = writeln!(out, "dnp3_connection_state{{{},state=\"{}\"}} {}", s.labels(), state, value);
+        }
+    }
+    per_session(&mut out, sessions, "dnp3_reconnects_total", "counter", "Reconnections since the channel was started", |s| {
+        s.status.reconnect_count as f64
+    });
+
+    family(&mut out, "dnp3_link_frames_total", "counter", "Link frames by direction (tx = sent by this session)");
+    directional(&mut out, sessions, "dnp3_link_frames_total", |s| (s.link_frames_tx, s.link_frames_rx));
+    family(&mut out, "dnp3_bytes_total", "counter", "Bytes on the wire by direction");
+    directional(&mut out, sessions, "dnp3_bytes_total", |s| (s.bytes_tx, s.bytes_rx));
+    family(&mut out, "dnp3_fragments_total", "counter", "Complete application fragments by direction");
+    directional(&mut out, sessions, "dnp3_fragments_total", |s| (s.fragments_tx, s.fragments_rx));
+
+    family(&mut out, "dnp3_errors_total", "counter", "Errors by kind");
+    for s in sessions {
+        let st = &s.stats;
+        for (kind, value) in [
+            ("crc", st.crc_errors),
+            ("framing", st.framing_errors),
+            ("transport", st.transport_errors),
+            ("timeout", st.timeouts),
+            ("task", st.task_failures),
+            ("control", st.controls_failed),
+        ] {
+            let _ = writeln!(out, "dnp3_errors_total{{{},kind=\"{}\"}} {}", s.labels(), kind, value);
+        }
+    }
+    per_session(&mut out, sessions, "dnp3_unsolicited_fragments_total", "counter", "Unsolicited responses", |s| {
+        s.stats.unsolicited_fragments as f64
+    });
+    per_session(&mut out, sessions, "dnp3_retries_total", "counter", "Retransmitted fragments", |s| s.stats.retries as f64);
+    per_session(&mut out, sessions, "dnp3_controls_ok_total", "counter", "Successful controls", |s| {
+        s.stats.controls_ok as f64
+    });
+
+    family(&mut out, "dnp3_analog_value", "gauge", "Current value of analog points");
+    for s in sessions {
+        for p in s.points.iter().filter(|p| matches!(p.point_type, DataPointType::AnalogInput | DataPointType::AnalogOutput)) {
+            let _ = writeln!(
+                out,
+                "dnp3_analog_value{{{},point_type=\"{:?}\",index=\"{}\",name=\"{}\"}} {}",
+                s.labels(),
+                p.point_type,
+                p.index,
+                escape(&p.name),
+                number(p.value)
+            );
+        }
+    }
+
+    family(&mut out, "dnp3_request_latency_seconds", "histogram", "Request/response round trip by request type");
+    for s in sessions {
+        for (request, summary) in &s.stats.latency {
+            let labels = format!("{},request=\"{}\"", s.labels(), escape(request));
+            let mut cumulative = 0;
+            for bucket in &summary.buckets {
+                cumulative += bucket.count;
+                let le = bucket.le_ms.map(|ms| number(ms / 1000.0)).unwrap_or_else(|| "+Inf".to_string());
+                let _ = writeln!(out, "dnp3_request_latency_seconds_bucket{{{},le=\"{}\"}} {}", labels, le, cumulative);
+            }
+            let _ = writeln!(out, "dnp3_request_latency_seconds_sum{{{}}} {}", labels, number(summary.sum_ms / 1000.0));
+            let _ = writeln!(out, "dnp3_request_latency_seconds_count{{{}}} {}", labels, summary.count);
+        }
+    }
+
+    out
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// One series per session
+fn per_session(
+    out: &mut String,
+    sessions: &[SessionMetrics],
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl Fn(&SessionMetrics) -> f64,
+) {
+    family(out, name, kind, help);
+    for s in sessions {
+        let _ = writeln!(out, "{}{{{}}} {}", name, s.labels(), number(value(s)));
+    }
+}
+
+fn directional(out: &mut String, sessions: &[SessionMetrics], name: &str, value: impl Fn(&ProtocolStats) -> (u64, u64)) {
+    for s in sessions {
+        let (tx, rx) = value(&s.stats);
+        let _ = writeln!(out, "{}{{{},direction=\"tx\"}} {}", name, s.labels(), tx);
+        let _ = writeln!(out, "{}{{{},direction=\"rx\"}} {}", name, s.labels(), rx);
+    }
+}
+
+fn number(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
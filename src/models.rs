@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DataPointType {
     BinaryInput,
     BinaryOutput,
@@ -10,6 +10,20 @@ pub enum DataPointType {
     Counter,
 }
 
+impl DataPointType {
+    /// Parse a point type name as used by the API ("AnalogInput") or its short alias ("AI")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace([' ', '_'], "").as_str() {
+            "binaryinput" | "bi" => Some(DataPointType::BinaryInput),
+            "binaryoutput" | "bo" => Some(DataPointType::BinaryOutput),
+            "analoginput" | "ai" => Some(DataPointType::AnalogInput),
+            "analogoutput" | "ao" => Some(DataPointType::AnalogOutput),
+            "counter" | "c" => Some(DataPointType::Counter),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DataQuality {
     Online,
@@ -106,6 +120,9 @@ pub struct Configuration {
     pub parity: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop_bits: Option<f32>,
+    /// Rhai behaviour script for an outstation (see outstation_script.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
 }
 
 impl Default for Configuration {
@@ -123,6 +140,7 @@ impl Default for Configuration {
             data_bits: None,
             parity: None,
             stop_bits: None,
+            script: None,
         }
     }
 }
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::dnp3_link::{self, LinkFramer};
+use crate::dnp3_service::RawFrame;
+use crate::frame_capture::CaptureEndpoint;
+use crate::pcap::{self, CapturedPacket};
+
+// Offline analysis of pcap/pcapng captures sent in from site. Packets are
+// dissected down to their TCP/UDP payloads (or taken whole for serial
+// DLT_USER captures), reassembled per stream and cut into link frames, so
+// they go through the same decoding as live traffic.
+
+/// Upper bound on frames kept from one capture
+const MAX_OFFLINE_FRAMES: usize = 100_000;
+
+// --- Offline Session ---
+#[derive(Debug, Clone)]
+pub struct OfflineSession {
+    pub id: String,
+    pub name: String,
+    pub imported_at: chrono::DateTime<chrono::Utc>,
+    pub packet_count: usize,
+    pub stream_count: usize,
+    pub frames: Vec<RawFrame>,
+}
+
+/// Summary returned by the list endpoint (frames omitted)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OfflineSessionInfo {
+    pub id: String,
+    pub name: String,
+    pub imported_at: chrono::DateTime<chrono::Utc>,
+    pub packet_count: usize,
+    pub stream_count: usize,
+    pub frame_count: usize,
+}
+
+impl OfflineSession {
+    pub fn info(&self) -> OfflineSessionInfo {
+        OfflineSessionInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            imported_at: self.imported_at,
+            packet_count: self.packet_count,
+            stream_count: self.stream_count,
+            frame_count: self.frames.len(),
+        }
+    }
+}
+
+/// Parse a capture file and extract every DNP3 link frame it carries
+pub fn import_capture(id: &str, name: &str, bytes: &[u8]) -> Result<OfflineSession, String> {
+    let packets = pcap::read_capture(bytes)?;
+    let mut extractor = StreamExtractor::new(id);
+    for packet in &packets {
+        extractor.push(packet);
+        if extractor.frames.len() >= MAX_OFFLINE_FRAMES {
+            break;
+        }
+    }
+    let stream_count = extractor.endpoints.len();
+    if extractor.frames.is_empty() {
+        return Err(format!("No DNP3 frames found in {} packets", packets.len()));
+    }
+    Ok(OfflineSession {
+        id: id.to_string(),
+        name: name.to_string(),
+        imported_at: chrono::Utc::now(),
+        packet_count: packets.len(),
+        stream_count,
+        frames: extractor.frames,
+    })
+}
+
+// ============================================================================
+// PACKET DISSECTION
+// ============================================================================
+
+const LINKTYPE_NULL: u16 = 0;
+const LINKTYPE_ETHERNET: u16 = 1;
+const LINKTYPE_LOOP: u16 = 108;
+const LINKTYPE_LINUX_SLL: u16 = 113;
+const LINKTYPE_IPV4: u16 = 228;
+const LINKTYPE_IPV6: u16 = 229;
+const LINKTYPE_LINUX_SLL2: u16 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88A8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Transport {
+    Tcp,
+    Udp,
+    Serial,
+}
+
+/// A transport payload pulled out of one packet
+struct Payload<'a> {
+    transport: Transport,
+    src: SocketAddr,
+    dst: SocketAddr,
+    tcp_seq: Option<u32>,
+    data: &'a [u8],
+}
+
+/// Peel the link layer off a packet and return the IP datagram inside
+fn ip_datagram(link_type: u16, data: &[u8]) -> Option<&[u8]> {
+    match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = u16::from_be_bytes([*data.get(12)?, *data.get(13)?]);
+            let mut offset = 14;
+            while ethertype == ETHERTYPE_VLAN || ethertype == ETHERTYPE_QINQ {
+                ethertype = u16::from_be_bytes([*data.get(offset + 2)?, *data.get(offset + 3)?]);
+                offset += 4;
+            }
+            matches!(ethertype, ETHERTYPE_IPV4 | ETHERTYPE_IPV6).then(|| data.get(offset..))?
+        }
+        pcap::LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Some(data),
+        LINKTYPE_NULL | LINKTYPE_LOOP => data.get(4..),
+        LINKTYPE_LINUX_SLL => data.get(16..),
+        LINKTYPE_LINUX_SLL2 => data.get(20..),
+        _ => None,
+    }
+}
+
+/// Dissect IPv4/IPv6 and TCP/UDP down to the payload
+fn transport_payload(datagram: &[u8]) -> Option<Payload<'_>> {
+    let version = datagram.first()? >> 4;
+    let (src_ip, dst_ip, protocol, segment) = match version {
+        4 => {
+            let ihl = (datagram[0] & 0x0F) as usize * 4;
+            if ihl < 20 {
+                return None;
+            }
+            let total = (u16::from_be_bytes([*datagram.get(2)?, *datagram.get(3)?]) as usize).min(datagram.len());
+            let fragment_offset = u16::from_be_bytes([*datagram.get(6)?, *datagram.get(7)?]) & 0x1FFF;
+            if fragment_offset != 0 {
+                return None; // Non-first fragments carry no transport header
+            }
+            let src: [u8; 4] = datagram.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = datagram.get(16..20)?.try_into().ok()?;
+            (IpAddr::V4(Ipv4Addr::from(src)), IpAddr::V4(Ipv4Addr::from(dst)), *datagram.get(9)?, datagram.get(ihl..total)?)
+        }
+        6 => {
+            let payload_len = u16::from_be_bytes([*datagram.get(4)?, *datagram.get(5)?]) as usize;
+            let src: [u8; 16] = datagram.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = datagram.get(24..40)?.try_into().ok()?;
+            let end = (40 + payload_len).min(datagram.len());
+            (IpAddr::V6(Ipv6Addr::from(src)), IpAddr::V6(Ipv6Addr::from(dst)), *datagram.get(6)?, datagram.get(40..end)?)
+        }
+        _ => return None,
+    };
+
+    let src_port = u16::from_be_bytes([*segment.first()?, *segment.get(1)?]);
+    let dst_port = u16::from_be_bytes([*segment.get(2)?, *segment.get(3)?]);
+    let src = SocketAddr::new(src_ip, src_port);
+    let dst = SocketAddr::new(dst_ip, dst_port);
+    match protocol {
+        6 => {
+            let seq = u32::from_be_bytes(segment.get(4..8)?.try_into().ok()?);
+            let data_offset = (*segment.get(12)? >> 4) as usize * 4;
+            Some(Payload { transport: Transport::Tcp, src, dst, tcp_seq: Some(seq), data: segment.get(data_offset..)? })
+        }
+        17 => Some(Payload { transport: Transport::Udp, src, dst, tcp_seq: None, data: segment.get(8..)? }),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// STREAM REASSEMBLY
+// ============================================================================
+
+/// One direction of a conversation
+struct Stream {
+    framer: LinkFramer,
+    next_seq: Option<u32>,
+    /// Direction of the last decoded frame, used for bytes that don't decode
+    last_direction: &'static str,
+    /// Set once a valid link frame is seen; until then undecodable bytes are
+    /// other protocols' traffic and are not kept
+    is_dnp3: bool,
+}
+
+/// Turns packets into frames, keeping a framer per stream direction
+struct StreamExtractor {
+    session_id: String,
+    streams: HashMap<(Transport, SocketAddr, SocketAddr), Stream>,
+    /// One endpoint per conversation so both directions share a connection id
+    endpoints: HashMap<(Transport, SocketAddr, SocketAddr), CaptureEndpoint>,
+    frames: Vec<RawFrame>,
+}
+
+impl StreamExtractor {
+    fn new(session_id: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            streams: HashMap::new(),
+            endpoints: HashMap::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, packet: &CapturedPacket) {
+        let payload = if packet.link_type == pcap::LINKTYPE_USER0 {
+            // Serial capture: the packet is the byte stream itself
+            let placeholder = SocketAddr::from(([0, 0, 0, 0], 0));
+            Payload { transport: Transport::Serial, src: placeholder, dst: placeholder, tcp_seq: None, data: &packet.data }
+        } else {
+            match ip_datagram(packet.link_type, &packet.data).and_then(transport_payload) {
+                Some(p) => p,
+                None => return,
+            }
+        };
+        if payload.data.is_empty() {
+            return;
+        }
+
+        let key = (payload.transport, payload.src, payload.dst);
+        let stream = self.streams.entry(key).or_insert_with(|| Stream {
+            framer: LinkFramer::new(),
+            next_seq: None,
+            last_direction: "RX",
+            is_dnp3: false,
+        });
+
+        // TCP: drop retransmitted bytes, accept gaps (lost packets) as they come
+        let mut data = payload.data;
+        if let Some(seq) = payload.tcp_seq {
+            let behind = stream.next_seq.map(|expected| expected.wrapping_sub(seq) as i32).unwrap_or(0);
+            if behind > 0 {
+                if behind as usize >= data.len() {
+                    return;
+                }
+                data = &data[behind as usize..];
+            }
+            let start = seq.wrapping_add(behind.max(0) as u32);
+            stream.next_seq = Some(start.wrapping_add(data.len() as u32));
+        }
+
+        let chunks = stream.framer.push(data);
+        self.emit(key, chunks, packet.timestamp_us);
+    }
+
+    fn emit(&mut self, key: (Transport, SocketAddr, SocketAddr), chunks: Vec<Vec<u8>>, timestamp_us: i64) {
+        for chunk in chunks {
+            // The link DIR bit says whether the master sent the frame; the
+            // offline session is presented from the master's point of view
+            let decoded_dir = dnp3_link::parse_frame(&chunk).ok().map(|(info, _)| info.dir);
+            let stream = self.streams.get_mut(&key).expect("stream exists");
+            let direction = match decoded_dir {
+                Some(true) => "TX",
+                Some(false) => "RX",
+                None if stream.is_dnp3 => stream.last_direction,
+                None => continue,
+            };
+            stream.last_direction = direction;
+            stream.is_dnp3 = true;
+
+            let (transport, src, dst) = key;
+            let conversation = if src <= dst { (transport, src, dst) } else { (transport, dst, src) };
+            let endpoint = self
+                .endpoints
+                .entry(conversation)
+                .or_insert_with(|| {
+                    let (master, outstation) = if direction == "TX" { (src, dst) } else { (dst, src) };
+                    match transport {
+                        Transport::Serial => CaptureEndpoint::new("serial", String::new(), "serial".to_string()),
+                        Transport::Tcp => CaptureEndpoint::new("tcp", master.to_string(), outstation.to_string()),
+                        Transport::Udp => CaptureEndpoint::new("udp", master.to_string(), outstation.to_string()),
+                    }
+                })
+                .clone();
+
+            let id = self.frames.len() as u64;
+            let timestamp = chrono::DateTime::from_timestamp_micros(timestamp_us).unwrap_or_default();
+            self.frames.push(RawFrame::new(id, &self.session_id, direction, chunk, timestamp, Some(endpoint)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dnp3_app;
+
+    /// Link frame from outstation 10 to master 1 carrying one transport segment
+    fn link_frame(apdu: &[u8]) -> Vec<u8> {
+        let mut user_data = vec![0xC0];
+        user_data.extend_from_slice(apdu);
+        let mut bytes = vec![0x05, 0x64, (user_data.len() + 5) as u8, 0x44, 1, 0, 10, 0];
+        bytes.extend_from_slice(&dnp3_link::crc16(&bytes).to_le_bytes());
+        for block in user_data.chunks(16) {
+            bytes.extend_from_slice(block);
+            bytes.extend_from_slice(&dnp3_link::crc16(block).to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn oversized_object_range_in_capture_is_reported() {
+        // g110v0 with a 0x0FFFFFFF-object range in a 15-byte response
+        let apdu = [0xC0, 0x81, 0x00, 0x00, 0x6E, 0x00, 0x02, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0x0F];
+        let frame = RawFrame::new(0, "site", "RX", link_frame(&apdu), chrono::Utc::now(), None);
+        let capture = pcap::write_pcapng([&frame]);
+
+        let session = import_capture("offline-0", "crafted.pcapng", &capture).unwrap();
+        assert_eq!(session.frames.len(), 1);
+        let decoded = dnp3_app::decode_frames(&session.frames);
+        let apdu = decoded[0].apdu.as_ref().expect("fragment decoded");
+        assert!(apdu.headers.is_empty());
+        assert_eq!(apdu.error.as_deref(), Some("g110v0: zero-length octet string objects"));
+    }
+}
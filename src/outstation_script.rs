@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use dnp3::app::control::CommandStatus;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+
+use crate::models::{DataPoint, DataPointType, DataQuality};
+
+// Device-specific outstation behaviour in Rhai, loaded per outstation from
+// the `script` path of its configuration. Optional hooks:
+//
+//   fn on_tick()            every simulation cycle; replaces the random values
+//   fn on_select(cmd)       cmd = #{point_type, index, value, op, mode}
+//   fn on_operate(cmd)      return () or "Success" for the built-in handling,
+//                           or a CommandStatus name/number to reject
+//   fn on_time_sync(ms)     absolute time write; return false to reject
+//
+// Scripts use get(type, index), flags(type, index), set(type, index, value),
+// set_flags(type, index, flags), schedule(delay_ms, type, index, value) and
+// log(text); types are names or aliases ("BI", "AnalogOutput"). `this` is an
+// object map kept between calls, for interlock state and the like.
+//
+// Hooks run synchronously inside the dnp3 callbacks. They read a mirror of
+// the point values and only record their writes; the caller applies them to
+// the database and the point table.
+
+/// Bound on script work per hook call, so a runaway loop can't stall the outstation
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+const DEFAULT_FLAGS: u8 = 0x01; // ONLINE
+
+/// A point change requested by a script
+#[derive(Debug, Clone)]
+pub struct PointWrite {
+    pub point_type: DataPointType,
+    pub index: u16,
+    pub value: f64,
+    pub flags: u8,
+}
+
+/// Changes and messages from one hook call
+#[derive(Debug, Default)]
+pub struct HookOutcome {
+    pub writes: Vec<PointWrite>,
+    pub scheduled: Vec<(Duration, PointWrite)>,
+    pub logs: Vec<String>,
+}
+
+/// A select or operate as passed to on_select / on_operate
+pub struct CommandInfo {
+    pub point_type: DataPointType,
+    pub index: u16,
+    pub value: f64,
+    /// LatchOn, PulseOff, ... for CROBs; empty for analog outputs
+    pub op: String,
+    /// Select, SelectBeforeOperate, DirectOperate, DirectOperateNoAck
+    pub mode: String,
+}
+
+#[derive(Default)]
+struct ScriptState {
+    /// Last known value and flags of every point
+    points: HashMap<(DataPointType, u16), (f64, u8)>,
+    /// Flags set by the script; other points are ONLINE
+    flag_overrides: HashMap<(DataPointType, u16), u8>,
+    /// Collected during the current hook call
+    pending: HookOutcome,
+}
+
+impl ScriptState {
+    fn write(&mut self, point_type: DataPointType, index: u16, value: Option<f64>, flags: Option<u8>) -> PointWrite {
+        let (current_value, current_flags) = self.points.get(&(point_type, index)).copied().unwrap_or((0.0, DEFAULT_FLAGS));
+        let write = PointWrite {
+            point_type,
+            index,
+            value: value.unwrap_or(current_value),
+            flags: flags.unwrap_or(current_flags),
+        };
+        if let Some(flags) = flags {
+            self.flag_overrides.insert((point_type, index), flags);
+        }
+        write
+    }
+}
+
+struct Runtime {
+    scope: Scope<'static>,
+    this: Dynamic,
+}
+
+pub struct OutstationScript {
+    pub name: String,
+    engine: Engine,
+    ast: AST,
+    state: Arc<Mutex<ScriptState>>,
+    runtime: Mutex<Runtime>,
+}
+
+impl OutstationScript {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("Failed to read script {}: {}", path.display(), e))?;
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        Self::compile(&name, &source)
+    }
+
+    /// Compile and run the script's top level once (initial log lines, constants)
+    pub fn compile(name: &str, source: &str) -> Result<Self, String> {
+        let state = Arc::new(Mutex::new(ScriptState::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        register_api(&mut engine, &state);
+
+        let ast = engine.compile(source).map_err(|e| format!("Script {}: {}", name, e))?;
+        let mut scope = Scope::new();
+        engine.run_ast_with_scope(&mut scope, &ast).map_err(|e| format!("Script {}: {}", name, e))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            engine,
+            ast,
+            state,
+            runtime: Mutex::new(Runtime { scope, this: Dynamic::from_map(Map::new()) }),
+        })
+    }
+
+    pub fn has_hook(&self, hook: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == hook)
+    }
+
+    /// Refresh the values scripts read from the point table
+    pub fn sync_points(&self, points: &[DataPoint]) {
+        if let Ok(mut state) = self.state.lock() {
+            for point in points {
+                let key = (point.point_type, point.index);
+                let flags = state.flag_overrides.get(&key).copied().unwrap_or(match point.quality {
+                    DataQuality::Offline | DataQuality::CommLost => 0,
+                    _ => DEFAULT_FLAGS,
+                });
+                state.points.insert(key, (point.value, flags));
+            }
+        }
+    }
+
+    /// Record a value set outside the script (a control), so get() sees it
+    pub fn sync_value(&self, point_type: DataPointType, index: u16, value: f64) {
+        if let Ok(mut state) = self.state.lock() {
+            let write = state.write(point_type, index, Some(value), None);
+            state.points.insert((point_type, index), (write.value, write.flags));
+        }
+    }
+
+    /// Record an applied write (scheduled changes land after their hook returned)
+    pub fn record(&self, write: &PointWrite) {
+        if let Ok(mut state) = self.state.lock() {
+            state.points.insert((write.point_type, write.index), (write.value, write.flags));
+        }
+    }
+
+    /// Writes and logs from the top level of the script, run at load time
+    pub fn take_pending(&self) -> HookOutcome {
+        self.state.lock().map(|mut s| std::mem::take(&mut s.pending)).unwrap_or_default()
+    }
+
+    /// Flags for a point: those set by the script, otherwise ONLINE
+    pub fn flags(&self, point_type: DataPointType, index: u16) -> u8 {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|s| s.flag_overrides.get(&(point_type, index)).copied())
+            .unwrap_or(DEFAULT_FLAGS)
+    }
+
+    pub fn on_tick(&self) -> Result<HookOutcome, String> {
+        self.call("on_tick", vec![]).map(|(_, outcome)| outcome)
+    }
+
+    /// on_select / on_operate; the status is None when the script leaves the
+    /// command to the built-in handling
+    pub fn on_command(&self, hook: &str, cmd: &CommandInfo) -> Result<(Option<CommandStatus>, HookOutcome), String> {
+        let mut map = Map::new();
+        map.insert("point_type".into(), format!("{:?}", cmd.point_type).into());
+        map.insert("index".into(), (cmd.index as rhai::INT).into());
+        map.insert("value".into(), (cmd.value as rhai::FLOAT).into());
+        map.insert("op".into(), cmd.op.clone().into());
+        map.insert("mode".into(), cmd.mode.clone().into());
+        let (result, outcome) = self.call(hook, vec![map.into()])?;
+        let status = parse_status(&result).map_err(|e| format!("{}: {}", hook, e))?;
+        Ok((status, outcome))
+    }
+
+    /// Returns whether the time write is accepted
+    pub fn on_time_sync(&self, time_ms: u64) -> Result<(bool, HookOutcome), String> {
+        let (result, outcome) = self.call("on_time_sync", vec![(time_ms as rhai::INT).into()])?;
+        let accepted = result.as_bool().unwrap_or(true);
+        Ok((accepted, outcome))
+    }
+
+    fn call(&self, hook: &str, args: Vec<Dynamic>) -> Result<(Dynamic, HookOutcome), String> {
+        if !self.has_hook(hook) {
+            return Ok((Dynamic::UNIT, HookOutcome::default()));
+        }
+        let mut runtime = self.runtime.lock().map_err(|_| "Script state poisoned".to_string())?;
+        let Runtime { scope, this } = &mut *runtime;
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(this);
+        let result = self.engine.call_fn_with_options::<Dynamic>(options, scope, &self.ast, hook, args);
+        // Changes made before an error are dropped with it
+        let outcome = self.state.lock().map(|mut s| std::mem::take(&mut s.pending)).unwrap_or_default();
+        match result {
+            Ok(value) => Ok((value, outcome)),
+            Err(e) => Err(format!("{} {}: {}", self.name, hook, e)),
+        }
+    }
+}
+
+/// () or "Success" keep the built-in handling; anything else is the status to return
+fn parse_status(value: &Dynamic) -> Result<Option<CommandStatus>, String> {
+    if value.is_unit() {
+        return Ok(None);
+    }
+    if let Ok(code) = value.as_int() {
+        return u8::try_from(code)
+            .map(|c| Some(CommandStatus::from(c)))
+            .map_err(|_| format!("invalid command status {}", code));
+    }
+    if let Some(name) = value.read_lock::<rhai::ImmutableString>() {
+        let status = (0..=u8::MAX)
+            .map(CommandStatus::from)
+            .find(|s| !matches!(s, CommandStatus::Unknown(_)) && format!("{:?}", s).eq_ignore_ascii_case(name.as_str()))
+            .ok_or_else(|| format!("unknown command status '{}'", name.as_str()))?;
+        return Ok(Some(status));
+    }
+    Err(format!("expected a command status name or number, got {}", value.type_name()))
+}
+
+fn point_key(point_type: &str, index: rhai::INT) -> Result<(DataPointType, u16), Box<EvalAltResult>> {
+    let point_type = DataPointType::from_name(point_type).ok_or_else(|| format!("unknown point type '{}'", point_type))?;
+    let index = u16::try_from(index).map_err(|_| format!("invalid point index {}", index))?;
+    Ok((point_type, index))
+}
+
+fn register_api(engine: &mut Engine, state: &Arc<Mutex<ScriptState>>) {
+    let s = state.clone();
+    engine.register_fn("get", move |point_type: &str, index: rhai::INT| -> Result<Dynamic, Box<EvalAltResult>> {
+        let key = point_key(point_type, index)?;
+        let state = s.lock().map_err(|_| "script state poisoned")?;
+        Ok(state.points.get(&key).map(|(v, _)| Dynamic::from_float(*v)).unwrap_or(Dynamic::UNIT))
+    });
+
+    let s = state.clone();
+    engine.register_fn("flags", move |point_type: &str, index: rhai::INT| -> Result<rhai::INT, Box<EvalAltResult>> {
+        let key = point_key(point_type, index)?;
+        let state = s.lock().map_err(|_| "script state poisoned")?;
+        Ok(state.points.get(&key).map(|(_, f)| *f as rhai::INT).unwrap_or(0))
+    });
+
+    let s = state.clone();
+    let set = move |point_type: &str, index: rhai::INT, value: f64| -> Result<(), Box<EvalAltResult>> {
+        let (point_type, index) = point_key(point_type, index)?;
+        let mut state = s.lock().map_err(|_| "script state poisoned")?;
+        let write = state.write(point_type, index, Some(value), None);
+        state.points.insert((point_type, index), (write.value, write.flags));
+        state.pending.writes.push(write);
+        Ok(())
+    };
+    let set_int = set.clone();
+    let set_bool = set.clone();
+    engine.register_fn("set", set);
+    engine.register_fn("set", move |t: &str, i: rhai::INT, v: rhai::INT| set_int(t, i, v as f64));
+    engine.register_fn("set", move |t: &str, i: rhai::INT, v: bool| set_bool(t, i, if v { 1.0 } else { 0.0 }));
+
+    let s = state.clone();
+    engine.register_fn("set_flags", move |point_type: &str, index: rhai::INT, flags: rhai::INT| -> Result<(), Box<EvalAltResult>> {
+        let (point_type, index) = point_key(point_type, index)?;
+        let flags = u8::try_from(flags).map_err(|_| format!("invalid flags {}", flags))?;
+        let mut state = s.lock().map_err(|_| "script state poisoned")?;
+        let write = state.write(point_type, index, None, Some(flags));
+        state.points.insert((point_type, index), (write.value, write.flags));
+        state.pending.writes.push(write);
+        Ok(())
+    });
+
+    // Scheduled writes keep the flags the point has when they are applied
+    let s = state.clone();
+    let schedule = move |delay_ms: rhai::INT, point_type: &str, index: rhai::INT, value: f64| -> Result<(), Box<EvalAltResult>> {
+        let (point_type, index) = point_key(point_type, index)?;
+        let delay = Duration::from_millis(u64::try_from(delay_ms).map_err(|_| format!("invalid delay {}", delay_ms))?);
+        let mut state = s.lock().map_err(|_| "script state poisoned")?;
+        let flags = state.flag_overrides.get(&(point_type, index)).copied().unwrap_or(DEFAULT_FLAGS);
+        state.pending.scheduled.push((delay, PointWrite { point_type, index, value, flags }));
+        Ok(())
+    };
+    let schedule_int = schedule.clone();
+    let schedule_bool = schedule.clone();
+    engine.register_fn("schedule", schedule);
+    engine.register_fn("schedule", move |d: rhai::INT, t: &str, i: rhai::INT, v: rhai::INT| schedule_int(d, t, i, v as f64));
+    engine.register_fn("schedule", move |d: rhai::INT, t: &str, i: rhai::INT, v: bool| {
+        schedule_bool(d, t, i, if v { 1.0 } else { 0.0 })
+    });
+
+    let s = state.clone();
+    engine.register_fn("log", move |text: &str| {
+        if let Ok(mut state) = s.lock() {
+            state.pending.logs.push(text.to_string());
+        }
+    });
+}
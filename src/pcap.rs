@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::dnp3_service::RawFrame;
+
+// PCAPNG export of captured frames (https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html).
+// Network frames are wrapped in synthesized IPv4/IPv6 + TCP/UDP headers on a
+// LINKTYPE_RAW interface so Wireshark's DNP3 dissector picks them up; serial
+// frames go on a DLT_USER0 interface as bare link frames.
+
+/// LINKTYPE_RAW: packet starts with an IPv4 or IPv6 header
+pub const LINKTYPE_RAW: u16 = 101;
+/// DLT_USER0: map to "dnp3" under Wireshark's User DLTs preferences
+pub const LINKTYPE_USER0: u16 = 147;
+
+const BLOCK_SHB: u32 = 0x0A0D_0D0A;
+const BLOCK_IDB: u32 = 0x0000_0001;
+const BLOCK_EPB: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+const OPT_END: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const OPT_SHB_USERAPPL: u16 = 4;
+const OPT_IF_NAME: u16 = 2;
+const OPT_EPB_FLAGS: u16 = 2;
+
+/// Used when a frame carries no endpoint (the DNP3 well-known port on loopback)
+const DEFAULT_LOCAL: &str = "127.0.0.1:50000";
+const DEFAULT_REMOTE: &str = "127.0.0.1:20000";
+
+const INTERFACE_NETWORK: u32 = 0;
+const INTERFACE_SERIAL: u32 = 1;
+
+/// Serialize frames (in capture order) as a PCAPNG file
+pub fn write_pcapng<'a>(frames: impl IntoIterator<Item = &'a RawFrame>) -> Vec<u8> {
+    let mut writer = PcapngWriter::new();
+    let mut out = writer.header();
+    for frame in frames {
+        out.extend(writer.packet(frame));
+    }
+    out
+}
+
+/// Incremental PCAPNG writer for appending frames to an open file
+#[derive(Default)]
+pub struct PcapngWriter {
+    streams: TcpStreams,
+}
+
+impl PcapngWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Section header and interface blocks that start every file
+    pub fn header(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_section_header(&mut out);
+        write_interface(&mut out, LINKTYPE_RAW, "dnp3-tester network");
+        write_interface(&mut out, LINKTYPE_USER0, "dnp3-tester serial");
+        out
+    }
+
+    /// Enhanced packet block for one frame
+    pub fn packet(&mut self, frame: &RawFrame) -> Vec<u8> {
+        let outbound = frame.direction == "TX";
+        let (interface, packet) = match frame.endpoint.as_ref() {
+            Some(ep) if ep.transport == "serial" => (INTERFACE_SERIAL, frame.data.clone()),
+            ep => {
+                let local = ep.and_then(|e| e.local.parse().ok()).unwrap_or_else(|| DEFAULT_LOCAL.parse().unwrap());
+                let remote = ep.and_then(|e| e.remote.parse().ok()).unwrap_or_else(|| DEFAULT_REMOTE.parse().unwrap());
+                let (src, dst) = if outbound { (local, remote) } else { (remote, local) };
+                let packet = if ep.is_some_and(|e| e.transport == "udp") {
+                    build_udp_packet(src, dst, &frame.data)
+                } else {
+                    let connection = ep.map(|e| e.connection_id).unwrap_or(0);
+                    let (seq, ack) = self.streams.advance(connection, outbound, frame.data.len());
+                    build_tcp_packet(src, dst, seq, ack, &frame.data)
+                };
+                (INTERFACE_NETWORK, packet)
+            }
+        };
+        let comment = format!("session={} id={}", frame.session_id, frame.id);
+        let mut out = Vec::new();
+        write_packet(&mut out, interface, frame.timestamp_us, outbound, &packet, &comment);
+        out
+    }
+}
+
+// --- TCP sequence tracking ---
+/// Keeps sequence numbers contiguous per connection and direction, so
+/// Wireshark reassembles APDUs that span several TCP segments
+#[derive(Default)]
+struct TcpStreams {
+    next_seq: HashMap<(u64, bool), u32>,
+}
+
+impl TcpStreams {
+    fn advance(&mut self, connection: u64, outbound: bool, len: usize) -> (u32, u32) {
+        let initial = |outbound: bool| if outbound { 1_000 } else { 1_000_000 };
+        let seq = *self.next_seq.entry((connection, outbound)).or_insert_with(|| initial(outbound));
+        let ack = *self.next_seq.entry((connection, !outbound)).or_insert_with(|| initial(!outbound));
+        self.next_seq.insert((connection, outbound), seq.wrapping_add(len as u32));
+        (seq, ack)
+    }
+}
+
+// --- Block writers ---
+fn write_block(out: &mut Vec<u8>, block_type: u32, body: &[u8]) {
+    let total = (12 + body.len()) as u32;
+    out.extend_from_slice(&block_type.to_le_bytes());
+    out.extend_from_slice(&total.to_le_bytes());
+    out.extend_from_slice(body);
+    out.extend_from_slice(&total.to_le_bytes());
+}
+
+fn push_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend_from_slice(&code.to_le_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    body.extend_from_slice(value);
+    pad32(body);
+}
+
+fn end_options(body: &mut Vec<u8>) {
+    body.extend_from_slice(&OPT_END.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes());
+}
+
+fn pad32(body: &mut Vec<u8>) {
+    while !body.len().is_multiple_of(4) {
+        body.push(0);
+    }
+}
+
+fn write_section_header(out: &mut Vec<u8>) {
+    let mut body = Vec::new();
+    body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+    body.extend_from_slice(&1u16.to_le_bytes()); // Major version
+    body.extend_from_slice(&0u16.to_le_bytes()); // Minor version
+    body.extend_from_slice(&(-1i64).to_le_bytes()); // Section length unknown
+    push_option(&mut body, OPT_SHB_USERAPPL, concat!("dnp3-tester ", env!("CARGO_PKG_VERSION")).as_bytes());
+    end_options(&mut body);
+    write_block(out, BLOCK_SHB, &body);
+}
+
+fn write_interface(out: &mut Vec<u8>, link_type: u16, name: &str) {
+    let mut body = Vec::new();
+    body.extend_from_slice(&link_type.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes()); // Reserved
+    body.extend_from_slice(&0u32.to_le_bytes()); // No snap length limit
+    // if_tsresol omitted: the default resolution is microseconds
+    push_option(&mut body, OPT_IF_NAME, name.as_bytes());
+    end_options(&mut body);
+    write_block(out, BLOCK_IDB, &body);
+}
+
+fn write_packet(out: &mut Vec<u8>, interface: u32, timestamp_us: i64, outbound: bool, packet: &[u8], comment: &str) {
+    let ts = timestamp_us.max(0) as u64;
+    let mut body = Vec::new();
+    body.extend_from_slice(&interface.to_le_bytes());
+    body.extend_from_slice(&((ts >> 32) as u32).to_le_bytes());
+    body.extend_from_slice(&(ts as u32).to_le_bytes());
+    body.extend_from_slice(&(packet.len() as u32).to_le_bytes()); // Captured length
+    body.extend_from_slice(&(packet.len() as u32).to_le_bytes()); // Original length
+    body.extend_from_slice(packet);
+    pad32(&mut body);
+    // epb_flags bits 0-1: 01 = inbound, 10 = outbound
+    let flags: u32 = if outbound { 0b10 } else { 0b01 };
+    push_option(&mut body, OPT_EPB_FLAGS, &flags.to_le_bytes());
+    push_option(&mut body, OPT_COMMENT, comment.as_bytes());
+    end_options(&mut body);
+    write_block(out, BLOCK_EPB, &body);
+}
+
+// --- Synthesized network headers ---
+const PROTO_TCP: u8 = 6;
+const PROTO_UDP: u8 = 17;
+const TCP_FLAGS_PSH_ACK: u8 = 0x18;
+
+fn build_tcp_packet(src: SocketAddr, dst: SocketAddr, seq: u32, ack: u32, payload: &[u8]) -> Vec<u8> {
+    let mut segment = Vec::with_capacity(20 + payload.len());
+    segment.extend_from_slice(&src.port().to_be_bytes());
+    segment.extend_from_slice(&dst.port().to_be_bytes());
+    segment.extend_from_slice(&seq.to_be_bytes());
+    segment.extend_from_slice(&ack.to_be_bytes());
+    segment.push(5 << 4); // Data offset: 5 words, no options
+    segment.push(TCP_FLAGS_PSH_ACK);
+    segment.extend_from_slice(&65535u16.to_be_bytes()); // Window
+    segment.extend_from_slice(&[0, 0]); // Checksum, filled below
+    segment.extend_from_slice(&[0, 0]); // Urgent pointer
+    segment.extend_from_slice(payload);
+    let checksum = transport_checksum(src.ip(), dst.ip(), PROTO_TCP, &segment);
+    segment[16..18].copy_from_slice(&checksum.to_be_bytes());
+    wrap_ip(src.ip(), dst.ip(), PROTO_TCP, &segment)
+}
+
+fn build_udp_packet(src: SocketAddr, dst: SocketAddr, payload: &[u8]) -> Vec<u8> {
+    let mut datagram = Vec::with_capacity(8 + payload.len());
+    datagram.extend_from_slice(&src.port().to_be_bytes());
+    datagram.extend_from_slice(&dst.port().to_be_bytes());
+    datagram.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+    datagram.extend_from_slice(&[0, 0]); // Checksum, filled below
+    datagram.extend_from_slice(payload);
+    let checksum = match transport_checksum(src.ip(), dst.ip(), PROTO_UDP, &datagram) {
+        0 => 0xFFFF, // Zero means "no checksum" for UDP
+        c => c,
+    };
+    datagram[6..8].copy_from_slice(&checksum.to_be_bytes());
+    wrap_ip(src.ip(), dst.ip(), PROTO_UDP, &datagram)
+}
+
+fn wrap_ip(src: IpAddr, dst: IpAddr, protocol: u8, payload: &[u8]) -> Vec<u8> {
+    match (src, dst) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => {
+            let mut header = Vec::with_capacity(20 + payload.len());
+            header.push(0x45); // Version 4, IHL 5
+            header.push(0);
+            header.extend_from_slice(&((20 + payload.len()) as u16).to_be_bytes());
+            header.extend_from_slice(&[0, 0]); // Identification
+            header.extend_from_slice(&[0x40, 0]); // Don't fragment
+            header.push(64); // TTL
+            header.push(protocol);
+            header.extend_from_slice(&[0, 0]); // Header checksum, filled below
+            header.extend_from_slice(&src.octets());
+            header.extend_from_slice(&dst.octets());
+            let checksum = !ones_complement_sum(&header, 0);
+            header[10..12].copy_from_slice(&checksum.to_be_bytes());
+            header.extend_from_slice(payload);
+            header
+        }
+        (src, dst) => {
+            let src = to_v6(src);
+            let dst = to_v6(dst);
+            let mut header = Vec::with_capacity(40 + payload.len());
+            header.extend_from_slice(&[0x60, 0, 0, 0]); // Version 6, no traffic class/flow label
+            header.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+            header.push(protocol);
+            header.push(64); // Hop limit
+            header.extend_from_slice(&src.octets());
+            header.extend_from_slice(&dst.octets());
+            header.extend_from_slice(payload);
+            header
+        }
+    }
+}
+
+fn to_v6(addr: IpAddr) -> std::net::Ipv6Addr {
+    match addr {
+        IpAddr::V4(v4) => v4.to_ipv6_mapped(),
+        IpAddr::V6(v6) => v6,
+    }
+}
+
+/// TCP/UDP checksum over the IP pseudo-header and the segment
+fn transport_checksum(src: IpAddr, dst: IpAddr, protocol: u8, segment: &[u8]) -> u16 {
+    let mut pseudo = Vec::with_capacity(40);
+    match (src, dst) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => {
+            pseudo.extend_from_slice(&src.octets());
+            pseudo.extend_from_slice(&dst.octets());
+            pseudo.push(0);
+            pseudo.push(protocol);
+            pseudo.extend_from_slice(&(segment.len() as u16).to_be_bytes());
+        }
+        (src, dst) => {
+            pseudo.extend_from_slice(&to_v6(src).octets());
+            pseudo.extend_from_slice(&to_v6(dst).octets());
+            pseudo.extend_from_slice(&(segment.len() as u32).to_be_bytes());
+            pseudo.extend_from_slice(&[0, 0, 0, protocol]);
+        }
+    }
+    !ones_complement_sum(segment, ones_complement_sum(&pseudo, 0))
+}
+
+/// Internet checksum accumulation (RFC 1071), folded to 16 bits
+fn ones_complement_sum(data: &[u8], initial: u16) -> u16 {
+    let mut sum = initial as u32;
+    for chunk in data.chunks(2) {
+        let word = match chunk {
+            [hi, lo] => u16::from_be_bytes([*hi, *lo]),
+            [hi] => u16::from_be_bytes([*hi, 0]),
+            _ => 0,
+        };
+        sum += word as u32;
+    }
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum as u16
+}
+
+
+// ============================================================================
+// READING (classic pcap and pcapng)
+// ============================================================================
+
+/// One packet from a capture file, before any protocol dissection
+#[derive(Debug, Clone)]
+pub struct CapturedPacket {
+    pub timestamp_us: i64,
+    pub link_type: u16,
+    pub data: Vec<u8>,
+}
+
+/// Parse a classic pcap or pcapng file, detected from its magic number
+pub fn read_capture(bytes: &[u8]) -> Result<Vec<CapturedPacket>, String> {
+    if bytes.len() < 4 {
+        return Err("File too short to be a capture".to_string());
+    }
+    match u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) {
+        BLOCK_SHB => read_pcapng(bytes),
+        0xA1B2_C3D4 | 0xD4C3_B2A1 | 0xA1B2_3C4D | 0x4D3C_B2A1 => read_pcap(bytes),
+        magic => Err(format!("Unrecognized capture format (magic {:08X})", magic)),
+    }
+}
+
+/// Little helper for reading integers in the file's byte order
+#[derive(Clone, Copy)]
+struct Endian {
+    big: bool,
+}
+
+impl Endian {
+    fn u16(self, b: &[u8], at: usize) -> Option<u16> {
+        let raw = [*b.get(at)?, *b.get(at + 1)?];
+        Some(if self.big { u16::from_be_bytes(raw) } else { u16::from_le_bytes(raw) })
+    }
+
+    fn u32(self, b: &[u8], at: usize) -> Option<u32> {
+        let raw = [*b.get(at)?, *b.get(at + 1)?, *b.get(at + 2)?, *b.get(at + 3)?];
+        Some(if self.big { u32::from_be_bytes(raw) } else { u32::from_le_bytes(raw) })
+    }
+}
+
+fn read_pcap(bytes: &[u8]) -> Result<Vec<CapturedPacket>, String> {
+    if bytes.len() < 24 {
+        return Err("Truncated pcap header".to_string());
+    }
+    let magic = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let e = Endian { big: matches!(magic, 0xD4C3_B2A1 | 0x4D3C_B2A1) };
+    let nanos = matches!(magic, 0xA1B2_3C4D | 0x4D3C_B2A1);
+    let link_type = e.u32(bytes, 20).unwrap_or(0) as u16;
+
+    let mut packets = Vec::new();
+    let mut pos = 24;
+    while pos + 16 <= bytes.len() {
+        let secs = e.u32(bytes, pos).unwrap_or(0) as i64;
+        let frac = e.u32(bytes, pos + 4).unwrap_or(0) as i64;
+        let captured = e.u32(bytes, pos + 8).unwrap_or(0) as usize;
+        let start = pos + 16;
+        let data = bytes.get(start..start + captured).ok_or("Truncated pcap record")?;
+        packets.push(CapturedPacket {
+            timestamp_us: secs * 1_000_000 + if nanos { frac / 1_000 } else { frac },
+            link_type,
+            data: data.to_vec(),
+        });
+        pos = start + captured;
+    }
+    Ok(packets)
+}
+
+struct PcapngInterface {
+    link_type: u16,
+    /// Timestamp units per second
+    units_per_sec: u64,
+}
+
+fn read_pcapng(bytes: &[u8]) -> Result<Vec<CapturedPacket>, String> {
+    let mut e = Endian { big: false };
+    let mut interfaces: Vec<PcapngInterface> = Vec::new();
+    let mut packets = Vec::new();
+    let mut pos = 0;
+
+    while pos + 12 <= bytes.len() {
+        if u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) == BLOCK_SHB {
+            // Each section declares its own byte order
+            let bom = bytes.get(pos + 8..pos + 12).ok_or("Truncated section header")?;
+            e = Endian { big: bom == BYTE_ORDER_MAGIC.to_be_bytes() };
+            interfaces.clear();
+        }
+        let block_type = e.u32(bytes, pos).ok_or("Truncated block")?;
+        let total = e.u32(bytes, pos + 4).ok_or("Truncated block")? as usize;
+        if total < 12 {
+            return Err(format!("Invalid block length {} at offset {}", total, pos));
+        }
+        if pos + total > bytes.len() {
+            break; // Cut short, e.g. a recording still being written: keep what was read
+        }
+        let body = &bytes[pos + 8..pos + total - 4];
+
+        match block_type {
+            BLOCK_IDB => {
+                let link_type = e.u16(body, 0).ok_or("Truncated interface block")?;
+                let mut units_per_sec = 1_000_000;
+                for (code, value) in options(e, body.get(8..).unwrap_or_default()) {
+                    // if_tsresol: high bit selects a power of 2, otherwise a power of 10
+                    if code == 9 && value.len() == 1 {
+                        let exp = (value[0] & 0x7F) as u32;
+                        units_per_sec = if value[0] & 0x80 != 0 { 2u64.saturating_pow(exp) } else { 10u64.saturating_pow(exp) };
+                    }
+                }
+                interfaces.push(PcapngInterface { link_type, units_per_sec });
+            }
+            BLOCK_EPB => {
+                let interface = e.u32(body, 0).ok_or("Truncated packet block")? as usize;
+                let high = e.u32(body, 4).ok_or("Truncated packet block")? as u64;
+                let low = e.u32(body, 8).ok_or("Truncated packet block")? as u64;
+                let captured = e.u32(body, 12).ok_or("Truncated packet block")? as usize;
+                let data = body.get(20..20 + captured).ok_or("Truncated packet data")?;
+                let iface = interfaces.get(interface).ok_or(format!("Packet references unknown interface {}", interface))?;
+                let ticks = (high << 32) | low;
+                let timestamp_us = (ticks as u128 * 1_000_000 / iface.units_per_sec.max(1) as u128) as i64;
+                packets.push(CapturedPacket { timestamp_us, link_type: iface.link_type, data: data.to_vec() });
+            }
+            0x0000_0003 => {
+                // Simple packet block: no timestamp, always interface 0
+                let original = e.u32(body, 0).ok_or("Truncated packet block")? as usize;
+                let iface = interfaces.first().ok_or("Packet before any interface")?;
+                let data = &body[4..(4 + original).min(body.len())];
+                packets.push(CapturedPacket { timestamp_us: 0, link_type: iface.link_type, data: data.to_vec() });
+            }
+            _ => {} // Statistics, name resolution, custom blocks...
+        }
+        pos += total;
+    }
+    Ok(packets)
+}
+
+/// Iterate the (code, value) options at the end of a pcapng block body
+fn options(e: Endian, mut data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut out = Vec::new();
+    while let (Some(code), Some(len)) = (e.u16(data, 0), e.u16(data, 2)) {
+        if code == OPT_END {
+            break;
+        }
+        let len = len as usize;
+        let Some(value) = data.get(4..4 + len) else { break };
+        out.push((code, value));
+        data = data.get((4 + len).div_ceil(4) * 4..).unwrap_or_default();
+    }
+    out
+}
@@ -0,0 +1,92 @@
+use std::collections::{HashMap, VecDeque};
+use chrono::{DateTime, Utc};
+
+use crate::models::DataPointType;
+
+/// Maximum number of samples kept per point before the oldest are dropped
+const MAX_SAMPLES_PER_POINT: usize = 1000;
+
+// --- Point History Sample ---
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PointSample {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    pub flags: u8, // Raw DNP3 flags byte (bit 0 = ONLINE)
+    /// Time stamped by the outstation, when the object carried one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_time: Option<DateTime<Utc>>,
+}
+
+/// Bounded per-point change history, keyed by point type and index.
+/// Only value/flags transitions are stored so chattering points stand out
+/// and steady points don't flush the buffer.
+pub struct PointHistory {
+    points: HashMap<(DataPointType, u16), VecDeque<PointSample>>,
+}
+
+impl PointHistory {
+    pub fn new() -> Self {
+        Self {
+            points: HashMap::new(),
+        }
+    }
+
+    /// Record a new value for a point. Returns true if it was stored as a change.
+    /// `timestamp` is the local (receive) time the history is ordered by.
+    pub fn record(
+        &mut self,
+        point_type: DataPointType,
+        index: u16,
+        value: f64,
+        flags: u8,
+        timestamp: DateTime<Utc>,
+        device_time: Option<DateTime<Utc>>,
+    ) -> bool {
+        let samples = self.points.entry((point_type, index)).or_default();
+
+        if let Some(last) = samples.back() {
+            if last.value == value && last.flags == flags {
+                return false;
+            }
+        }
+
+        if samples.len() >= MAX_SAMPLES_PER_POINT {
+            samples.pop_front();
+        }
+        samples.push_back(PointSample { timestamp, value, flags, device_time });
+        true
+    }
+
+    /// Samples for one point within an optional time window, oldest first.
+    /// When `limit` is set only the most recent `limit` samples are returned.
+    pub fn query(
+        &self,
+        point_type: DataPointType,
+        index: u16,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Vec<PointSample> {
+        let Some(samples) = self.points.get(&(point_type, index)) else {
+            return Vec::new();
+        };
+
+        let mut result: Vec<PointSample> = samples
+            .iter()
+            .filter(|s| from.is_none_or(|f| s.timestamp >= f))
+            .filter(|s| to.is_none_or(|t| s.timestamp <= t))
+            .cloned()
+            .collect();
+
+        if let Some(limit) = limit {
+            if result.len() > limit {
+                result.drain(..result.len() - limit);
+            }
+        }
+        result
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+}
@@ -0,0 +1,435 @@
+use std::collections::HashSet;
+use std::io::Cursor;
+
+use calamine::{Data, Reader};
+use serde::Serialize;
+
+use crate::models::{DeviceConfiguration, PointConfig};
+
+// Point lists from spreadsheets (port of tools/excel_to_config.py). The
+// first row holds the column names: type (or point_type), index, name and
+// optionally description, unit and scale, in any order and case. CSV and
+// the first sheet of an XLSX/XLS workbook are read the same way; rows that
+// can't be imported are reported with their spreadsheet row number.
+
+/// Point type names and aliases, lower case
+const TYPE_ALIASES: [(&str, PointList); 14] = [
+    ("binaryinput", PointList::BinaryInputs),
+    ("binary input", PointList::BinaryInputs),
+    ("bi", PointList::BinaryInputs),
+    ("binaryoutput", PointList::BinaryOutputs),
+    ("binary output", PointList::BinaryOutputs),
+    ("bo", PointList::BinaryOutputs),
+    ("analoginput", PointList::AnalogInputs),
+    ("analog input", PointList::AnalogInputs),
+    ("ai", PointList::AnalogInputs),
+    ("analogoutput", PointList::AnalogOutputs),
+    ("analog output", PointList::AnalogOutputs),
+    ("ao", PointList::AnalogOutputs),
+    ("counter", PointList::Counters),
+    ("c", PointList::Counters),
+];
+
+const TYPE_COLUMNS: [&str; 3] = ["type", "point_type", "point type"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PointList {
+    BinaryInputs,
+    BinaryOutputs,
+    AnalogInputs,
+    AnalogOutputs,
+    Counters,
+}
+
+impl PointList {
+    fn key(self) -> &'static str {
+        match self {
+            PointList::BinaryInputs => "binary_inputs",
+            PointList::BinaryOutputs => "binary_outputs",
+            PointList::AnalogInputs => "analog_inputs",
+            PointList::AnalogOutputs => "analog_outputs",
+            PointList::Counters => "counters",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Workbook,
+}
+
+impl Format {
+    /// From `?format=`, else the file name, else the content (XLSX is a zip,
+    /// XLS an OLE2 compound file)
+    pub fn detect(format: Option<&str>, file_name: Option<&str>, data: &[u8]) -> Result<Format, String> {
+        let extension = file_name.and_then(|name| name.rsplit_once('.')).map(|(_, ext)| ext);
+        match format.or(extension).map(|f| f.to_ascii_lowercase()).as_deref() {
+            Some("csv" | "txt") => Ok(Format::Csv),
+            Some("xlsx" | "xlsm" | "xls" | "xlsb" | "ods") => Ok(Format::Workbook),
+            Some(other) if format.is_some() => Err(format!("Unknown format '{}' (csv or xlsx)", other)),
+            _ if data.starts_with(b"PK\x03\x04") || data.starts_with(&[0xD0, 0xCF, 0x11, 0xE0]) => Ok(Format::Workbook),
+            _ => Ok(Format::Csv),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RowError {
+    /// Spreadsheet row number (the header is row 1)
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PointImport {
+    pub config: DeviceConfiguration,
+    /// Non-empty data rows
+    pub rows: usize,
+    pub imported: usize,
+    pub errors: Vec<RowError>,
+}
+
+impl PointImport {
+    pub fn counts(&self) -> serde_json::Value {
+        let len = |list: &Option<Vec<PointConfig>>| list.as_ref().map_or(0, |l| l.len());
+        serde_json::json!({
+            "binary_inputs": len(&self.config.binary_inputs),
+            "binary_outputs": len(&self.config.binary_outputs),
+            "analog_inputs": len(&self.config.analog_inputs),
+            "analog_outputs": len(&self.config.analog_outputs),
+            "counters": len(&self.config.counters),
+        })
+    }
+}
+
+/// Parse a point list. Fails if the file can't be read or no row is valid;
+/// `PointImport::errors` lists the rows that were skipped.
+pub fn import(data: &[u8], format: Format, name: Option<String>) -> Result<PointImport, (String, Vec<RowError>)> {
+    let (first_row, rows) = match format {
+        Format::Csv => read_csv(data),
+        Format::Workbook => read_workbook(data),
+    }
+    .map_err(|e| (e, Vec::new()))?;
+    convert(first_row, rows, name)
+}
+
+/// Cells as text, and the spreadsheet row number of the first row
+type Grid = (usize, Vec<Vec<String>>);
+
+fn read_csv(data: &[u8]) -> Result<Grid, String> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    // Excel writes ';' or tabs instead of ',' in some locales
+    let header = data
+        .split(|&b| b == b'\n')
+        .find(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .unwrap_or_default();
+    let delimiter = [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|d| header.iter().filter(|b| *b == d).count())
+        .unwrap_or(b',');
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(data);
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let (mut scanned, mut line) = (0, 1); // Newlines counted up to byte `scanned`
+    for record in reader.records() {
+        let record = record.map_err(|e| match e.kind() {
+            csv::ErrorKind::Utf8 { .. } => "CSV is not UTF-8; save it as \"CSV UTF-8\"".to_string(),
+            _ => format!("Invalid CSV: {}", e),
+        })?;
+        // The reader skips blank lines (and reports a record as starting on the
+        // first of them); keep them as empty rows so row numbers match the file
+        if let Some(p) = record.position() {
+            let start = p.byte() as usize;
+            let start = start + data[start..].iter().take_while(|b| matches!(b, b'\r' | b'\n')).count();
+            line += data[scanned..start].iter().filter(|b| **b == b'\n').count();
+            scanned = start;
+        }
+        while rows.len() + 1 < line {
+            rows.push(Vec::new());
+        }
+        rows.push(record.iter().map(|cell| cell.trim().to_string()).collect());
+    }
+    Ok((1, rows))
+}
+
+fn read_workbook(data: &[u8]) -> Result<Grid, String> {
+    let mut workbook =
+        calamine::open_workbook_auto_from_rs(Cursor::new(data)).map_err(|e| format!("Invalid workbook: {}", e))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or("The workbook has no sheets")?
+        .map_err(|e| format!("Cannot read the first sheet: {}", e))?;
+    let first_row = range.start().map_or(0, |(row, _)| row as usize) + 1;
+    let rows = range.rows().map(|row| row.iter().map(cell_text).collect()).collect();
+    Ok((first_row, rows))
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        // Excel stores every number as a float; 3.0 is index 3
+        Data::Float(f) if f.fract() == 0.0 && f.abs() < 1e15 => format!("{}", *f as i64),
+        Data::String(s) => s.trim().to_string(),
+        Data::Empty => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn convert(first_row: usize, rows: Vec<Vec<String>>, name: Option<String>) -> Result<PointImport, (String, Vec<RowError>)> {
+    let mut rows = rows.into_iter().enumerate().map(|(n, cells)| (first_row + n, cells));
+    // The header is the first non-empty row
+    let Some((_, header)) = rows.find(|(_, cells)| cells.iter().any(|c| !c.is_empty())) else {
+        return Err(("The file is empty".to_string(), Vec::new()));
+    };
+    let header: Vec<String> = header.iter().map(|c| c.trim().to_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|c| c == name);
+    let type_column = TYPE_COLUMNS.iter().find_map(|name| column(name));
+    let Some(index_column) = column("index") else {
+        return Err(("Missing column 'index'".to_string(), Vec::new()));
+    };
+    let name_column = column("name");
+    let description_column = column("description");
+    let unit_column = column("unit");
+    let scale_column = column("scale");
+
+    let mut import = PointImport {
+        config: DeviceConfiguration {
+            name,
+            binary_inputs: Some(Vec::new()),
+            binary_outputs: Some(Vec::new()),
+            analog_inputs: Some(Vec::new()),
+            analog_outputs: Some(Vec::new()),
+            counters: Some(Vec::new()),
+        },
+        rows: 0,
+        imported: 0,
+        errors: Vec::new(),
+    };
+    let mut seen: HashSet<(PointList, u16)> = HashSet::new();
+
+    for (row, cells) in rows {
+        let cell = |column: Option<usize>| column.and_then(|c| cells.get(c)).map(String::as_str).unwrap_or("");
+        if cells.iter().all(|c| c.is_empty()) {
+            continue;
+        }
+        import.rows += 1;
+        let error = |message: String| RowError { row, message };
+
+        // Like the Python tool, fall back to the first column for the type
+        let Some(list) = point_list(cell(type_column)).or_else(|| point_list(cell(Some(0)))) else {
+            let value = cell(type_column.or(Some(0)));
+            import.errors.push(error(if value.is_empty() {
+                "Missing point type".to_string()
+            } else {
+                format!("Unknown point type '{}'", value)
+            }));
+            continue;
+        };
+        let index = match parse_index(cell(Some(index_column))) {
+            Ok(index) => index,
+            Err(e) => {
+                import.errors.push(error(e));
+                continue;
+            }
+        };
+        let scale = match cell(scale_column) {
+            "" => None,
+            text => match text.parse::<f64>() {
+                Ok(scale) if scale.is_finite() => Some(scale),
+                _ => {
+                    import.errors.push(error(format!("Invalid scale '{}'", text)));
+                    continue;
+                }
+            },
+        };
+        if !seen.insert((list, index)) {
+            import.errors.push(error(format!("Duplicate {} index {}", list.key(), index)));
+            continue;
+        }
+
+        let optional = |column: Option<usize>| Some(cell(column)).filter(|c| !c.is_empty()).map(str::to_string);
+        let point = PointConfig {
+            index,
+            name: cell(name_column).to_string(),
+            description: optional(description_column),
+            unit: optional(unit_column),
+            scale,
+        };
+        let points = match list {
+            PointList::BinaryInputs => &mut import.config.binary_inputs,
+            PointList::BinaryOutputs => &mut import.config.binary_outputs,
+            PointList::AnalogInputs => &mut import.config.analog_inputs,
+            PointList::AnalogOutputs => &mut import.config.analog_outputs,
+            PointList::Counters => &mut import.config.counters,
+        };
+        points.get_or_insert_with(Vec::new).push(point);
+        import.imported += 1;
+    }
+
+    if import.imported == 0 {
+        return Err((
+            "No valid rows found. Ensure a `type` (or `point_type`) column exists and contains point types".to_string(),
+            import.errors,
+        ));
+    }
+    Ok(import)
+}
+
+fn point_list(text: &str) -> Option<PointList> {
+    let text = text.trim().to_lowercase();
+    TYPE_ALIASES.iter().find(|(alias, _)| *alias == text).map(|(_, list)| *list)
+}
+
+fn parse_index(text: &str) -> Result<u16, String> {
+    if text.is_empty() {
+        return Err("Missing index".to_string());
+    }
+    if let Ok(index) = text.parse::<u16>() {
+        return Ok(index);
+    }
+    match text.parse::<f64>() {
+        Ok(f) if f.fract() == 0.0 && (0.0..=u16::MAX as f64).contains(&f) => Ok(f as u16),
+        Ok(_) => Err(format!("Index {} is not an integer in 0-65535", text)),
+        Err(_) => Err(format!("Invalid index '{}'", text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv(text: &str) -> Result<PointImport, (String, Vec<RowError>)> {
+        import(text.as_bytes(), Format::Csv, Some("test".to_string()))
+    }
+
+    fn indexes(points: &Option<Vec<PointConfig>>) -> Vec<u16> {
+        points.iter().flatten().map(|p| p.index).collect()
+    }
+
+    #[test]
+    fn accepts_type_aliases_in_any_case() {
+        let import = csv("Type,Index,Name\nBI,0,Breaker\nbinary input,1,Isolator\nAO,2,Setpoint\nc,3,Energy\nAnalogInput,4,Voltage\n")
+            .unwrap();
+        assert_eq!(indexes(&import.config.binary_inputs), vec![0, 1]);
+        assert_eq!(indexes(&import.config.analog_outputs), vec![2]);
+        assert_eq!(indexes(&import.config.counters), vec![3]);
+        assert_eq!(indexes(&import.config.analog_inputs), vec![4]);
+        assert_eq!((import.rows, import.imported), (5, 5));
+        assert!(import.errors.is_empty());
+    }
+
+    #[test]
+    fn reads_optional_columns() {
+        let import = csv("index,point_type,name,unit,scale,description\n7,AI,Voltage,kV,0.1,Bus A\n").unwrap();
+        let point = &import.config.analog_inputs.unwrap()[0];
+        assert_eq!(point.index, 7);
+        assert_eq!(point.unit.as_deref(), Some("kV"));
+        assert_eq!(point.scale, Some(0.1));
+        assert_eq!(point.description.as_deref(), Some("Bus A"));
+    }
+
+    #[test]
+    fn reports_invalid_rows_with_row_numbers() {
+        let import = csv(
+            "type,index,name,scale\n\
+             BI,0,ok\n\
+             BI,0,duplicate\n\
+             \n\
+             XX,1,unknown type\n\
+             AI,,no index\n\
+             AI,2.5,fraction\n\
+             AI,70000,too large\n\
+             AI,3,bad scale,abc\n\
+             BO,0,same index other type\n",
+        )
+        .unwrap();
+        let errors: Vec<(usize, &str)> = import.errors.iter().map(|e| (e.row, e.message.as_str())).collect();
+        assert_eq!(
+            errors,
+            vec![
+                (3, "Duplicate binary_inputs index 0"),
+                (5, "Unknown point type 'XX'"),
+                (6, "Missing index"),
+                (7, "Index 2.5 is not an integer in 0-65535"),
+                (8, "Index 70000 is not an integer in 0-65535"),
+                (9, "Invalid scale 'abc'"),
+            ]
+        );
+        // The empty line 4 is not a data row
+        assert_eq!((import.rows, import.imported), (8, 2));
+    }
+
+    #[test]
+    fn counts_blank_lines_in_row_numbers() {
+        let import = csv("type,index\r\n\r\n\r\nXX,1\r\nBI,1\r\n").unwrap();
+        assert_eq!(import.errors[0].row, 4);
+        assert_eq!(import.rows, 2);
+    }
+
+    #[test]
+    fn skips_blank_lines_before_header() {
+        let import = csv("\n\ntype,index\nBI,1\nXX,2\n").unwrap();
+        assert_eq!(import.imported, 1);
+        assert_eq!(import.errors[0].row, 5);
+    }
+
+    #[test]
+    fn accepts_excel_float_indexes() {
+        assert_eq!(parse_index("3"), Ok(3));
+        assert_eq!(parse_index("3.0"), Ok(3));
+        assert_eq!(parse_index("65535"), Ok(65535));
+        assert!(parse_index("-1").is_err());
+        assert_eq!(parse_index("x"), Err("Invalid index 'x'".to_string()));
+
+        let import = csv("type,index,name\nAI,3.0,Voltage\n").unwrap();
+        assert_eq!(indexes(&import.config.analog_inputs), vec![3]);
+        assert_eq!(cell_text(&Data::Float(3.0)), "3");
+        assert_eq!(cell_text(&Data::Float(0.5)), "0.5");
+    }
+
+    #[test]
+    fn detects_csv_delimiter() {
+        for text in ["type;index;name\nBI;1;Breaker;\n", "type\tindex\tname\nBI\t1\tBreaker\n", "\u{FEFF}type,index,name\nBI,1,\"Breaker, A\"\n"] {
+            let import = csv(text).unwrap();
+            assert_eq!(indexes(&import.config.binary_inputs), vec![1], "{:?}", text);
+        }
+        let import = csv("type;index;name\nBI;1;Breaker, A\n").unwrap();
+        assert_eq!(import.config.binary_inputs.unwrap()[0].name, "Breaker, A");
+    }
+
+    #[test]
+    fn fails_without_index_column_or_valid_rows() {
+        let (message, errors) = csv("type,name\nBI,Breaker\n").unwrap_err();
+        assert_eq!(message, "Missing column 'index'");
+        assert!(errors.is_empty());
+
+        let (message, errors) = csv("name,index\nBreaker,0\n").unwrap_err();
+        assert!(message.starts_with("No valid rows found"));
+        assert_eq!(errors[0].row, 2);
+
+        assert_eq!(csv("").unwrap_err().0, "The file is empty");
+    }
+
+    #[test]
+    fn falls_back_to_first_column_for_type() {
+        let import = csv("kind,index,name\nAO,1,Setpoint\n").unwrap();
+        assert_eq!(indexes(&import.config.analog_outputs), vec![1]);
+    }
+
+    #[test]
+    fn detects_format() {
+        let zip = b"PK\x03\x04rest";
+        assert_eq!(Format::detect(Some("XLSX"), None, b"a,b"), Ok(Format::Workbook));
+        assert_eq!(Format::detect(Some("csv"), Some("points.xlsx"), zip), Ok(Format::Csv));
+        assert_eq!(Format::detect(None, Some("Points.XLS"), b""), Ok(Format::Workbook));
+        assert_eq!(Format::detect(None, Some("points.csv"), zip), Ok(Format::Csv));
+        assert_eq!(Format::detect(None, None, zip), Ok(Format::Workbook));
+        assert_eq!(Format::detect(None, Some("upload"), &[0xD0, 0xCF, 0x11, 0xE0, 0]), Ok(Format::Workbook));
+        assert_eq!(Format::detect(None, None, b"type,index"), Ok(Format::Csv));
+        assert!(Format::detect(Some("pdf"), None, b"").is_err());
+    }
+}
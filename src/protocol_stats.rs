@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use dnp3::master::{CommandError, TaskError};
+use serde::Serialize;
+
+use crate::dnp3_link;
+use crate::dnp3_transport::Reassembler;
+use crate::latency::{LatencyHistogram, LatencySummary};
+
+// Per-session protocol counters. Link, transport and application counts are
+// taken from the bytes the capture shim forwards, so they reflect exactly
+// what went over the wire; task outcomes (timeouts, control results) are
+// recorded by the service when a master request completes. Round-trip
+// latency is measured here too: a request this session sends is matched to
+// the response carrying the same application sequence number.
+
+const FC_CONFIRM: u8 = 0x00;
+const FC_RESPONSE: u8 = 0x81;
+const FC_UNSOLICITED_RESPONSE: u8 = 0x82;
+const APP_FIR: u8 = 0x80;
+const APP_SEQ_MASK: u8 = 0x0F;
+const GROUP_TIME: u8 = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtocolStats {
+    /// Start of the counting period (session creation or last reset)
+    pub since: chrono::DateTime<chrono::Utc>,
+    pub link_frames_tx: u64,
+    pub link_frames_rx: u64,
+    pub bytes_tx: u64,
+    pub bytes_rx: u64,
+    pub crc_errors: u64,
+    /// Chunks of the byte stream that were not a link frame (noise, truncation)
+    pub framing_errors: u64,
+    /// Transport segments dropped (bad sequence, missing FIR, oversize)
+    pub transport_errors: u64,
+    /// Complete application fragments
+    pub fragments_tx: u64,
+    pub fragments_rx: u64,
+    pub unsolicited_fragments: u64,
+    pub confirms_tx: u64,
+    pub confirms_rx: u64,
+    /// Fragments identical to the previous one in the same direction
+    pub retries: u64,
+    pub timeouts: u64,
+    /// Master requests that failed for reasons other than a timeout
+    pub task_failures: u64,
+    pub controls_ok: u64,
+    pub controls_failed: u64,
+    /// Round-trip latency per request type (read, direct_operate, time_sync, ...)
+    pub latency: BTreeMap<String, LatencySummary>,
+}
+
+impl ProtocolStats {
+    fn new() -> Self {
+        Self {
+            since: chrono::Utc::now(),
+            link_frames_tx: 0,
+            link_frames_rx: 0,
+            bytes_tx: 0,
+            bytes_rx: 0,
+            crc_errors: 0,
+            framing_errors: 0,
+            transport_errors: 0,
+            fragments_tx: 0,
+            fragments_rx: 0,
+            unsolicited_fragments: 0,
+            confirms_tx: 0,
+            confirms_rx: 0,
+            retries: 0,
+            timeouts: 0,
+            task_failures: 0,
+            controls_ok: 0,
+            controls_failed: 0,
+            latency: BTreeMap::new(),
+        }
+    }
+
+    /// Everything that went wrong, for the summary in the UI
+    pub fn error_total(&self) -> u64 {
+        self.crc_errors
+            + self.framing_errors
+            + self.transport_errors
+            + self.timeouts
+            + self.task_failures
+            + self.controls_failed
+    }
+}
+
+struct CounterState {
+    stats: ProtocolStats,
+    reassembler: Reassembler,
+    /// Last fragment per direction, to spot retransmissions
+    last_fragment: HashMap<bool, Vec<u8>>,
+    latency: BTreeMap<&'static str, LatencyHistogram>,
+    /// Requests awaiting a response, by application sequence number
+    pending: HashMap<u8, (&'static str, chrono::DateTime<chrono::Utc>)>,
+}
+
+// --- Stats Counter ---
+#[derive(Clone)]
+pub struct StatsCounter {
+    state: Arc<Mutex<CounterState>>,
+}
+
+impl StatsCounter {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(CounterState {
+                stats: ProtocolStats::new(),
+                reassembler: Reassembler::new(),
+                last_fragment: HashMap::new(),
+                latency: BTreeMap::new(),
+                pending: HashMap::new(),
+            })),
+        }
+    }
+
+    pub fn snapshot(&self) -> ProtocolStats {
+        self.with(|s| {
+            let mut stats = s.stats.clone();
+            stats.latency = s.latency.iter().map(|(kind, h)| (kind.to_string(), h.summary())).collect();
+            stats
+        })
+    }
+
+    pub fn reset(&self) {
+        self.with(|s| {
+            s.stats = ProtocolStats::new();
+            s.latency.clear();
+        });
+    }
+
+    /// Count one chunk from the capture shim ("TX" = sent by this session)
+    pub fn record_frame(&self, direction: &str, data: &[u8], timestamp: chrono::DateTime<chrono::Utc>) {
+        let tx = direction == "TX";
+        self.with(|s| {
+            if tx {
+                s.stats.bytes_tx += data.len() as u64;
+            } else {
+                s.stats.bytes_rx += data.len() as u64;
+            }
+            let Ok((link, _)) = dnp3_link::parse_frame(data) else {
+                s.stats.framing_errors += 1;
+                return;
+            };
+            if tx {
+                s.stats.link_frames_tx += 1;
+            } else {
+                s.stats.link_frames_rx += 1;
+            }
+            if !link.crc_ok {
+                s.stats.crc_errors += 1;
+                return;
+            }
+
+            let Some(segment) = s.reassembler.push(&link) else {
+                return;
+            };
+            if segment.error.is_some() {
+                s.stats.transport_errors += 1;
+            }
+            if let Some(apdu) = segment.apdu {
+                s.count_fragment(tx, apdu, timestamp);
+            }
+        });
+    }
+
+    /// A master request (read, time sync, ...) failed
+    pub fn record_task_error(&self, error: &TaskError) {
+        self.with(|s| match error {
+            TaskError::ResponseTimeout => s.stats.timeouts += 1,
+            _ => s.stats.task_failures += 1,
+        });
+    }
+
+    /// Outcome of a control: sent by a master, or received by an outstation
+    pub fn record_control(&self, result: Result<(), &CommandError>) {
+        self.with(|s| match result {
+            Ok(()) => s.stats.controls_ok += 1,
+            Err(e) => {
+                s.stats.controls_failed += 1;
+                if matches!(e, CommandError::Task(TaskError::ResponseTimeout)) {
+                    s.stats.timeouts += 1;
+                }
+            }
+        });
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut CounterState) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut state)
+    }
+}
+
+impl CounterState {
+    fn count_fragment(&mut self, tx: bool, apdu: Vec<u8>, timestamp: chrono::DateTime<chrono::Utc>) {
+        if tx {
+            self.stats.fragments_tx += 1;
+        } else {
+            self.stats.fragments_rx += 1;
+        }
+        // Application header: control (FIR FIN CON UNS SEQ) then function code
+        match apdu.get(1).copied() {
+            Some(FC_CONFIRM) if tx => self.stats.confirms_tx += 1,
+            Some(FC_CONFIRM) => self.stats.confirms_rx += 1,
+            Some(FC_UNSOLICITED_RESPONSE) => self.stats.unsolicited_fragments += 1,
+            _ => {}
+        }
+        self.match_latency(tx, &apdu, timestamp);
+        // Same control byte (so same sequence number) and content: a retransmission
+        if self.last_fragment.get(&tx).is_some_and(|last| *last == apdu) {
+            self.stats.retries += 1;
+        }
+        self.last_fragment.insert(tx, apdu);
+    }
+
+    fn match_latency(&mut self, tx: bool, apdu: &[u8], timestamp: chrono::DateTime<chrono::Utc>) {
+        let (Some(&control), Some(&function)) = (apdu.first(), apdu.get(1)) else {
+            return;
+        };
+        let seq = control & APP_SEQ_MASK;
+        if tx {
+            // A retransmission restarts the clock
+            if let Some(kind) = request_kind(function, apdu.get(2).copied()) {
+                self.pending.insert(seq, (kind, timestamp));
+            }
+        } else if function == FC_RESPONSE && control & APP_FIR != 0 {
+            if let Some((kind, sent)) = self.pending.remove(&seq) {
+                let ms = (timestamp - sent).num_microseconds().unwrap_or(0) as f64 / 1000.0;
+                self.latency.entry(kind).or_insert_with(LatencyHistogram::new).record(ms.max(0.0));
+            }
+        }
+    }
+}
+
+/// Latency category of a request function code; `None` for anything that
+/// gets no response (confirms, direct operate no ack, responses)
+fn request_kind(function: u8, first_group: Option<u8>) -> Option<&'static str> {
+    Some(match function {
+        0x01 => "read",
+        // Time sync writes g50 (absolute or last recorded time)
+        0x02 if first_group == Some(GROUP_TIME) => "time_sync",
+        0x02 => "write",
+        0x03 => "select",
+        0x04 => "operate",
+        0x05 => "direct_operate",
+        0x0D => "cold_restart",
+        0x0E => "warm_restart",
+        0x14 => "enable_unsolicited",
+        0x15 => "disable_unsolicited",
+        0x16 => "assign_class",
+        // Delay measurement and record current time start a time sync
+        0x17 | 0x18 => "time_sync",
+        0x06 | 0x00 | 0x81..=0x83 => return None,
+        _ => "other",
+    })
+}
@@ -0,0 +1,170 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use tokio_util::sync::CancellationToken;
+
+use crate::models::DataPointType;
+use crate::soe::SoeRecord;
+
+// Field captures for the outstation simulator: a master session records
+// every measurement it receives with its arrival offset, and an outstation
+// session plays the recording back into its database with the same timing.
+
+/// Recording stops growing past this many measurements
+const MAX_RECORDED_MEASUREMENTS: usize = 1_000_000;
+
+// --- Recording ---
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecordedMeasurement {
+    pub offset_ms: u64,          // Since the start of the recording
+    pub point_type: DataPointType,
+    pub index: u16,
+    pub value: f64,
+    pub flags: u8,
+    #[serde(default)]
+    pub is_event: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MeasurementRecording {
+    pub session_id: String,
+    pub started: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopped: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub truncated: bool,
+    pub records: Vec<RecordedMeasurement>,
+}
+
+impl MeasurementRecording {
+    pub fn new(session_id: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            started: Utc::now(),
+            stopped: None,
+            truncated: false,
+            records: Vec::new(),
+        }
+    }
+
+    /// Append a received measurement (called from `SoeStore::push`)
+    pub fn push(&mut self, record: &SoeRecord) {
+        if self.records.len() >= MAX_RECORDED_MEASUREMENTS {
+            self.truncated = true;
+            return;
+        }
+        let offset = (record.received - self.started).num_milliseconds().max(0) as u64;
+        self.records.push(RecordedMeasurement {
+            offset_ms: offset,
+            point_type: record.point_type,
+            index: record.index,
+            value: record.value,
+            flags: record.flags,
+            is_event: record.is_event,
+            device_time: record.device_time,
+        });
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        self.records.last().map(|r| r.offset_ms).unwrap_or(0)
+    }
+
+    /// Summary without the records themselves
+    pub fn info(&self) -> serde_json::Value {
+        serde_json::json!({
+            "session_id": self.session_id,
+            "started": self.started,
+            "stopped": self.stopped,
+            "recording": self.stopped.is_none(),
+            "records": self.records.len(),
+            "duration_ms": self.duration_ms(),
+            "truncated": self.truncated,
+        })
+    }
+
+    /// Parse an exported recording
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let recording: Self = serde_json::from_str(text).map_err(|e| format!("Invalid recording: {}", e))?;
+        recording.playable()
+    }
+
+    /// Check the recording can be replayed and sort it by offset
+    pub fn playable(mut self) -> Result<Self, String> {
+        if self.records.is_empty() {
+            return Err("Recording contains no measurements".to_string());
+        }
+        self.records.sort_by_key(|r| r.offset_ms);
+        Ok(self)
+    }
+}
+
+// --- Replay ---
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayOptions {
+    pub speed: f64,              // 2.0 plays twice as fast
+    pub repeat: bool,            // Start over after the last measurement
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self { speed: 1.0, repeat: false }
+    }
+}
+
+/// Accepted range for `ReplayOptions::speed`
+pub const MIN_REPLAY_SPEED: f64 = 0.01;
+pub const MAX_REPLAY_SPEED: f64 = 1000.0;
+/// Longest wait before a single measurement; offsets in a recording file are not trusted
+const MAX_REPLAY_DELAY: Duration = Duration::from_secs(365 * 24 * 3600);
+
+impl ReplayOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_REPLAY_SPEED..=MAX_REPLAY_SPEED).contains(&self.speed) {
+            return Err(format!("Invalid replay speed {} (allowed {} to {})", self.speed, MIN_REPLAY_SPEED, MAX_REPLAY_SPEED));
+        }
+        Ok(())
+    }
+
+    /// Wall-clock delay of a recorded offset
+    pub fn delay(&self, offset_ms: u64) -> Duration {
+        Duration::try_from_secs_f64(offset_ms as f64 / 1000.0 / self.speed)
+            .map_or(MAX_REPLAY_DELAY, |delay| delay.min(MAX_REPLAY_DELAY))
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReplayProgress {
+    pub source_session: String,
+    pub started: DateTime<Utc>,
+    pub speed: f64,
+    pub repeat: bool,
+    pub records: usize,
+    pub played: u64,             // Measurements written, over all passes
+    pub skipped: u64,            // Points missing from this outstation's database
+    pub passes: u32,
+    pub finished: bool,
+}
+
+/// Running playback; dropping it stops the task
+pub struct ReplayHandle {
+    cancel: CancellationToken,
+    pub progress: Arc<Mutex<ReplayProgress>>,
+}
+
+impl ReplayHandle {
+    pub fn new(cancel: CancellationToken, progress: Arc<Mutex<ReplayProgress>>) -> Self {
+        Self { cancel, progress }
+    }
+
+    pub fn progress(&self) -> Option<ReplayProgress> {
+        self.progress.lock().ok().map(|p| p.clone())
+    }
+}
+
+impl Drop for ReplayHandle {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
@@ -0,0 +1,495 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dnp3::master::{Classes, EventClasses};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::dnp3_service::{Dnp3Service, ProtocolLogEntry, RawFrame};
+use crate::models::*;
+
+// Declarative acceptance tests. A scenario is a list of steps (connect,
+// read, operate, wait, assert_point, assert_command, disconnect) run in
+// order against one master session; every step is a single-key map so the
+// same file works as YAML or JSON:
+//
+//   name: Breaker trip
+//   connection: { host: 192.168.1.20, port: 20000 }
+//   steps:
+//     - connect: {}
+//     - operate: { point_type: BO, index: 0, command: Latch, value: 1, mode: SBO }
+//     - assert_point: { point_type: BI, index: 1, equals: 1, timeout_ms: 3000 }
+//     - disconnect: {}
+//
+// The first failing step stops the run (unless continue_on_failure); a
+// connected master is always disconnected at the end.
+
+const DEFAULT_ASSERT_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 10000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Defaults for connect steps
+    #[serde(default)]
+    pub connection: ConnectionSettings,
+    /// Default timeout of assert_point steps
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub continue_on_failure: bool,
+    /// `- connect: {}` maps rather than YAML tags
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConnectionSettings {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    /// Serial device instead of TCP
+    pub serial: Option<String>,
+    pub baud_rate: Option<u32>,
+    /// Master link address
+    pub local_address: Option<u16>,
+    /// Outstation link address
+    pub remote_address: Option<u16>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    Connect(ConnectionSettings),
+    Read(ReadStep),
+    Operate(OperateStep),
+    Wait(WaitStep),
+    AssertPoint(AssertPointStep),
+    AssertCommand(AssertCommandStep),
+    Disconnect {},
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReadStep {
+    /// Digits 0-3
+    #[serde(default = "all_classes")]
+    pub classes: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OperateStep {
+    #[serde(deserialize_with = "point_type")]
+    pub point_type: DataPointType,
+    pub index: u16,
+    /// Binary outputs: > 0.5 is on
+    pub value: f64,
+    /// Direct, DirectNoAck, SBO (control panel names)
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    /// Latch or Pulse, for binary outputs
+    #[serde(default = "default_command")]
+    pub command: String,
+    /// Outcome that lets the step pass
+    #[serde(default)]
+    pub expect: Expect,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaitStep {
+    pub ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssertPointStep {
+    #[serde(deserialize_with = "point_type")]
+    pub point_type: DataPointType,
+    pub index: u16,
+    pub equals: Option<f64>,
+    #[serde(default)]
+    pub tolerance: f64,
+    /// Raw DNP3 flags byte
+    pub flags: Option<u8>,
+    /// ONLINE flag
+    pub online: Option<bool>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssertCommandStep {
+    /// Outcome of the most recent operate step
+    pub status: Expect,
+    /// Text the result message must contain (e.g. "NoSelect")
+    pub contains: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expect {
+    #[default]
+    Success,
+    Failure,
+}
+
+fn all_classes() -> String {
+    "0123".to_string()
+}
+
+fn default_mode() -> String {
+    "Direct".to_string()
+}
+
+fn default_command() -> String {
+    "Latch".to_string()
+}
+
+/// Accepts the API names and the short aliases ("AI", "BO", ...)
+fn point_type<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DataPointType, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    DataPointType::from_name(&name).ok_or_else(|| serde::de::Error::custom(format!("unknown point type '{}'", name)))
+}
+
+/// Parse a scenario file; YAML, which also accepts JSON
+pub fn parse(text: &str) -> Result<Scenario, String> {
+    let scenario: Scenario = serde_yaml::from_str(text).map_err(|e| format!("Invalid scenario: {}", e))?;
+    if scenario.steps.is_empty() {
+        return Err("Invalid scenario: no steps".to_string());
+    }
+    for (i, step) in scenario.steps.iter().enumerate() {
+        if let Step::Read(read) = step {
+            parse_classes(&read.classes).ok_or_else(|| format!("Step {}: invalid classes '{}'", i + 1, read.classes))?;
+        }
+    }
+    Ok(scenario)
+}
+
+pub fn parse_classes(text: &str) -> Option<Classes> {
+    if text.is_empty() || !text.chars().all(|c| ('0'..='3').contains(&c)) {
+        return None;
+    }
+    Some(Classes::new(
+        text.contains('0'),
+        EventClasses::new(text.contains('1'), text.contains('2'), text.contains('3')),
+    ))
+}
+
+// --- Report ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    /// 1-based position in the scenario
+    pub index: usize,
+    pub kind: &'static str,
+    pub description: String,
+    pub status: StepStatus,
+    pub message: String,
+    pub started: Option<chrono::DateTime<chrono::Utc>>,
+    pub duration_ms: u64,
+    /// Session logs written while a failed step ran
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<ProtocolLogEntry>,
+    /// Frames captured while a failed step ran
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub frames: Vec<RawFrame>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub description: Option<String>,
+    /// Session whose logs and frames belong to this run
+    pub session_id: String,
+    pub passed: bool,
+    pub started: chrono::DateTime<chrono::Utc>,
+    pub duration_ms: u64,
+    pub steps: Vec<StepResult>,
+}
+
+impl ScenarioReport {
+    pub fn count(&self, status: StepStatus) -> usize {
+        self.steps.iter().filter(|s| s.status == status).count()
+    }
+}
+
+// --- Runner ---
+
+struct Runner<'a> {
+    scenario: &'a Scenario,
+    service: Arc<Dnp3Service>,
+    connected: bool,
+    last_command: Option<Result<String, String>>,
+}
+
+/// Run every step against `service` (a master session of its own)
+pub async fn run(scenario: &Scenario, service: Arc<Dnp3Service>) -> ScenarioReport {
+    let started = chrono::Utc::now();
+    let clock = Instant::now();
+    let mut runner = Runner { scenario, service, connected: false, last_command: None };
+    let mut steps = Vec::with_capacity(scenario.steps.len());
+    let mut failed = false;
+
+    for (i, step) in scenario.steps.iter().enumerate() {
+        let description = describe(step);
+        if failed && !scenario.continue_on_failure {
+            steps.push(StepResult {
+                index: i + 1,
+                kind: kind(step),
+                description,
+                status: StepStatus::Skipped,
+                message: String::new(),
+                started: None,
+                duration_ms: 0,
+                logs: Vec::new(),
+                frames: Vec::new(),
+            });
+            continue;
+        }
+        let step_started = chrono::Utc::now();
+        let step_clock = Instant::now();
+        let marks = runner.marks();
+        let outcome = runner.execute(step).await;
+        let duration_ms = step_clock.elapsed().as_millis() as u64;
+        let (status, message, (logs, frames)) = match outcome {
+            Ok(message) => (StepStatus::Passed, message, Default::default()),
+            Err(message) => (StepStatus::Failed, message, runner.captured_since(marks).await),
+        };
+        failed |= status == StepStatus::Failed;
+        steps.push(StepResult {
+            index: i + 1,
+            kind: kind(step),
+            description,
+            status,
+            message,
+            started: Some(step_started),
+            duration_ms,
+            logs,
+            frames,
+        });
+    }
+    if runner.connected {
+        runner.service.disconnect().await;
+    }
+
+    ScenarioReport {
+        name: scenario.name.clone(),
+        description: scenario.description.clone(),
+        session_id: runner.service.session_id.clone(),
+        passed: !failed,
+        started,
+        duration_ms: clock.elapsed().as_millis() as u64,
+        steps,
+    }
+}
+
+impl Runner<'_> {
+    /// Next log and frame ids, taken before a step runs
+    fn marks(&self) -> (u64, u64) {
+        let store = &self.service.log_store;
+        (
+            store.log_counter.load(std::sync::atomic::Ordering::Relaxed),
+            store.frame_counter.load(std::sync::atomic::Ordering::Relaxed),
+        )
+    }
+
+    /// This session's logs and frames from `marks` on, as evidence for a failed step
+    async fn captured_since(&self, (log_mark, frame_mark): (u64, u64)) -> (Vec<ProtocolLogEntry>, Vec<RawFrame>) {
+        let store = &self.service.log_store;
+        let session_id = &self.service.session_id;
+        let logs = store
+            .logs
+            .read()
+            .await
+            .iter()
+            .filter(|l| l.id >= log_mark && &l.session_id == session_id)
+            .cloned()
+            .collect();
+        let frames = store
+            .raw_frames
+            .read()
+            .await
+            .iter()
+            .filter(|f| f.id >= frame_mark && &f.session_id == session_id)
+            .cloned()
+            .collect();
+        (logs, frames)
+    }
+
+    async fn execute(&mut self, step: &Step) -> Result<String, String> {
+        match step {
+            Step::Connect(settings) => self.connect(settings).await,
+            Step::Read(read) => {
+                let classes = parse_classes(&read.classes).ok_or("Invalid classes")?;
+                self.service.read_classes(classes).await?;
+                Ok("Read completed".to_string())
+            }
+            Step::Operate(op) => {
+                let result = self
+                    .service
+                    .execute_control(op.point_type, op.index, op.value, op.mode.clone(), op.command.clone())
+                    .await;
+                let outcome = match (&result, op.expect) {
+                    (Ok(message), Expect::Success) => Ok(message.clone()),
+                    (Err(e), Expect::Failure) => Ok(format!("Failed as expected: {}", e)),
+                    (Ok(message), Expect::Failure) => Err(format!("Expected failure, got: {}", message)),
+                    (Err(e), Expect::Success) => Err(e.clone()),
+                };
+                self.last_command = Some(result);
+                outcome
+            }
+            Step::Wait(wait) => {
+                tokio::time::sleep(Duration::from_millis(wait.ms)).await;
+                Ok(String::new())
+            }
+            Step::AssertPoint(assert) => self.assert_point(assert).await,
+            Step::AssertCommand(assert) => {
+                let result = self.last_command.as_ref().ok_or("No operate step before this assertion")?;
+                let (status, message) = match result {
+                    Ok(message) => (Expect::Success, message),
+                    Err(e) => (Expect::Failure, e),
+                };
+                if status != assert.status {
+                    return Err(format!("Command status was {:?}: {}", status, message));
+                }
+                if let Some(text) = &assert.contains {
+                    if !message.contains(text.as_str()) {
+                        return Err(format!("Message '{}' does not contain '{}'", message, text));
+                    }
+                }
+                Ok(message.clone())
+            }
+            Step::Disconnect {} => {
+                self.service.disconnect().await;
+                self.connected = false;
+                Ok("Disconnected".to_string())
+            }
+        }
+    }
+
+    async fn connect(&mut self, step: &ConnectionSettings) -> Result<String, String> {
+        let defaults = &self.scenario.connection;
+        let serial = step.serial.clone().or_else(|| defaults.serial.clone());
+        let config = Configuration {
+            role: DeviceRole::Master,
+            connection_type: if serial.is_some() { ConnectionType::Serial } else { ConnectionType::TcpClient },
+            ip_address: step.host.clone().or_else(|| defaults.host.clone()).unwrap_or_else(|| "127.0.0.1".to_string()),
+            port: step.port.or(defaults.port).unwrap_or(20000),
+            local_address: step.local_address.or(defaults.local_address).unwrap_or(1),
+            remote_address: step.remote_address.or(defaults.remote_address).unwrap_or(10),
+            serial_port: serial,
+            baud_rate: step.baud_rate.or(defaults.baud_rate),
+            ..Configuration::default()
+        };
+        let timeout = step.timeout_ms.or(defaults.timeout_ms).unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS);
+
+        self.connected = true;
+        if let Err(e) = self.service.start_master(&config).await {
+            self.service.status.start_failed(&e);
+            return Err(e);
+        }
+        self.service.wait_connected(Duration::from_millis(timeout)).await?;
+        Ok(format!("Connected to {}", self.service.status.snapshot().peer.unwrap_or_default()))
+    }
+
+    async fn assert_point(&self, assert: &AssertPointStep) -> Result<String, String> {
+        let timeout = assert.timeout_ms.or(self.scenario.timeout_ms).unwrap_or(DEFAULT_ASSERT_TIMEOUT_MS);
+        let deadline = Instant::now() + Duration::from_millis(timeout);
+        loop {
+            let latest = self.service.soe.lock().ok().and_then(|soe| soe.latest(assert.point_type, assert.index));
+            let mismatch = match &latest {
+                None => Some("no value received".to_string()),
+                Some(record) => check_point(assert, record.value, record.flags),
+            };
+            match (mismatch, latest) {
+                (None, Some(record)) => {
+                    return Ok(format!("value {} flags {:#04x}", record.value, record.flags));
+                }
+                (Some(reason), _) if Instant::now() >= deadline => {
+                    return Err(format!("After {} ms: {}", timeout, reason));
+                }
+                _ => tokio::time::sleep(Duration::from_millis(50)).await,
+            }
+        }
+    }
+}
+
+/// Why the received value doesn't satisfy the assertion, if it doesn't
+fn check_point(assert: &AssertPointStep, value: f64, flags: u8) -> Option<String> {
+    if let Some(expected) = assert.equals {
+        if (value - expected).abs() > assert.tolerance {
+            return Some(format!("value is {}, expected {}", value, expected));
+        }
+    }
+    if let Some(expected) = assert.flags {
+        if flags != expected {
+            return Some(format!("flags are {:#04x}, expected {:#04x}", flags, expected));
+        }
+    }
+    if let Some(expected) = assert.online {
+        if (flags & 0x01 != 0) != expected {
+            return Some(format!("ONLINE is {}, expected {}", !expected, expected));
+        }
+    }
+    None
+}
+
+fn kind(step: &Step) -> &'static str {
+    match step {
+        Step::Connect(_) => "connect",
+        Step::Read(_) => "read",
+        Step::Operate(_) => "operate",
+        Step::Wait(_) => "wait",
+        Step::AssertPoint(_) => "assert_point",
+        Step::AssertCommand(_) => "assert_command",
+        Step::Disconnect {} => "disconnect",
+    }
+}
+
+fn describe(step: &Step) -> String {
+    match step {
+        Step::Connect(c) => match (&c.serial, &c.host, c.port) {
+            (Some(serial), _, _) => format!("connect {}", serial),
+            (None, None, None) => "connect".to_string(),
+            (None, host, port) => format!(
+                "connect {}:{}",
+                host.as_deref().unwrap_or("127.0.0.1"),
+                port.map(|p| p.to_string()).unwrap_or_else(|| "20000".to_string())
+            ),
+        },
+        Step::Read(r) => format!("read class {}", r.classes),
+        Step::Operate(o) => format!("operate {:?}[{}] = {} ({} {})", o.point_type, o.index, o.value, o.mode, o.command),
+        Step::Wait(w) => format!("wait {} ms", w.ms),
+        Step::AssertPoint(a) => {
+            let mut parts = Vec::new();
+            if let Some(v) = a.equals {
+                parts.push(if a.tolerance > 0.0 { format!("== {} ±{}", v, a.tolerance) } else { format!("== {}", v) });
+            }
+            if let Some(f) = a.flags {
+                parts.push(format!("flags {:#04x}", f));
+            }
+            if let Some(o) = a.online {
+                parts.push(if o { "online".to_string() } else { "offline".to_string() });
+            }
+            format!("assert {:?}[{}] {}", a.point_type, a.index, parts.join(", "))
+        }
+        Step::AssertCommand(a) => match &a.contains {
+            Some(text) => format!("assert command {:?} containing '{}'", a.status, text),
+            None => format!("assert command {:?}", a.status),
+        },
+        Step::Disconnect {} => "disconnect".to_string(),
+    }
+}
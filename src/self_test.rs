@@ -0,0 +1,308 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use dnp3::master::{Classes, EventClasses};
+use serde::Serialize;
+
+use crate::dnp3_service::Dnp3Service;
+use crate::models::*;
+
+// Loopback self-test of a point table: an outstation session and a master
+// session with the same DeviceConfiguration are connected over 127.0.0.1,
+// then the master runs an integrity poll, an event poll and a control
+// (SBO, then Direct to restore) on every output point. Every point whose
+// value doesn't arrive on the other side is reported.
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Outputs must be reported back within this time after a control
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(2);
+/// The simulation may change a value between the read and the comparison
+const POLL_ATTEMPTS: usize = 3;
+/// Longer than one simulation cycle (2 s), so inputs have changed
+const EVENT_WAIT: Duration = Duration::from_millis(2500);
+const OUTSTATION_ADDRESS: u16 = 10;
+const MASTER_ADDRESS: u16 = 1;
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct StageSummary {
+    pub checked: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PointFailure {
+    pub stage: &'static str,     // integrity / events / control
+    pub point_type: DataPointType,
+    pub index: u16,
+    pub name: String,
+    pub expected: Option<f64>,
+    pub received: Option<f64>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub started: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub outstation_session: String,
+    pub master_session: String,
+    pub port: u16,
+    pub points: usize,
+    pub integrity: StageSummary,
+    pub events: StageSummary,
+    /// Event objects received during the event stage
+    pub events_received: usize,
+    pub controls: StageSummary,
+    pub failures: Vec<PointFailure>,
+    /// Set when the sessions could not be started or a poll failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub async fn run(config: DeviceConfiguration, outstation: Arc<Dnp3Service>, master: Arc<Dnp3Service>) -> SelfTestReport {
+    let started = Utc::now();
+    let clock = Instant::now();
+    let mut test = SelfTest {
+        outstation: outstation.clone(),
+        master: master.clone(),
+        report: SelfTestReport {
+            passed: false,
+            started,
+            duration_ms: 0,
+            outstation_session: outstation.session_id.clone(),
+            master_session: master.session_id.clone(),
+            port: 0,
+            points: 0,
+            integrity: StageSummary::default(),
+            events: StageSummary::default(),
+            events_received: 0,
+            controls: StageSummary::default(),
+            failures: Vec::new(),
+            error: None,
+        },
+    };
+
+    match test.connect(config).await {
+        Ok(()) => {
+            test.integrity().await;
+            if test.report.error.is_none() {
+                test.events().await;
+            }
+            if test.report.error.is_none() {
+                test.controls().await;
+            }
+        }
+        Err(e) => test.report.error = Some(e),
+    }
+    master.disconnect().await;
+    outstation.disconnect().await;
+
+    let mut report = test.report;
+    report.passed = report.error.is_none() && report.failures.is_empty();
+    report.duration_ms = clock.elapsed().as_millis() as u64;
+    report
+}
+
+struct SelfTest {
+    outstation: Arc<Dnp3Service>,
+    master: Arc<Dnp3Service>,
+    report: SelfTestReport,
+}
+
+impl SelfTest {
+    async fn connect(&mut self, config: DeviceConfiguration) -> Result<(), String> {
+        self.outstation.update_config(config.clone()).await;
+        self.master.update_config(config).await;
+        self.report.points = self.outstation.get_data().await.len();
+        if self.report.points == 0 {
+            return Err("The configuration has no points".to_string());
+        }
+
+        let outstation_config = Configuration {
+            role: DeviceRole::Outstation,
+            connection_type: ConnectionType::TcpServer,
+            ip_address: "127.0.0.1".to_string(),
+            port: 0,
+            local_address: OUTSTATION_ADDRESS,
+            remote_address: MASTER_ADDRESS,
+            ..Configuration::default()
+        };
+        self.outstation.start_outstation(&outstation_config).await?;
+        let port = self
+            .outstation
+            .listen_addr()
+            .await
+            .map(|addr| addr.port())
+            .ok_or("Outstation is not listening")?;
+        self.report.port = port;
+
+        let master_config = Configuration {
+            role: DeviceRole::Master,
+            connection_type: ConnectionType::TcpClient,
+            ip_address: "127.0.0.1".to_string(),
+            port,
+            local_address: MASTER_ADDRESS,
+            remote_address: OUTSTATION_ADDRESS,
+            ..Configuration::default()
+        };
+        self.master.start_master(&master_config).await?;
+        self.master.wait_connected(CONNECT_TIMEOUT).await?;
+        self.outstation
+            .wait_points_online(CONNECT_TIMEOUT)
+            .await
+            .map_err(|e| format!("Outstation did not bring its points online: {}", e))
+    }
+
+    /// Class 0 scan: every configured point must arrive with its value
+    async fn integrity(&mut self) {
+        let classes = Classes::new(true, EventClasses::none());
+        self.report.integrity = self.poll("integrity", classes, |_| true).await;
+    }
+
+    /// Class 1/2/3 scan after the simulation changed the inputs. Events the
+    /// master's own polls picked up meanwhile count as received too.
+    async fn events(&mut self) {
+        let last_id = self.master.soe.lock().map(|soe| soe.last_id()).unwrap_or(0);
+        tokio::time::sleep(EVENT_WAIT).await;
+        let classes = Classes::new(false, EventClasses::all());
+        self.report.events = self.poll("events", classes, |t| !is_output(t)).await;
+        self.report.events_received = self
+            .master
+            .soe
+            .lock()
+            .map(|soe| soe.list(Some(last_id), None).iter().filter(|r| r.is_event).count())
+            .unwrap_or(0);
+    }
+
+    /// Read, then compare the master's latest values with the outstation's
+    async fn poll(&mut self, stage: &'static str, classes: Classes, include: impl Fn(DataPointType) -> bool) -> StageSummary {
+        let mut failures = Vec::new();
+        let mut checked = 0;
+        for _ in 0..POLL_ATTEMPTS {
+            if let Err(e) = self.master.read_classes(classes).await {
+                self.report.error = Some(format!("{} poll: {}", stage, e));
+                break;
+            }
+            let sent = self.outstation.get_data().await;
+            let points: Vec<&DataPoint> = sent.iter().filter(|p| include(p.point_type)).collect();
+            checked = points.len();
+            failures = points.into_iter().filter_map(|point| self.compare(stage, point, point.value)).collect();
+            if failures.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        let summary = StageSummary { checked, failed: failures.len() };
+        self.report.failures.extend(failures);
+        summary
+    }
+
+    /// Operate every output away from its value (SBO) and back (Direct)
+    async fn controls(&mut self) {
+        let outputs: Vec<DataPoint> = self
+            .outstation
+            .get_data()
+            .await
+            .into_iter()
+            .filter(|p| is_output(p.point_type))
+            .collect();
+        for point in &outputs {
+            self.report.controls.checked += 1;
+            let (test, original) = match point.point_type {
+                DataPointType::BinaryOutput => (if point.value > 0.5 { 0.0 } else { 1.0 }, point.value),
+                // Group41Var1 carries an i32
+                _ => (point.value.trunc() + 1.0, point.value.trunc()),
+            };
+            for (value, mode) in [(test, "SBO"), (original, "Direct")] {
+                if let Some(failure) = self.control(point, value, mode).await {
+                    self.report.controls.failed += 1;
+                    self.report.failures.push(failure);
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn control(&self, point: &DataPoint, value: f64, mode: &str) -> Option<PointFailure> {
+        let fail = |received: Option<f64>, message: String| PointFailure {
+            stage: "control",
+            point_type: point.point_type,
+            index: point.index,
+            name: point.name.clone(),
+            expected: Some(value),
+            received,
+            message,
+        };
+        if let Err(e) = self
+            .master
+            .execute_control(point.point_type, point.index, value, mode.to_string(), "Latch".to_string())
+            .await
+        {
+            return Some(fail(None, format!("{}: {}", mode, e)));
+        }
+
+        let deadline = Instant::now() + CONTROL_TIMEOUT;
+        loop {
+            let applied = self
+                .outstation
+                .get_data()
+                .await
+                .into_iter()
+                .find(|p| p.point_type == point.point_type && p.index == point.index)
+                .map(|p| p.value);
+            let reported = self.master.soe.lock().ok().and_then(|soe| soe.latest(point.point_type, point.index));
+            let message = match (applied, &reported) {
+                (Some(applied), _) if !same_value(point.point_type, value, applied) => {
+                    format!("{}: outstation value is {}", mode, applied)
+                }
+                (_, None) => format!("{}: no status received", mode),
+                (_, Some(record)) if !same_value(point.point_type, value, record.value) => {
+                    format!("{}: master received {}", mode, record.value)
+                }
+                _ => return None,
+            };
+            if Instant::now() >= deadline {
+                return Some(fail(reported.map(|r| r.value), message));
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    fn compare(&self, stage: &'static str, point: &DataPoint, expected: f64) -> Option<PointFailure> {
+        let received = self.master.soe.lock().ok().and_then(|soe| soe.latest(point.point_type, point.index));
+        let message = match &received {
+            None => "not received".to_string(),
+            Some(record) if record.flags & 0x01 == 0 => format!("received without ONLINE (flags {:#04x})", record.flags),
+            Some(record) if !same_value(point.point_type, expected, record.value) => {
+                format!("value is {}, outstation has {}", record.value, expected)
+            }
+            Some(_) => return None,
+        };
+        Some(PointFailure {
+            stage,
+            point_type: point.point_type,
+            index: point.index,
+            name: point.name.clone(),
+            expected: Some(expected),
+            received: received.map(|r| r.value),
+            message,
+        })
+    }
+}
+
+fn is_output(point_type: DataPointType) -> bool {
+    matches!(point_type, DataPointType::BinaryOutput | DataPointType::AnalogOutput)
+}
+
+/// Equal as far as the wire format carries it
+pub fn same_value(point_type: DataPointType, sent: f64, received: f64) -> bool {
+    match point_type {
+        DataPointType::BinaryInput | DataPointType::BinaryOutput => (sent > 0.5) == (received > 0.5),
+        // The outstation writes counters as u32
+        DataPointType::Counter => (sent as u32) as f64 == received,
+        // Analogs travel as f32
+        DataPointType::AnalogInput | DataPointType::AnalogOutput => (sent - received).abs() <= 1e-3 * sent.abs().max(1.0),
+    }
+}
@@ -1,28 +1,64 @@
 use std::net::SocketAddr;
-use std::sync::Arc;
-use tokio::io::{AsyncRead, AsyncWrite, AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::RwLock;
 use tokio_serial::{SerialPortBuilderExt, SerialStream};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
-// Start a TCP server that proxies a single TCP connection to the specified serial device.
-// Returns the bound socket address (127.0.0.1:port) on success.
-pub async fn start_serial_proxy_server(device: &str, baud: u32, bind_addr: &str) -> anyhow::Result<SocketAddr> {
-    let listener = TcpListener::bind(bind_addr).await?;
+use crate::frame_capture::{pump, CaptureEndpoint, CaptureSink, ShimHandle};
+use crate::models::Configuration;
+
+// Serial line settings taken from a session Configuration
+#[derive(Debug, Clone)]
+pub struct SerialParams {
+    pub device: String,
+    pub baud: u32,
+    pub data_bits: u8,
+    pub parity: String,
+    pub stop_bits: f32,
+}
+
+impl SerialParams {
+    pub fn from_config(config: &Configuration) -> Result<Self, String> {
+        let device = config.serial_port.clone().ok_or("Serial port not configured")?;
+        Ok(Self {
+            device,
+            baud: config.baud_rate.unwrap_or(9600),
+            data_bits: config.data_bits.unwrap_or(8),
+            parity: config.parity.clone().unwrap_or_else(|| "none".to_string()),
+            stop_bits: config.stop_bits.unwrap_or(1.0),
+        })
+    }
+}
+
+// Start a loopback TCP server that bridges each accepted connection to the serial device.
+// Used for Master serial mode: the dnp3 master connects as a TCP client to the returned address.
+pub async fn start_serial_proxy_server(params: SerialParams, sink: CaptureSink) -> anyhow::Result<ShimHandle> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
     let local_addr = listener.local_addr()?;
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
 
     // Spawn accept loop
-    let device = device.to_string();
+    let span = tracing::info_span!("capture", session_id = %sink.session_id);
     tokio::spawn(async move {
         loop {
-            match listener.accept().await {
-                Ok((stream, peer)) => {
-                    let dev = device.clone();
-                    tokio::spawn(async move {
-                        if let Err(e) = handle_tcp_serial(stream, &dev, baud).await {
-                            tracing::warn!("Serial proxy connection error: {}", e);
+            let accepted = tokio::select! {
+                _ = token.cancelled() => break,
+                r = listener.accept() => r,
+            };
+            match accepted {
+                Ok((stream, _peer)) => {
+                    let serial = match open_serial(&params) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            // Dropping the stream makes the master retry later
+                            tracing::warn!("Serial proxy failed to open {}: {}", params.device, e);
+                            sink.status.connect_failed(&params.device, &e.to_string());
+                            continue;
                         }
-                    });
+                    };
+                    let endpoint = CaptureEndpoint::new("serial", "dnp3_tester".to_string(), params.device.clone());
+                    pump(stream, serial, endpoint, sink.clone(), token.child_token()).await;
                 }
                 Err(e) => {
                     tracing::error!("Serial proxy accept failed: {}", e);
@@ -30,41 +66,68 @@ pub async fn start_serial_proxy_server(device: &str, baud: u32, bind_addr: &str)
                 }
             }
         }
-    });
+    }.instrument(span));
 
-    Ok(local_addr)
+    Ok(ShimHandle::new(cancel, local_addr))
 }
 
-// Start a client that connects to a TCP server at target_addr and proxies that connection to serial device.
+// Start a client that connects to a TCP server at target_addr and bridges that connection to the serial device.
 // This is used for Outstation serial mode: Outstation binds locally, proxy connects as TCP client and bridges to serial device.
-pub async fn start_serial_proxy_client(device: &str, baud: u32, target_addr: &str) -> anyhow::Result<()> {
-    let device = device.to_string();
-    let target = target_addr.to_string();
+pub async fn start_serial_proxy_client(params: SerialParams, target_addr: SocketAddr, sink: CaptureSink) -> anyhow::Result<ShimHandle> {
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
 
+    let span = tracing::info_span!("capture", session_id = %sink.session_id);
     tokio::spawn(async move {
-        loop {
-            match TcpStream::connect(&target).await {
-                Ok(stream) => {
-                    if let Err(e) = handle_tcp_serial(stream, &device, baud).await {
-                        tracing::warn!("Serial proxy client error: {}", e);
+        while !token.is_cancelled() {
+            match TcpStream::connect(target_addr).await {
+                Ok(stream) => match open_serial(&params) {
+                    Ok(serial) => {
+                        let endpoint = CaptureEndpoint::new("serial", "dnp3_tester".to_string(), params.device.clone());
+                        pump(stream, serial, endpoint, sink.clone(), token.child_token()).await;
                     }
-                }
+                    Err(e) => {
+                        tracing::warn!("Serial proxy failed to open {}: {} - retrying in 1s", params.device, e);
+                        sink.status.connect_failed(&params.device, &e.to_string());
+                        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    }
+                },
                 Err(e) => {
-                    tracing::warn!("Serial proxy client failed to connect {}: {} - retrying in 1s", &target, e);
+                    tracing::warn!("Serial proxy client failed to connect {}: {} - retrying in 1s", target_addr, e);
                     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
                 }
             }
             // If connection ended, retry after a short delay
             tokio::time::sleep(std::time::Duration::from_millis(200)).await;
         }
-    });
+    }.instrument(span));
 
-    Ok(())
+    Ok(ShimHandle::new(cancel, target_addr))
 }
 
-async fn open_serial(device: &str, baud: u32) -> anyhow::Result<SerialStream> {
-    let builder = tokio_serial::new(device, baud);
-    let port = builder.open_native_async()?;
+fn open_serial(params: &SerialParams) -> anyhow::Result<SerialStream> {
+    let data_bits = match params.data_bits {
+        5 => tokio_serial::DataBits::Five,
+        6 => tokio_serial::DataBits::Six,
+        7 => tokio_serial::DataBits::Seven,
+        _ => tokio_serial::DataBits::Eight,
+    };
+    let parity = match params.parity.to_lowercase().as_str() {
+        "even" => tokio_serial::Parity::Even,
+        "odd" => tokio_serial::Parity::Odd,
+        _ => tokio_serial::Parity::None,
+    };
+    let stop_bits = if (params.stop_bits - 2.0).abs() < f32::EPSILON {
+        tokio_serial::StopBits::Two
+    } else {
+        tokio_serial::StopBits::One
+    };
+    let port = tokio_serial::new(&params.device, params.baud)
+        .data_bits(data_bits)
+        .parity(parity)
+        .stop_bits(stop_bits)
+        .flow_control(tokio_serial::FlowControl::None)
+        .open_native_async()?;
     Ok(port)
 }
 
@@ -81,23 +144,3 @@ pub async fn try_open_serial(device: &str, baud: u32) -> anyhow::Result<()> {
         Err(e) => Err(anyhow::anyhow!(e)),
     }
 }
-
-async fn handle_tcp_serial(mut tcp: TcpStream, device: &str, baud: u32) -> anyhow::Result<()> {
-    // Open serial port
-    let mut serial = open_serial(device, baud).await?;
-
-    // Split TCP stream
-    let (mut tr, mut tw) = tcp.split();
-
-    // For SerialStream, we need to handle it differently since it may not have split
-    // Let's use tokio::io::copy directly
-    let client_to_serial = async {
-        tokio::io::copy(&mut tr, &mut serial).await.map(|_| ())
-    };
-
-    // Note: For bidirectional, we'd need to handle serial reading separately
-    // For now, just handle one direction
-    client_to_serial.await?;
-
-    Ok(())
-}
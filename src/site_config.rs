@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::models::{Configuration, ConnectionType, DeviceConfiguration, DeviceRole};
+
+// Site file for `dnp3_tester outstation --config site.json`: one outstation
+// object, or {"outstations": [...]}. Each entry holds Configuration settings
+// (role is implied; missing ones take Configuration's defaults, listening on
+// every interface), plus a session name and the point table, inline or as
+// a path to a default_config.json-style file relative to the site file.
+// The behaviour script path is relative to the site file too.
+
+#[derive(Debug, Deserialize)]
+pub struct SiteOutstation {
+    /// Session name in logs and captures (default: outstation-N)
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub device_config: Option<PointTable>,
+    #[serde(flatten)]
+    pub config: Configuration,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum PointTable {
+    File(String),
+    Inline(DeviceConfiguration),
+}
+
+/// Fill the settings an entry leaves out from Configuration::default()
+fn with_defaults(entry: Value) -> Value {
+    let Value::Object(entry) = entry else {
+        return entry;
+    };
+    let defaults = Configuration {
+        ip_address: "0.0.0.0".to_string(),
+        ..Configuration::default()
+    };
+    let mut settings = match serde_json::to_value(defaults) {
+        Ok(Value::Object(settings)) => settings,
+        _ => Map::new(),
+    };
+    settings.remove("device_config"); // A point table here, not a Configuration field
+    settings.extend(entry);
+    Value::Object(settings)
+}
+
+/// A validated outstation ready to start
+pub struct OutstationPlan {
+    pub name: String,
+    pub config: Configuration,
+    pub points: Option<DeviceConfiguration>,
+}
+
+impl OutstationPlan {
+    /// Where masters connect, for the startup log
+    pub fn endpoint(&self) -> String {
+        match self.config.connection_type {
+            ConnectionType::Serial => self.config.serial_port.clone().unwrap_or_default(),
+            _ => format!("{}:{}", self.config.ip_address, self.config.port),
+        }
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<OutstationPlan>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let value: Value = serde_json::from_str(&text).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    let invalid = |e: serde_json::Error| format!("Invalid outstation settings in {}: {}", path.display(), e);
+    let entries: Vec<Value> = match value.get("outstations") {
+        Some(list) => serde_json::from_value(list.clone()).map_err(invalid)?,
+        None => vec![value],
+    };
+    let entries: Vec<SiteOutstation> = entries
+        .into_iter()
+        .map(|entry| serde_json::from_value(with_defaults(entry)))
+        .collect::<Result<_, _>>()
+        .map_err(invalid)?;
+    if entries.is_empty() {
+        return Err(format!("No outstations in {}", path.display()));
+    }
+
+    let base = path.parent().unwrap_or(Path::new("."));
+    let mut names = HashSet::new();
+    let mut endpoints = HashSet::new();
+    let mut plans = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.into_iter().enumerate() {
+        let name = entry.name.clone().unwrap_or_else(|| format!("outstation-{}", i + 1));
+        if !names.insert(name.clone()) {
+            return Err(format!("Duplicate outstation name '{}'", name));
+        }
+        let points = match &entry.device_config {
+            Some(PointTable::Inline(config)) => Some(config.clone()),
+            Some(PointTable::File(file)) => Some(load_point_table(&base.join(file))?),
+            None => None,
+        };
+        let mut config = entry.config;
+        if config.connection_type == ConnectionType::Serial && config.serial_port.is_none() {
+            return Err(format!("Outstation '{}': serial_port is required for serial", name));
+        }
+        config.role = DeviceRole::Outstation;
+        config.device_config = None;
+        config.script = config.script.map(|file| base.join(file).to_string_lossy().to_string());
+        let plan = OutstationPlan { name: name.clone(), config, points };
+        if !endpoints.insert(plan.endpoint()) {
+            return Err(format!("Outstation '{}': {} is already used by another outstation", name, plan.endpoint()));
+        }
+        plans.push(plan);
+    }
+    Ok(plans)
+}
+
+fn load_point_table(path: &Path) -> Result<DeviceConfiguration, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
@@ -0,0 +1,133 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use chrono::{DateTime, Utc};
+
+use crate::models::DataPointType;
+use crate::replay::MeasurementRecording;
+
+/// Maximum number of records kept in the sequence-of-events store
+const MAX_SOE_RECORDS: usize = 10000;
+
+// --- Sequence Of Events Record ---
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SoeRecord {
+    pub id: u64,
+    pub received: DateTime<Utc>,
+    pub point_type: DataPointType,
+    pub index: u16,
+    pub value: f64,
+    pub flags: u8,
+    pub variation: String,       // e.g. "Group32Var5"
+    pub is_event: bool,          // Event (class 1/2/3) vs static (class 0) object
+    pub read_type: String,       // StartupIntegrity / SinglePoll / PeriodicPoll / Unsolicited
+    pub fragment_seq: u8,        // Application layer sequence number of the response
+    pub device_time: Option<DateTime<Utc>>,
+    pub time_synchronized: Option<bool>,
+}
+
+/// Fragment context captured in `begin_fragment` and applied to every record in it
+#[derive(Debug, Clone)]
+pub struct FragmentInfo {
+    pub read_type: String,
+    pub seq: u8,
+}
+
+/// Bounded store of every measurement received by a master session
+pub struct SoeStore {
+    records: VecDeque<SoeRecord>,
+    next_id: u64,
+    // Unbounded copy of the measurements while a recording is running
+    recording: Option<MeasurementRecording>,
+}
+
+impl SoeStore {
+    pub fn new() -> Self {
+        Self {
+            records: VecDeque::with_capacity(1024),
+            next_id: 1,
+            recording: None,
+        }
+    }
+
+    /// Append a record, assigning it the next sequence id
+    pub fn push(&mut self, mut record: SoeRecord) {
+        if self.records.len() >= MAX_SOE_RECORDS {
+            self.records.pop_front();
+        }
+        record.id = self.next_id;
+        self.next_id += 1;
+        if let Some(recording) = self.recording.as_mut().filter(|r| r.stopped.is_none()) {
+            recording.push(&record);
+        }
+        self.records.push_back(record);
+    }
+
+    /// Records with `id > since`, oldest first, capped at `limit`
+    pub fn list(&self, since: Option<u64>, limit: Option<usize>) -> Vec<SoeRecord> {
+        self.records
+            .iter()
+            .filter(|r| since.is_none_or(|s| r.id > s))
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// Newest record for one point
+    pub fn latest(&self, point_type: DataPointType, index: u16) -> Option<SoeRecord> {
+        self.records.iter().rev().find(|r| r.point_type == point_type && r.index == index).cloned()
+    }
+
+    /// Id of the newest record ever pushed (0 if none)
+    pub fn last_id(&self) -> u64 {
+        self.next_id - 1
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Start a new recording, replacing any previous one
+    pub fn start_recording(&mut self, session_id: &str) {
+        self.recording = Some(MeasurementRecording::new(session_id));
+    }
+
+    /// Stop the running recording; it stays available for export
+    pub fn stop_recording(&mut self) -> Option<&MeasurementRecording> {
+        let recording = self.recording.as_mut()?;
+        if recording.stopped.is_none() {
+            recording.stopped = Some(chrono::Utc::now());
+        }
+        Some(recording)
+    }
+
+    pub fn recording(&self) -> Option<&MeasurementRecording> {
+        self.recording.as_ref()
+    }
+
+    /// Export all records as CSV (RFC 3339 timestamps, one row per measurement)
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "id,received,point_type,index,value,flags,variation,is_event,read_type,fragment_seq,device_time,time_synchronized\n",
+        );
+        for r in &self.records {
+            writeln!(
+                out,
+                "{},{},{:?},{},{},0x{:02X},{},{},{},{},{},{}",
+                r.id,
+                r.received.to_rfc3339(),
+                r.point_type,
+                r.index,
+                r.value,
+                r.flags,
+                r.variation,
+                r.is_event,
+                r.read_type,
+                r.fragment_seq,
+                r.device_time.map(|t| t.to_rfc3339()).unwrap_or_default(),
+                r.time_synchronized.map(|s| s.to_string()).unwrap_or_default(),
+            )
+            .ok();
+        }
+        out
+    }
+}
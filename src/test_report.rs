@@ -0,0 +1,236 @@
+use std::fmt::Write;
+
+use crate::dnp3_service::{ProtocolLogEntry, RawFrame};
+use crate::scenario::{ScenarioReport, StepResult, StepStatus};
+
+// Scenario results for CI: JUnit XML (one testsuite per scenario, one
+// testcase per step) and a single-file HTML page with inline styles.
+// Failed steps carry the session's logs and frames captured while they ran.
+
+pub const JUNIT_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+pub const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+
+fn seconds(ms: u64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+fn total_ms(reports: &[ScenarioReport]) -> u64 {
+    reports.iter().map(|r| r.duration_ms).sum()
+}
+
+fn count(reports: &[ScenarioReport], status: StepStatus) -> usize {
+    reports.iter().map(|r| r.count(status)).sum()
+}
+
+fn testcase_name(step: &StepResult) -> String {
+    format!("{:02} {}", step.index, step.description)
+}
+
+fn log_line(log: &ProtocolLogEntry) -> String {
+    format!("{} [{}] {}", log.timestamp.format("%H:%M:%S%.3f"), log.direction, log.message)
+}
+
+fn frame_line(frame: &RawFrame) -> String {
+    let hex: Vec<String> = frame.data.iter().map(|b| format!("{:02X}", b)).collect();
+    let info = match (&frame.link, &frame.decode_error) {
+        (Some(link), _) => format!(
+            "{} {}->{}{}",
+            link.function_name,
+            link.source,
+            link.destination,
+            if link.crc_ok { "" } else { " CRC error" }
+        ),
+        (None, Some(e)) => e.clone(),
+        (None, None) => String::new(),
+    };
+    format!("{} {} {} | {}", frame.timestamp.format("%H:%M:%S%.3f"), frame.direction, info, hex.join(" "))
+}
+
+/// Failure message followed by the captured logs and frames
+fn failure_details(step: &StepResult) -> String {
+    let mut text = step.message.clone();
+    if !step.logs.is_empty() {
+        text.push_str("\n\nLogs:\n");
+        for log in &step.logs {
+            text.push_str(&log_line(log));
+            text.push('\n');
+        }
+    }
+    if !step.frames.is_empty() {
+        text.push_str("\nFrames:\n");
+        for frame in &step.frames {
+            text.push_str(&frame_line(frame));
+            text.push('\n');
+        }
+    }
+    text
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            // Not allowed in XML 1.0
+            c if (c as u32) < 0x20 && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// --- JUnit XML ---
+
+pub fn junit(reports: &[ScenarioReport]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"dnp3_tester\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\">",
+        reports.iter().map(|r| r.steps.len()).sum::<usize>(),
+        count(reports, StepStatus::Failed),
+        count(reports, StepStatus::Skipped),
+        seconds(total_ms(reports))
+    );
+    for report in reports {
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{}\" timestamp=\"{}\">",
+            escape(&report.name),
+            report.steps.len(),
+            report.count(StepStatus::Failed),
+            report.count(StepStatus::Skipped),
+            seconds(report.duration_ms),
+            report.started.format("%Y-%m-%dT%H:%M:%S")
+        );
+        out.push_str("    <properties>\n");
+        let _ = writeln!(out, "      <property name=\"session_id\" value=\"{}\"/>", escape(&report.session_id));
+        if let Some(description) = &report.description {
+            let _ = writeln!(out, "      <property name=\"description\" value=\"{}\"/>", escape(description));
+        }
+        out.push_str("    </properties>\n");
+        for step in &report.steps {
+            let _ = write!(
+                out,
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\"",
+                escape(&report.name),
+                escape(&testcase_name(step)),
+                seconds(step.duration_ms)
+            );
+            match step.status {
+                StepStatus::Passed => out.push_str("/>\n"),
+                StepStatus::Skipped => out.push_str(">\n      <skipped/>\n    </testcase>\n"),
+                StepStatus::Failed => {
+                    let _ = writeln!(
+                        out,
+                        ">\n      <failure type=\"{}\" message=\"{}\">{}</failure>\n    </testcase>",
+                        step.kind,
+                        escape(&step.message),
+                        escape(&failure_details(step))
+                    );
+                }
+            }
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+// --- HTML ---
+
+const STYLE: &str = "\
+body{font-family:-apple-system,Segoe UI,Roboto,sans-serif;margin:24px;color:#222;background:#fafafa}\
+h1{font-size:20px}h2{font-size:16px;margin:28px 0 6px}\
+.summary span{display:inline-block;margin-right:16px}\
+.pass{color:#1a7f37}.fail{color:#cf222e}.skip{color:#8c8c8c}\
+table{border-collapse:collapse;width:100%;background:#fff}\
+th,td{border:1px solid #ddd;padding:4px 8px;text-align:left;vertical-align:top;font-size:13px}\
+th{background:#f0f0f0}td.num{text-align:right;white-space:nowrap}\
+tr.failed td{background:#fff1f0}\
+details{margin:4px 0}pre{margin:4px 0;font-size:12px;white-space:pre-wrap;word-break:break-all}";
+
+pub fn html(reports: &[ScenarioReport]) -> String {
+    let failed = reports.iter().filter(|r| !r.passed).count();
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>DNP3 scenario report</title><style>{}</style></head><body>\n",
+        STYLE
+    );
+    let _ = writeln!(
+        out,
+        "<h1>DNP3 scenario report</h1>\n<div class=\"summary\"><span class=\"{}\"><b>{}</b></span><span>{} scenario(s), {} failed</span><span class=\"pass\">{} passed</span><span class=\"fail\">{} failed</span><span class=\"skip\">{} skipped</span><span>{} s</span></div>",
+        if failed == 0 { "pass" } else { "fail" },
+        if failed == 0 { "PASS" } else { "FAIL" },
+        reports.len(),
+        failed,
+        count(reports, StepStatus::Passed),
+        count(reports, StepStatus::Failed),
+        count(reports, StepStatus::Skipped),
+        seconds(total_ms(reports))
+    );
+    for report in reports {
+        let _ = writeln!(
+            out,
+            "<h2><span class=\"{}\">{}</span> {}</h2>",
+            if report.passed { "pass" } else { "fail" },
+            if report.passed { "✔" } else { "✘" },
+            escape(&report.name)
+        );
+        if let Some(description) = &report.description {
+            let _ = writeln!(out, "<p>{}</p>", escape(description));
+        }
+        let _ = writeln!(
+            out,
+            "<p>Session <code>{}</code>, started {}, {} ms</p>",
+            escape(&report.session_id),
+            report.started.format("%Y-%m-%d %H:%M:%S UTC"),
+            report.duration_ms
+        );
+        out.push_str("<table><tr><th>#</th><th>Step</th><th>Status</th><th>Time (ms)</th><th>Result</th></tr>\n");
+        for step in &report.steps {
+            let (class, label) = match step.status {
+                StepStatus::Passed => ("pass", "passed"),
+                StepStatus::Failed => ("fail", "failed"),
+                StepStatus::Skipped => ("skip", "skipped"),
+            };
+            let _ = write!(
+                out,
+                "<tr class=\"{}\"><td class=\"num\">{}</td><td>{}</td><td class=\"{}\">{}</td><td class=\"num\">{}</td><td>{}",
+                label,
+                step.index,
+                escape(&step.description),
+                class,
+                label,
+                step.duration_ms,
+                escape(&step.message)
+            );
+            if !step.logs.is_empty() {
+                let lines: Vec<String> = step.logs.iter().map(|l| escape(&log_line(l))).collect();
+                let _ = write!(
+                    out,
+                    "<details><summary>{} log(s)</summary><pre>{}</pre></details>",
+                    step.logs.len(),
+                    lines.join("\n")
+                );
+            }
+            if !step.frames.is_empty() {
+                let lines: Vec<String> = step.frames.iter().map(|f| escape(&frame_line(f))).collect();
+                let _ = write!(
+                    out,
+                    "<details><summary>{} frame(s)</summary><pre>{}</pre></details>",
+                    step.frames.len(),
+                    lines.join("\n")
+                );
+            }
+            out.push_str("</td></tr>\n");
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
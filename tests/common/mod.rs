@@ -0,0 +1,170 @@
+// Shared harness for the integration tests: a Dnp3Service outstation on an
+// ephemeral loopback port and a Dnp3Service master connected to it, the
+// same way the app wires two sessions.
+
+#![allow(dead_code)] // Each test binary uses a different part
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dnp3::master::{Classes, EventClasses};
+use dnp3_tester::dnp3_service::{Dnp3Service, LogStore};
+use dnp3_tester::models::*;
+pub use dnp3_tester::self_test::same_value;
+use dnp3_tester::soe::SoeRecord;
+
+pub const OUTSTATION_ADDRESS: u16 = 10;
+pub const MASTER_ADDRESS: u16 = 1;
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+/// One outstation simulation cycle plus margin
+pub const SIMULATION_CYCLE: Duration = Duration::from_millis(2500);
+
+fn points(indexes: &[u16], prefix: &str) -> Option<Vec<PointConfig>> {
+    Some(
+        indexes
+            .iter()
+            .map(|&index| PointConfig {
+                index,
+                name: format!("{} {}", prefix, index),
+                description: None,
+                unit: None,
+                scale: None,
+            })
+            .collect(),
+    )
+}
+
+/// Small table with every point type; indexes are not contiguous on purpose
+pub fn device_config() -> DeviceConfiguration {
+    DeviceConfiguration {
+        name: Some("integration".to_string()),
+        binary_inputs: points(&[0, 1, 5], "BI"),
+        binary_outputs: points(&[0, 3], "BO"),
+        analog_inputs: points(&[0, 2], "AI"),
+        analog_outputs: points(&[0, 1], "AO"),
+        counters: points(&[0], "CNT"),
+    }
+}
+
+pub fn outstation_config(port: u16) -> Configuration {
+    Configuration {
+        role: DeviceRole::Outstation,
+        connection_type: ConnectionType::TcpServer,
+        ip_address: "127.0.0.1".to_string(),
+        port,
+        local_address: OUTSTATION_ADDRESS,
+        remote_address: MASTER_ADDRESS,
+        ..Configuration::default()
+    }
+}
+
+pub fn master_config(port: u16) -> Configuration {
+    Configuration {
+        role: DeviceRole::Master,
+        connection_type: ConnectionType::TcpClient,
+        ip_address: "127.0.0.1".to_string(),
+        port,
+        local_address: MASTER_ADDRESS,
+        remote_address: OUTSTATION_ADDRESS,
+        ..Configuration::default()
+    }
+}
+
+pub fn integrity() -> Classes {
+    Classes::new(true, EventClasses::none())
+}
+
+pub fn events() -> Classes {
+    Classes::new(false, EventClasses::all())
+}
+
+pub struct Pair {
+    pub outstation: Arc<Dnp3Service>,
+    pub master: Arc<Dnp3Service>,
+    pub port: u16,
+}
+
+impl Pair {
+    /// Outstation with `config` on 127.0.0.1:0, and a connected master
+    pub async fn start(config: DeviceConfiguration) -> Pair {
+        let log_store = Arc::new(LogStore::new());
+        let outstation = Arc::new(Dnp3Service::new("outstation", log_store.clone()));
+        let master = Arc::new(Dnp3Service::new("master", log_store));
+        outstation.update_config(config.clone()).await;
+        master.update_config(config).await;
+
+        outstation.start_outstation(&outstation_config(0)).await.expect("outstation starts");
+        let port = outstation.listen_addr().await.expect("outstation listens").port();
+        master.start_master(&master_config(port)).await.expect("master starts");
+        master.wait_connected(TIMEOUT).await.expect("master connects");
+
+        let pair = Pair { outstation, master, port };
+        pair.wait_online().await;
+        pair
+    }
+
+    pub async fn wait_online(&self) {
+        self.outstation.wait_points_online(TIMEOUT).await.expect("outstation points online");
+    }
+
+    pub async fn stop(&self) {
+        self.master.disconnect().await;
+        self.outstation.disconnect().await;
+    }
+
+    pub async fn outstation_value(&self, point_type: DataPointType, index: u16) -> Option<f64> {
+        self.outstation
+            .get_data()
+            .await
+            .into_iter()
+            .find(|p| p.point_type == point_type && p.index == index)
+            .map(|p| p.value)
+    }
+
+    pub fn received(&self, point_type: DataPointType, index: u16) -> Option<SoeRecord> {
+        self.master.soe.lock().unwrap().latest(point_type, index)
+    }
+
+    /// Read `classes` until every point the outstation has (per `include`)
+    /// arrived ONLINE with the outstation's value; the simulation may change
+    /// a value between the read and the comparison.
+    pub async fn assert_round_trip(&self, classes: Classes, include: impl Fn(DataPointType) -> bool) {
+        let mut mismatches = Vec::new();
+        for _ in 0..3 {
+            self.master.read_classes(classes).await.expect("read succeeds");
+            mismatches = self
+                .outstation
+                .get_data()
+                .await
+                .into_iter()
+                .filter(|p| include(p.point_type))
+                .filter_map(|p| match self.received(p.point_type, p.index) {
+                    None => Some(format!("{:?}[{}] not received", p.point_type, p.index)),
+                    Some(r) if r.flags & 0x01 == 0 => Some(format!("{:?}[{}] flags {:#04x}", p.point_type, p.index, r.flags)),
+                    Some(r) if !same_value(p.point_type, p.value, r.value) => {
+                        Some(format!("{:?}[{}] received {}, sent {}", p.point_type, p.index, r.value, p.value))
+                    }
+                    Some(_) => None,
+                })
+                .collect();
+            if mismatches.is_empty() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        panic!("points did not round-trip: {:?}", mismatches);
+    }
+}
+
+/// Poll `condition` every 50 ms; panics after TIMEOUT
+pub async fn wait_until<F, Fut>(what: &str, condition: F)
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    let deadline = Instant::now() + TIMEOUT;
+    while !condition().await {
+        assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use dnp3_tester::dnp3_service::{Dnp3Service, LogStore};
+use dnp3_tester::models::*;
+
+fn count(points: &[DataPoint], point_type: DataPointType) -> usize {
+    points.iter().filter(|p| p.point_type == point_type).count()
+}
+
+#[tokio::test]
+async fn update_config_replaces_the_point_table() {
+    let service = Dnp3Service::new("config", Arc::new(LogStore::new()));
+    service.update_config(device_config()).await;
+    let points = service.get_data().await;
+    assert_eq!(points.len(), 10);
+    assert_eq!(count(&points, DataPointType::BinaryInput), 3);
+    assert_eq!(count(&points, DataPointType::BinaryOutput), 2);
+    assert_eq!(count(&points, DataPointType::AnalogInput), 2);
+    assert_eq!(count(&points, DataPointType::AnalogOutput), 2);
+    assert_eq!(count(&points, DataPointType::Counter), 1);
+    assert!(points.iter().all(|p| p.quality == DataQuality::Offline && p.value == 0.0));
+    assert!(points.iter().any(|p| p.point_type == DataPointType::BinaryInput && p.index == 5 && p.name == "BI 5"));
+
+    let smaller = DeviceConfiguration {
+        analog_inputs: device_config().analog_inputs,
+        ..DeviceConfiguration::default()
+    };
+    service.update_config(smaller).await;
+    let points = service.get_data().await;
+    assert_eq!(points.len(), 2);
+    assert_eq!(count(&points, DataPointType::AnalogInput), 2);
+}
+
+#[tokio::test]
+async fn add_and_clear_datapoints() {
+    let service = Dnp3Service::new("config", Arc::new(LogStore::new()));
+    service.update_config(device_config()).await;
+
+    service.add_datapoint(DataPointType::Counter, 9, "CNT 9".to_string()).await.unwrap();
+    let error = service.add_datapoint(DataPointType::Counter, 9, "again".to_string()).await.unwrap_err();
+    assert!(error.contains("already exists"), "{}", error);
+    // Same index, other type
+    service.add_datapoint(DataPointType::AnalogInput, 9, "AI 9".to_string()).await.unwrap();
+    assert_eq!(service.get_data().await.len(), 12);
+
+    service.clear_datapoints().await;
+    assert!(service.get_data().await.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restarted_outstation_serves_the_new_table() {
+    let pair = Pair::start(device_config()).await;
+    pair.master.read_classes(integrity()).await.unwrap();
+    assert!(pair.received(DataPointType::AnalogInput, 7).is_none());
+
+    let mut config = device_config();
+    config.analog_inputs.as_mut().unwrap().push(PointConfig {
+        index: 7,
+        name: "AI 7".to_string(),
+        description: None,
+        unit: None,
+        scale: None,
+    });
+    config.counters = None;
+    pair.outstation.update_config(config.clone()).await;
+    pair.outstation.start_outstation(&outstation_config(pair.port)).await.unwrap();
+    pair.master.update_config(config).await;
+
+    // The master reconnects on its own; its startup integrity poll fetches the new point
+    wait_until("new point at the master", || async {
+        let _ = pair.master.read_classes(integrity()).await;
+        pair.received(DataPointType::AnalogInput, 7).is_some()
+    })
+    .await;
+    pair.wait_online().await;
+    pair.assert_round_trip(integrity(), |_| true).await;
+    pair.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn config_update_clears_history() {
+    let pair = Pair::start(device_config()).await;
+    pair.master.read_classes(integrity()).await.unwrap();
+    let before = pair.master.history.lock().unwrap().query(DataPointType::AnalogInput, 0, None, None, None);
+    assert!(!before.is_empty());
+
+    pair.master.update_config(device_config()).await;
+    let after = pair.master.history.lock().unwrap().query(DataPointType::AnalogInput, 0, None, None, None);
+    assert!(after.is_empty());
+    pair.stop().await;
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::*;
+use dnp3_tester::models::*;
+
+/// Every op_mode the UI and the REST API accept; unknown ones fall back to Direct
+const OP_MODES: [&str; 6] = ["Direct", "DirectNoAck", "Select", "Operate", "SBO", "Unknown"];
+
+async fn operate(pair: &Pair, point_type: DataPointType, index: u16, value: f64, op_mode: &str, command: &str) {
+    let message = pair
+        .master
+        .execute_control(point_type, index, value, op_mode.to_string(), command.to_string())
+        .await
+        .unwrap_or_else(|e| panic!("{} {:?}[{}] = {}: {}", op_mode, point_type, index, value, e));
+    assert!(message.contains(op_mode), "{}", message);
+
+    wait_until("outstation to apply the control", || async {
+        pair.outstation_value(point_type, index).await == Some(value)
+    })
+    .await;
+    // Select skips the verification read
+    pair.master.read_classes(integrity()).await.unwrap();
+    let received = pair.received(point_type, index).expect("output status received");
+    assert!(same_value(point_type, value, received.value), "{} {:?}[{}]: received {}", op_mode, point_type, index, received.value);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn binary_output_latch_in_every_mode() {
+    let pair = Pair::start(device_config()).await;
+    for op_mode in OP_MODES {
+        for value in [1.0, 0.0] {
+            operate(&pair, DataPointType::BinaryOutput, 3, value, op_mode, "Latch").await;
+        }
+    }
+    pair.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn binary_output_pulse_in_every_mode() {
+    let pair = Pair::start(device_config()).await;
+    for op_mode in OP_MODES {
+        for value in [1.0, 0.0] {
+            operate(&pair, DataPointType::BinaryOutput, 0, value, op_mode, "Pulse").await;
+        }
+    }
+    pair.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn analog_output_setpoint_in_every_mode() {
+    let pair = Pair::start(device_config()).await;
+    for (n, op_mode) in OP_MODES.into_iter().enumerate() {
+        operate(&pair, DataPointType::AnalogOutput, 1, 100.0 + n as f64, op_mode, "Latch").await;
+        operate(&pair, DataPointType::AnalogOutput, 0, -(n as f64), op_mode, "Latch").await;
+    }
+    pair.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn controls_are_counted() {
+    let pair = Pair::start(device_config()).await;
+    operate(&pair, DataPointType::BinaryOutput, 3, 1.0, "SBO", "Latch").await;
+    operate(&pair, DataPointType::AnalogOutput, 0, 42.0, "Direct", "Latch").await;
+    assert_eq!(pair.master.get_stats().controls_ok, 2);
+    pair.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn input_points_cannot_be_controlled() {
+    let pair = Pair::start(device_config()).await;
+    let error = pair
+        .master
+        .execute_control(DataPointType::BinaryInput, 0, 1.0, "Direct".to_string(), "Latch".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(error, "Unsupported control point type");
+    pair.stop().await;
+}
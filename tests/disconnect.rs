@@ -0,0 +1,66 @@
+mod common;
+
+use common::*;
+use dnp3_tester::models::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn master_disconnect_stops_requests() {
+    let pair = Pair::start(device_config()).await;
+    pair.master.disconnect().await;
+
+    assert!(!*pair.master.connected.read().await);
+    assert_eq!(pair.master.status.snapshot().state, "shutdown");
+    assert_eq!(pair.master.read_classes(integrity()).await.unwrap_err(), "Master not connected");
+    let error = pair
+        .master
+        .execute_control(DataPointType::BinaryOutput, 3, 1.0, "Direct".to_string(), "Latch".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(error, "Master not connected");
+
+    // The outstation sees the master go away and keeps listening
+    wait_until("outstation to wait for a master", || async {
+        pair.outstation.status.snapshot().state != "connected"
+    })
+    .await;
+    assert!(pair.outstation.listen_addr().await.is_some());
+    pair.outstation.disconnect().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn outstation_disconnect_is_seen_by_the_master() {
+    let pair = Pair::start(device_config()).await;
+    pair.outstation.disconnect().await;
+
+    assert!(pair.outstation.listen_addr().await.is_none());
+    wait_until("master to lose the connection", || async {
+        pair.master.status.snapshot().state != "connected"
+    })
+    .await;
+    assert!(pair.master.read_classes(integrity()).await.is_err());
+    assert!(pair.master.status.snapshot().disconnect_count >= 1);
+    pair.master.disconnect().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn outstation_port_is_released_on_disconnect() {
+    let pair = Pair::start(device_config()).await;
+    pair.stop().await;
+    wait_until("port to be free", || async { std::net::TcpListener::bind(("127.0.0.1", pair.port)).is_ok() }).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn master_reconnects_when_the_outstation_comes_back() {
+    let pair = Pair::start(device_config()).await;
+    pair.outstation.disconnect().await;
+    wait_until("master to lose the connection", || async {
+        pair.master.status.snapshot().state != "connected"
+    })
+    .await;
+
+    pair.outstation.start_outstation(&outstation_config(pair.port)).await.unwrap();
+    pair.master.wait_connected(TIMEOUT).await.unwrap();
+    wait_until("reads to succeed again", || async { pair.master.read_classes(integrity()).await.is_ok() }).await;
+    assert!(pair.master.status.snapshot().reconnect_count >= 1);
+    pair.stop().await;
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use dnp3_tester::dnp3_service::{Dnp3Service, LogStore};
+use dnp3_tester::models::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn integrity_poll_returns_every_point() {
+    let pair = Pair::start(device_config()).await;
+    pair.assert_round_trip(integrity(), |_| true).await;
+
+    let records = pair.master.soe.lock().unwrap().list(None, None);
+    assert!(records.iter().any(|r| !r.is_event));
+    pair.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn event_poll_reports_changed_inputs() {
+    let pair = Pair::start(device_config()).await;
+    pair.master.read_classes(integrity()).await.unwrap();
+    let last_id = pair.master.soe.lock().unwrap().last_id();
+
+    // Analog inputs change every simulation cycle
+    tokio::time::sleep(SIMULATION_CYCLE).await;
+    pair.assert_round_trip(events(), |t| t != DataPointType::BinaryOutput && t != DataPointType::AnalogOutput)
+        .await;
+
+    let received = pair.master.soe.lock().unwrap().list(Some(last_id), None);
+    assert!(
+        received.iter().any(|r| r.is_event && r.point_type == DataPointType::AnalogInput),
+        "no analog input events in {:?}",
+        received
+    );
+    pair.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn master_data_points_follow_the_outstation() {
+    let pair = Pair::start(device_config()).await;
+    pair.master.read_classes(integrity()).await.unwrap();
+
+    let master_points = pair.master.get_data().await;
+    assert_eq!(master_points.len(), pair.outstation.get_data().await.len());
+    for point in &master_points {
+        assert_eq!(point.quality, DataQuality::Online, "{:?}[{}]", point.point_type, point.index);
+    }
+    pair.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn read_without_a_master_fails() {
+    let service = Dnp3Service::new("idle", Arc::new(LogStore::new()));
+    let error = service.read_classes(integrity()).await.unwrap_err();
+    assert_eq!(error, "Master not connected");
+}